@@ -41,12 +41,9 @@ extern crate rustc_serialize;
 extern crate docopt;
 extern crate rust_sodium;
 extern crate routing;
-extern crate lru_time_cache;
 extern crate term;
 #[macro_use]
 extern crate unwrap;
-#[macro_use]
-extern crate serde_derive;
 
 mod utils;
 
@@ -45,11 +45,8 @@ extern crate unwrap;
 extern crate docopt;
 extern crate rustc_serialize;
 extern crate rust_sodium;
-#[macro_use]
-extern crate serde_derive;
 
 extern crate routing;
-extern crate lru_time_cache;
 
 mod utils;
 
@@ -22,6 +22,7 @@ use messages::RoutingMessage;
 use sha3;
 use std::collections::BTreeMap;
 use std::fmt;
+use std::mem;
 use std::time::Duration;
 use tiny_keccak::sha3_256;
 
@@ -101,6 +102,14 @@ impl AckManager {
 
         Some((unacked_msg, timed_out_ack))
     }
+
+    /// Removes and returns all pending messages, e.g. to resend them via a different proxy.
+    pub fn take_pending(&mut self) -> Vec<UnacknowledgedMessage> {
+        mem::replace(&mut self.pending, BTreeMap::new())
+            .into_iter()
+            .map(|(_, unacked_msg)| unacked_msg)
+            .collect()
+    }
 }
 
 #[cfg(feature = "use-mock-crust")]
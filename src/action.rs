@@ -23,6 +23,8 @@ use messages::DirectMessage;
 use routing_table::Authority;
 use std::fmt::{self, Debug, Formatter};
 use std::sync::mpsc::Sender;
+use types::MessageId;
+use utils;
 use xor_name::XorName;
 
 /// An Action initiates a message flow < A | B > where we are (a part of) A.
@@ -48,6 +50,15 @@ pub enum Action {
         result_tx: Sender<Result<(), InterfaceError>>,
     },
     Name { result_tx: Sender<XorName> },
+    SectionVote {
+        value: Vec<u8>,
+        result_tx: Sender<Result<(), InterfaceError>>,
+    },
+    GetSectionInfo {
+        name: XorName,
+        message_id: MessageId,
+        result_tx: Sender<Result<(), InterfaceError>>,
+    },
     Timeout(u64),
     ResourceProofResult(PeerId, Vec<DirectMessage>),
     Terminate,
@@ -72,6 +83,21 @@ impl Debug for Action {
                        dst)
             }
             Action::Name { .. } => write!(formatter, "Action::Name"),
+            Action::SectionVote { ref value, .. } => {
+                write!(formatter,
+                       "Action::SectionVote {{ {}, result_tx }}",
+                       utils::format_binary_array(value))
+            }
+            Action::GetSectionInfo {
+                ref name,
+                ref message_id,
+                ..
+            } => {
+                write!(formatter,
+                       "Action::GetSectionInfo {{ {:?}, {:?}, result_tx }}",
+                       name,
+                       message_id)
+            }
             Action::Timeout(token) => write!(formatter, "Action::Timeout({})", token),
             Action::ResourceProofResult(peer_id, _) => {
                 write!(formatter, "Action::ResourceProofResult({:?}, ...)", peer_id)
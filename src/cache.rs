@@ -15,7 +15,24 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software.
 
+use data::{Data, DataIdentifier};
+#[cfg(feature="use-mock-crust")]
+use fake_clock::FakeClock as Instant;
+use lru_time_cache::LruCache as TimedCache;
+use maidsafe_utilities::serialisation;
 use messages::{Request, Response};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+#[cfg(not(feature="use-mock-crust"))]
+use std::time::Instant;
+
+/// Default size budget of an `LruCache`, in bytes.
+const DEFAULT_CACHE_CAPACITY_BYTES: usize = 16 * 1024 * 1024;
+/// Default time (in seconds) for which an `LruCache` entry is served after it was stored.
+const DEFAULT_CACHE_TIME_TO_LIVE_SECS: u64 = 10 * 60;
+/// Maximum number of structured data items whose lowest servable version an `LruCache` tracks.
+const MAX_TRACKED_VERSIONS: usize = 100_000;
 
 /// A cache that stores `Response`s keyed by `Requests`. Should be implemented
 /// by layers above routing.
@@ -25,6 +42,42 @@ pub trait Cache: Send {
 
     /// Cache the given response.
     fn put(&self, response: Response);
+
+    /// Whether this cache handles `StructuredData`. If so, it is also passed `Get` requests and
+    /// `GetSuccess` responses for structured data, and `invalidate` is called whenever a relayed
+    /// response shows that the data has been mutated.
+    ///
+    /// The default implementation returns `false`: only responses for which
+    /// `Response::is_cacheable` returns `true` are passed to the cache.
+    fn caches_structured_data(&self) -> bool {
+        false
+    }
+
+    /// Called when a relayed response shows that the identified data has been mutated, e.g. a
+    /// `PostSuccess`, `DeleteSuccess` or `AppendSuccess`, if `caches_structured_data` returns
+    /// `true`. Any cached copy is outdated and must not be served anymore.
+    ///
+    /// The default implementation does nothing, so a cache which handles structured data needs to
+    /// override it.
+    fn invalidate(&self, _data_id: &DataIdentifier) {}
+}
+
+/// Returns whether `request` is a `Get` for structured data, which is only passed to caches that
+/// opt in via `Cache::caches_structured_data`.
+pub fn is_structured_data_get(request: &Request) -> bool {
+    match *request {
+        Request::Get(DataIdentifier::Structured(..), _) => true,
+        _ => false,
+    }
+}
+
+/// Returns whether `response` is a `GetSuccess` with structured data, which is only passed to
+/// caches that opt in via `Cache::caches_structured_data`.
+pub fn is_structured_data_response(response: &Response) -> bool {
+    match *response {
+        Response::GetSuccess(Data::Structured(..), _) => true,
+        _ => false,
+    }
 }
 
 /// A no-op implementation of the `Cache` trait. Throws everything away on put
@@ -37,3 +90,352 @@ impl Cache for NullCache {
     }
     fn put(&self, _: Response) {}
 }
+
+/// A cache of responses to `Get` requests for immutable data, bounded by the total serialised
+/// size of the cached data.
+///
+/// Entries expire after a fixed time to live; if the cache is full, the least recently used ones
+/// are evicted first. Only `GetSuccess` responses with immutable or structured data are stored:
+/// custom responses don't identify the request they answer, so they are ignored.
+///
+/// Invalidated entries are evicted. For structured data, the cache also remembers the latest
+/// version it has seen, and never stores or serves an older one while it remembers it.
+pub struct LruCache {
+    inner: RefCell<LruCacheInner>,
+}
+
+struct LruCacheInner {
+    capacity_bytes: usize,
+    time_to_live: Duration,
+    size_bytes: usize,
+    entries: HashMap<DataIdentifier, CacheEntry>,
+    /// Identifiers of the cached data, by the time of their last use (least recent first).
+    usage: BTreeMap<u64, DataIdentifier>,
+    next_use: u64,
+    /// The lowest version of each structured data item we may still cache.
+    min_versions: TimedCache<DataIdentifier, u64>,
+}
+
+struct CacheEntry {
+    data: Data,
+    size_bytes: usize,
+    stored_at: Instant,
+    last_use: u64,
+}
+
+impl LruCache {
+    /// Creates a cache holding at most `capacity_bytes` of serialised data, each entry for at
+    /// most `time_to_live`.
+    pub fn new(capacity_bytes: usize, time_to_live: Duration) -> Self {
+        LruCache {
+            inner: RefCell::new(LruCacheInner {
+                                    capacity_bytes: capacity_bytes,
+                                    time_to_live: time_to_live,
+                                    size_bytes: 0,
+                                    entries: HashMap::new(),
+                                    usage: BTreeMap::new(),
+                                    next_use: 0,
+                                    min_versions: TimedCache::with_expiry_duration_and_capacity(
+                                        time_to_live, MAX_TRACKED_VERSIONS),
+                                }),
+        }
+    }
+
+    /// Returns the number of cached entries.
+    pub fn len(&self) -> usize {
+        self.inner.borrow().entries.len()
+    }
+
+    /// Returns `true` if nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.inner.borrow().entries.is_empty()
+    }
+
+    /// Returns the total serialised size of the cached data, in bytes.
+    pub fn size_bytes(&self) -> usize {
+        self.inner.borrow().size_bytes
+    }
+}
+
+impl Default for LruCache {
+    /// Creates a cache of 16 MiB, whose entries expire after 10 minutes.
+    fn default() -> LruCache {
+        LruCache::new(DEFAULT_CACHE_CAPACITY_BYTES,
+                      Duration::from_secs(DEFAULT_CACHE_TIME_TO_LIVE_SECS))
+    }
+}
+
+impl Cache for LruCache {
+    fn get(&self, request: &Request) -> Option<Response> {
+        if !request.is_cacheable() && !is_structured_data_get(request) {
+            return None;
+        }
+        if let Request::Get(identifier, message_id) = *request {
+            match self.inner.borrow_mut().get(&identifier) {
+                Some(ref data) if data.is_expired() => None,
+                data => data.map(|data| Response::GetSuccess(data, message_id)),
+            }
+        } else {
+            None
+        }
+    }
+
+    fn put(&self, response: Response) {
+        if !response.is_cacheable() && !is_structured_data_response(&response) {
+            return;
+        }
+        if let Response::GetSuccess(data, _) = response {
+            self.inner.borrow_mut().insert(data);
+        }
+    }
+
+    fn caches_structured_data(&self) -> bool {
+        true
+    }
+
+    fn invalidate(&self, data_id: &DataIdentifier) {
+        self.inner.borrow_mut().invalidate(data_id);
+    }
+}
+
+impl LruCacheInner {
+    fn get(&mut self, identifier: &DataIdentifier) -> Option<Data> {
+        let expired = match self.entries.get(identifier) {
+            Some(entry) => entry.stored_at.elapsed() > self.time_to_live,
+            None => return None,
+        };
+        if expired {
+            self.remove(identifier);
+            return None;
+        }
+
+        let use_count = self.next_use();
+        let entry = match self.entries.get_mut(identifier) {
+            Some(entry) => entry,
+            None => return None,
+        };
+        let _ = self.usage.remove(&entry.last_use);
+        let _ = self.usage.insert(use_count, *identifier);
+        entry.last_use = use_count;
+        Some(entry.data.clone())
+    }
+
+    fn insert(&mut self, data: Data) {
+        let size_bytes = match serialisation::serialise(&data) {
+            Ok(serialised) => serialised.len(),
+            Err(error) => {
+                error!("Failed to serialise {:?}: {:?}", data.identifier(), error);
+                return;
+            }
+        };
+        if size_bytes > self.capacity_bytes {
+            return;
+        }
+
+        let identifier = data.identifier();
+        if let Data::Structured(ref structured_data) = data {
+            let version = structured_data.get_version();
+            if version < self.min_versions.get(&identifier).cloned().unwrap_or(0) {
+                return;
+            }
+            let _ = self.min_versions.insert(identifier, version);
+        }
+
+        self.remove(&identifier);
+        while self.size_bytes + size_bytes > self.capacity_bytes {
+            let oldest = match self.usage.values().next() {
+                Some(oldest) => *oldest,
+                None => break,
+            };
+            self.remove(&oldest);
+        }
+
+        let use_count = self.next_use();
+        let _ = self.usage.insert(use_count, identifier);
+        self.size_bytes += size_bytes;
+        let _ = self.entries
+            .insert(identifier,
+                    CacheEntry {
+                        data: data,
+                        size_bytes: size_bytes,
+                        stored_at: Instant::now(),
+                        last_use: use_count,
+                    });
+    }
+
+    fn invalidate(&mut self, identifier: &DataIdentifier) {
+        self.remove(identifier);
+        // The version we have seen last is outdated now, so only accept newer ones.
+        if let Some(version) = self.min_versions.get(identifier).cloned() {
+            let _ = self.min_versions.insert(*identifier, version + 1);
+        }
+    }
+
+    fn remove(&mut self, identifier: &DataIdentifier) {
+        if let Some(entry) = self.entries.remove(identifier) {
+            let _ = self.usage.remove(&entry.last_use);
+            self.size_bytes -= entry.size_bytes;
+        }
+    }
+
+    fn next_use(&mut self) -> u64 {
+        let use_count = self.next_use;
+        self.next_use += 1;
+        use_count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::{ImmutableData, StructuredData};
+    use rand;
+    use std::collections::BTreeSet;
+    use types::MessageId;
+    use xor_name::XorName;
+
+    #[cfg(feature = "use-mock-crust")]
+    fn sleep(duration: Duration) {
+        use fake_clock::FakeClock;
+        FakeClock::advance_time(duration.as_secs() * 1000 +
+                                u64::from(duration.subsec_nanos() / 1_000_000));
+    }
+
+    #[cfg(not(feature = "use-mock-crust"))]
+    fn sleep(duration: Duration) {
+        use std::thread;
+        thread::sleep(duration);
+    }
+
+    fn immutable_data(value: u8, len: usize) -> Data {
+        Data::Immutable(ImmutableData::new(vec![value; len]))
+    }
+
+    fn structured_data(name: XorName, version: u64) -> Data {
+        let structured_data = StructuredData::new(10000, name, version, vec![], BTreeSet::new());
+        Data::Structured(unwrap!(structured_data))
+    }
+
+    fn serialised_size(data: &Data) -> usize {
+        unwrap!(serialisation::serialise(data)).len()
+    }
+
+    fn get(cache: &LruCache, data: &Data) -> Option<Data> {
+        match cache.get(&Request::Get(data.identifier(), MessageId::new())) {
+            Some(Response::GetSuccess(data, _)) => Some(data),
+            Some(response) => panic!("Unexpected response {:?}", response),
+            None => None,
+        }
+    }
+
+    #[test]
+    fn get_and_put() {
+        let cache = LruCache::default();
+        let data = immutable_data(0, 100);
+        assert_eq!(get(&cache, &data), None);
+
+        let message_id = MessageId::new();
+        cache.put(Response::GetSuccess(data.clone(), MessageId::new()));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.size_bytes(), serialised_size(&data));
+        match cache.get(&Request::Get(data.identifier(), message_id)) {
+            Some(Response::GetSuccess(cached, id)) => {
+                assert_eq!(cached, data);
+                assert_eq!(id, message_id);
+            }
+            response => panic!("Unexpected response {:?}", response),
+        }
+
+        // Responses which are not cacheable are ignored.
+        cache.put(Response::PutSuccess(data.identifier(), MessageId::new()));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn least_recently_used_is_evicted() {
+        let data = (0..3).map(|i| immutable_data(i, 100)).collect::<Vec<_>>();
+        let cache = LruCache::new(2 * serialised_size(&data[0]),
+                                  Duration::from_secs(DEFAULT_CACHE_TIME_TO_LIVE_SECS));
+        cache.put(Response::GetSuccess(data[0].clone(), MessageId::new()));
+        cache.put(Response::GetSuccess(data[1].clone(), MessageId::new()));
+        assert!(get(&cache, &data[0]).is_some());
+
+        // `data[1]` hasn't been used since it was stored, so it makes room for `data[2]`.
+        cache.put(Response::GetSuccess(data[2].clone(), MessageId::new()));
+        assert_eq!(cache.len(), 2);
+        assert!(get(&cache, &data[0]).is_some());
+        assert!(get(&cache, &data[1]).is_none());
+        assert!(get(&cache, &data[2]).is_some());
+
+        // Data larger than the whole budget is never cached.
+        let large_data = immutable_data(3, 300);
+        cache.put(Response::GetSuccess(large_data.clone(), MessageId::new()));
+        assert!(get(&cache, &large_data).is_none());
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn entries_expire() {
+        let time_to_live = Duration::from_millis(100);
+        let cache = LruCache::new(DEFAULT_CACHE_CAPACITY_BYTES, time_to_live);
+        let data = immutable_data(0, 100);
+        cache.put(Response::GetSuccess(data.clone(), MessageId::new()));
+        assert!(get(&cache, &data).is_some());
+
+        sleep(time_to_live + Duration::from_millis(50));
+        assert!(get(&cache, &data).is_none());
+        assert!(cache.is_empty());
+        assert_eq!(cache.size_bytes(), 0);
+    }
+
+    #[test]
+    fn invalidation() {
+        let cache = LruCache::default();
+        let data = immutable_data(0, 100);
+        cache.put(Response::GetSuccess(data.clone(), MessageId::new()));
+        assert!(get(&cache, &data).is_some());
+
+        cache.invalidate(&data.identifier());
+        assert!(get(&cache, &data).is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn structured_data_is_opt_in() {
+        let data = structured_data(rand::random(), 0);
+        assert!(!Request::Get(data.identifier(), MessageId::new()).is_cacheable());
+        assert!(!Response::GetSuccess(data.clone(), MessageId::new()).is_cacheable());
+        assert!(!NullCache.caches_structured_data());
+
+        let cache = LruCache::default();
+        assert!(cache.caches_structured_data());
+        cache.put(Response::GetSuccess(data.clone(), MessageId::new()));
+        assert_eq!(get(&cache, &data), Some(data));
+    }
+
+    #[test]
+    fn structured_data_versions() {
+        let cache = LruCache::default();
+        let name = rand::random();
+        let version_0 = structured_data(name, 0);
+        let version_1 = structured_data(name, 1);
+        let version_2 = structured_data(name, 2);
+
+        cache.put(Response::GetSuccess(version_1.clone(), MessageId::new()));
+        assert_eq!(get(&cache, &version_1), Some(version_1.clone()));
+
+        // An older version is never cached once a newer one was seen.
+        cache.put(Response::GetSuccess(version_0.clone(), MessageId::new()));
+        assert_eq!(get(&cache, &version_1), Some(version_1.clone()));
+
+        // After the data was mutated, neither the cached version nor the ones seen before are
+        // served.
+        cache.invalidate(&version_1.identifier());
+        assert!(get(&cache, &version_1).is_none());
+        cache.put(Response::GetSuccess(version_1.clone(), MessageId::new()));
+        assert!(get(&cache, &version_1).is_none());
+
+        cache.put(Response::GetSuccess(version_2.clone(), MessageId::new()));
+        assert_eq!(get(&cache, &version_1), Some(version_2));
+    }
+}
@@ -15,19 +15,25 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software.
 
+use TYPE_TAG_DNS_PACKET;
 use action::Action;
 use cache::NullCache;
-use data::{AppendWrapper, Data, DataIdentifier};
+use data::{AppendWrapper, Data, DataIdentifier, StructuredData};
+use dns::{self, DnsRecord};
 use error::{InterfaceError, RoutingError};
 use event::Event;
 use id::FullId;
+use key_rotation::KeyRotation;
 #[cfg(not(feature = "use-mock-crust"))]
 use maidsafe_utilities::thread::{self, Joiner};
 use messages::{CLIENT_GET_PRIORITY, DEFAULT_PRIORITY, Request};
 use outbox::{EventBox, EventBuf};
+use relocation::HashingRelocation;
 use routing_table::Authority;
 #[cfg(not(feature = "use-mock-crust"))]
 use rust_sodium;
+use rust_sodium::crypto::sign::{PublicKey, SecretKey};
+use session_keys::SessionKeys;
 use state_machine::{State, StateMachine};
 use states::{Bootstrapping, BootstrappingTargetState};
 #[cfg(feature = "use-mock-crust")]
@@ -43,7 +49,9 @@ use xor_name::XorName;
 /// client.
 ///
 /// A client is connected to the network via one or more nodes. Messages are never routed via a
-/// client, and a client cannot be part of a section authority.
+/// client, and a client cannot be part of a section authority. Requests are sent via a primary
+/// proxy node; if it disconnects, another connected proxy takes over and unacknowledged requests
+/// are resent through it. The connections to proxy nodes are encrypted if they support it.
 pub struct Client {
     interface_result_tx: Sender<Result<(), InterfaceError>>,
     interface_result_rx: Receiver<Result<(), InterfaceError>>,
@@ -70,8 +78,34 @@ impl Client {
     /// Keys will be exchanged with the `ClientAuthority` so that communication with the network is
     /// cryptographically secure and uses section consensus. The restriction for the client name
     /// exists to ensure that the client cannot choose its `ClientAuthority`.
+    ///
+    /// To keep the same `ClientAuthority` across restarts, persist the keys with `FullId::save_to`
+    /// and pass them in again after loading them with `FullId::load_from`.
     #[cfg(not(feature = "use-mock-crust"))]
     pub fn new(event_sender: Sender<Event>, keys: Option<FullId>) -> Result<Client, RoutingError> {
+        Self::start(event_sender, keys, false)
+    }
+
+    /// Create a new `Client` which doesn't rely on its proxy node to check responses from
+    /// `ClientManager`, `NaeManager` and `NodeManager` authorities.
+    ///
+    /// Before handling such a response, the client obtains the section's members with a
+    /// `GetSectionInfo` request sent via each of its proxies, and checks that a quorum of the
+    /// group authority has signed the response. The members are only trusted if at least two
+    /// proxies relayed the same list, confirmed by a quorum of the section. Responses which fail
+    /// the check are raised as `Event::ResponseVerificationFailed` instead of `Event::Response`.
+    #[cfg(not(feature = "use-mock-crust"))]
+    pub fn with_response_verification(event_sender: Sender<Event>,
+                                      keys: Option<FullId>)
+                                      -> Result<Client, RoutingError> {
+        Self::start(event_sender, keys, true)
+    }
+
+    #[cfg(not(feature = "use-mock-crust"))]
+    fn start(event_sender: Sender<Event>,
+             keys: Option<FullId>,
+             verify_responses: bool)
+             -> Result<Client, RoutingError> {
         // TODO - replace this hard-coded value
         let min_section_size = 8;
         rust_sodium::init(); // enable shared global (i.e. safe to multithread now)
@@ -82,8 +116,10 @@ impl Client {
         let raii_joiner = thread::named("Client thread", move || {
             // start the handler for routing with a restriction to become a full node
             let mut event_buffer = EventBuf::new();
-            let (action_sender, mut machine) =
-                Self::make_state_machine(keys, min_section_size, &mut event_buffer);
+            let (action_sender, mut machine) = Self::make_state_machine(keys,
+                                                                        min_section_size,
+                                                                        verify_responses,
+                                                                        &mut event_buffer);
 
             for ev in event_buffer.take_all() {
                 unwrap!(event_sender.send(ev));
@@ -116,14 +152,19 @@ impl Client {
 
     fn make_state_machine(keys: Option<FullId>,
                           min_section_size: usize,
+                          verify_responses: bool,
                           outbox: &mut EventBox)
                           -> (RoutingActionSender, StateMachine) {
         StateMachine::new(move |action_sender, crust_service, timer, _outbox2| {
             Bootstrapping::new(action_sender,
                                Box::new(NullCache),
-                               BootstrappingTargetState::Client,
+                               Box::new(HashingRelocation),
+                               BootstrappingTargetState::Client {
+                                   verify_responses: verify_responses,
+                               },
                                crust_service,
                                keys.unwrap_or_else(FullId::new),
+                               SessionKeys::opportunistic(),
                                min_section_size,
                                timer)
                     .map_or(State::Terminated, State::Bootstrapping)
@@ -176,6 +217,78 @@ impl Client {
         self.send_action(Request::Append(wrapper, message_id), dst, DEFAULT_PRIORITY)
     }
 
+    /// Ask for several data items managed by `dst` at once. The response contains a result for
+    /// each item.
+    pub fn send_get_batch_request(&self,
+                                  dst: Authority<XorName>,
+                                  data_ids: Vec<DataIdentifier>,
+                                  message_id: MessageId)
+                                  -> Result<(), InterfaceError> {
+        self.send_action(Request::GetBatch(data_ids, message_id),
+                         dst,
+                         CLIENT_GET_PRIORITY)
+    }
+
+    /// Add several data items to the network at once, via the `ClientManager` `dst`. The response
+    /// contains a result for each item.
+    pub fn send_put_batch_request(&self,
+                                  dst: Authority<XorName>,
+                                  data: Vec<Data>,
+                                  message_id: MessageId)
+                                  -> Result<(), InterfaceError> {
+        self.send_action(Request::PutBatch(data, message_id), dst, DEFAULT_PRIORITY)
+    }
+
+    /// Register `record` under its domain, signed with the owner's `keys`.
+    ///
+    /// The registration fails with `DataExists` if the domain has already been registered.
+    pub fn send_dns_register_request(&self,
+                                     record: &DnsRecord,
+                                     keys: &(PublicKey, SecretKey),
+                                     message_id: MessageId)
+                                     -> Result<(), RoutingError> {
+        if *record.owner() != keys.0 {
+            return Err(RoutingError::InvalidOwners);
+        }
+        let data = record.to_structured_data(0, keys)?;
+        let dst = Authority::ClientManager(self.name()?);
+        Ok(self.send_put_request(dst, Data::Structured(data), message_id)?)
+    }
+
+    /// Replace the registered record `current`, as returned when resolving its domain, with
+    /// `record`. Fails if `record` is for a different domain or `keys` don't belong to the owner
+    /// of `current`.
+    ///
+    /// If `record` has a different owner, this transfers the domain to them.
+    pub fn send_dns_update_request(&self,
+                                   current: &StructuredData,
+                                   record: &DnsRecord,
+                                   keys: &(PublicKey, SecretKey),
+                                   message_id: MessageId)
+                                   -> Result<(), RoutingError> {
+        let current_record = DnsRecord::from_structured_data(current)?;
+        if current_record.domain() != record.domain() {
+            return Err(RoutingError::InvalidDnsRecord);
+        }
+        if *current_record.owner() != keys.0 {
+            return Err(RoutingError::InvalidOwners);
+        }
+        let data = record.to_structured_data(current.get_version() + 1, keys)?;
+        current.validate_self_against_successor(&data)?;
+        let dst = Authority::NaeManager(*current.name());
+        Ok(self.send_post_request(dst, Data::Structured(data), message_id)?)
+    }
+
+    /// Look up the record for `domain`. Pass the `StructuredData` in the response to
+    /// `DnsRecord::from_structured_data` to validate it.
+    pub fn send_dns_resolve_request(&self,
+                                    domain: &str,
+                                    message_id: MessageId)
+                                    -> Result<(), RoutingError> {
+        let name = dns::dns_name(domain)?;
+        let data_id = DataIdentifier::Structured(name, TYPE_TAG_DNS_PACKET);
+        Ok(self.send_get_request(Authority::NaeManager(name), data_id, message_id)?)
+    }
 
     /// Request account information for the Client calling this function
     pub fn send_get_account_info_request(&self,
@@ -187,6 +300,55 @@ impl Client {
                          CLIENT_GET_PRIORITY)
     }
 
+    /// Request to replace this client's signing key with the new one in `rotation`, moving the
+    /// account to the `ClientManager` of the new key. This client has to use the old key.
+    ///
+    /// Once this succeeded, the client should reconnect with the new `FullId`.
+    pub fn send_rotate_key_request(&self,
+                                   rotation: KeyRotation,
+                                   message_id: MessageId)
+                                   -> Result<(), InterfaceError> {
+        let dst = Authority::ClientManager(rotation.old_name());
+        self.send_action(Request::RotateKey(rotation, message_id),
+                         dst,
+                         CLIENT_GET_PRIORITY)
+    }
+
+    /// Send an application-defined request to `dst`. If `cacheable` is `true`, nodes relaying it
+    /// can respond from their `Cache`.
+    pub fn send_custom_request(&self,
+                               dst: Authority<XorName>,
+                               tag: u64,
+                               payload: Vec<u8>,
+                               cacheable: bool,
+                               message_id: MessageId)
+                               -> Result<(), InterfaceError> {
+        let request = Request::Custom {
+            tag: tag,
+            payload: payload,
+            cacheable: cacheable,
+            id: message_id,
+        };
+        self.send_action(request, dst, DEFAULT_PRIORITY)
+    }
+
+    /// Asks the section responsible for `name` for its prefix and members. Once a response signed
+    /// by a quorum of that section arrives, it is raised as an `Event::SectionInfo` with the same
+    /// `message_id`.
+    pub fn send_section_info_request(&self,
+                                     name: XorName,
+                                     message_id: MessageId)
+                                     -> Result<(), InterfaceError> {
+        let action = Action::GetSectionInfo {
+            name: name,
+            message_id: message_id,
+            result_tx: self.interface_result_tx.clone(),
+        };
+
+        self.action_sender.send(action)?;
+        self.receive_action_result(&self.interface_result_rx)?
+    }
+
     /// Returns the name of this node.
     pub fn name(&self) -> Result<XorName, InterfaceError> {
         let (result_tx, result_rx) = channel();
@@ -226,11 +388,27 @@ impl Client {
 impl Client {
     /// Create a new `Client` for unit testing.
     pub fn new(keys: Option<FullId>, min_section_size: usize) -> Result<Client, RoutingError> {
+        Self::start(keys, min_section_size, false)
+    }
+
+    /// Create a new `Client` for unit testing, which verifies responses from group authorities.
+    pub fn with_response_verification(keys: Option<FullId>,
+                                      min_section_size: usize)
+                                      -> Result<Client, RoutingError> {
+        Self::start(keys, min_section_size, true)
+    }
+
+    fn start(keys: Option<FullId>,
+             min_section_size: usize,
+             verify_responses: bool)
+             -> Result<Client, RoutingError> {
         // start the handler for routing with a restriction to become a full node
         let mut event_buffer = EventBuf::new();
 
-        let (action_sender, machine) =
-            Self::make_state_machine(keys, min_section_size, &mut event_buffer);
+        let (action_sender, machine) = Self::make_state_machine(keys,
+                                                                min_section_size,
+                                                                verify_responses,
+                                                                &mut event_buffer);
 
         let (tx, rx) = channel();
 
@@ -15,6 +15,7 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software.
 
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::fmt::{self, Display, Formatter};
 
@@ -28,6 +29,10 @@ pub enum GetError {
     /// Network error occurring at Vault level which has no bearing on clients, e.g. serialisation
     /// failure or database failure
     NetworkOther(String),
+    /// Requested data has expired
+    DataExpired,
+    /// Requested data is not managed by the authority the request was sent to
+    WrongAuthority,
 }
 
 impl<T: Into<String>> From<T> for GetError {
@@ -44,6 +49,10 @@ impl Display for GetError {
             GetError::NetworkOther(ref error) => {
                 write!(formatter, "Error on Vault network: {}", error)
             }
+            GetError::DataExpired => write!(formatter, "Requested data has expired"),
+            GetError::WrongAuthority => {
+                write!(formatter, "Requested data is not managed by this authority")
+            }
         }
     }
 }
@@ -54,6 +63,8 @@ impl Error for GetError {
             GetError::NoSuchAccount => "No such account",
             GetError::NoSuchData => "No such data",
             GetError::NetworkOther(ref error) => error,
+            GetError::DataExpired => "Data expired",
+            GetError::WrongAuthority => "Wrong authority",
         }
     }
 }
@@ -90,6 +101,14 @@ pub enum MutationError {
     /// Network error occurring at Vault level which has no bearing on clients, e.g. serialisation
     /// failure or database failure
     NetworkOther(String),
+    /// The signing key is not allowed to perform the requested mutation
+    AccessDenied,
+    /// Attempt to exceed the maximum number of entries of a mutable data
+    TooManyEntries,
+    /// Some of the entry actions of a mutable data mutation are invalid
+    InvalidEntryActions(BTreeMap<Vec<u8>, EntryError>),
+    /// Attempt to store data which has already expired
+    DataExpired,
 }
 
 impl<T: Into<String>> From<T> for MutationError {
@@ -126,6 +145,14 @@ impl Display for MutationError {
             MutationError::NetworkOther(ref error) => {
                 write!(formatter, "Error on Vault network: {}", error)
             }
+            MutationError::AccessDenied => {
+                write!(formatter, "Key is not permitted to perform this mutation")
+            }
+            MutationError::TooManyEntries => write!(formatter, "Data has too many entries"),
+            MutationError::InvalidEntryActions(ref errors) => {
+                write!(formatter, "Invalid entry actions: {:?}", errors)
+            }
+            MutationError::DataExpired => write!(formatter, "Data given has already expired"),
         }
     }
 }
@@ -145,6 +172,45 @@ impl Error for MutationError {
             MutationError::InvitationAlreadyClaimed => "Invitation token already claimed",
             MutationError::NetworkFull => "Network full",
             MutationError::NetworkOther(ref error) => error,
+            MutationError::AccessDenied => "Access denied",
+            MutationError::TooManyEntries => "Too many entries",
+            MutationError::InvalidEntryActions(_) => "Invalid entry actions",
+            MutationError::DataExpired => "Data expired",
+        }
+    }
+}
+
+/// Errors in a single entry action of a mutable data mutation
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub enum EntryError {
+    /// The entry to update or delete doesn't exist
+    NoSuchEntry,
+    /// The entry to insert already exists
+    EntryExists,
+    /// The entry version is not the current one plus one; contains the current version
+    InvalidSuccessor(u64),
+}
+
+impl Display for EntryError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match *self {
+            EntryError::NoSuchEntry => write!(formatter, "Requested entry not found"),
+            EntryError::EntryExists => write!(formatter, "Entry given already exists"),
+            EntryError::InvalidSuccessor(version) => {
+                write!(formatter,
+                       "Entry version is not a successor of current version {}",
+                       version)
+            }
+        }
+    }
+}
+
+impl Error for EntryError {
+    fn description(&self) -> &str {
+        match *self {
+            EntryError::NoSuchEntry => "No such entry",
+            EntryError::EntryExists => "Entry exists",
+            EntryError::InvalidSuccessor(_) => "Invalid entry successor",
         }
     }
 }
@@ -17,20 +17,26 @@
 
 mod append_types;
 mod immutable_data;
+mod mutable_data;
 mod priv_appendable_data;
 mod pub_appendable_data;
 mod structured_data;
 
 pub use self::append_types::{AppendWrapper, AppendedData, Filter};
 pub use self::immutable_data::{ImmutableData, MAX_IMMUTABLE_DATA_SIZE_IN_BYTES};
+pub use self::mutable_data::{EntryAction, MAX_MUTABLE_DATA_ENTRIES,
+                             MAX_MUTABLE_DATA_SIZE_IN_BYTES, MutableData, Permission, Value};
 pub use self::priv_appendable_data::{MAX_PRIV_APPENDABLE_DATA_SIZE_IN_BYTES, PrivAppendableData,
                                      PrivAppendedData};
 pub use self::pub_appendable_data::{MAX_PUB_APPENDABLE_DATA_SIZE_IN_BYTES, PubAppendableData};
 pub use self::structured_data::{MAX_STRUCTURED_DATA_SIZE_IN_BYTES, StructuredData};
 use error::RoutingError;
+use maidsafe_utilities::serialisation::serialise;
 use rust_sodium::crypto::sign::{self, PublicKey, Signature};
+use serde::Serialize;
 use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{self, Debug, Formatter};
+use utils;
 use xor_name::XorName;
 
 /// A signing key with no matching private key. Passing ownership to it will make a chunk
@@ -55,6 +61,19 @@ pub fn verify_signatures(owners: &BTreeSet<PublicKey>,
     Ok(())
 }
 
+/// Returns the bytes the owners sign: the serialised `signed_fields`, followed by the serialised
+/// `expiry` if there is one. So data without an expiry is signed the same way as before expiry was
+/// introduced.
+pub fn data_to_sign<T: Serialize>(signed_fields: &T,
+                                  expiry: Option<u64>)
+                                  -> Result<Vec<u8>, RoutingError> {
+    let mut data = serialise(signed_fields)?;
+    if let Some(expiry) = expiry {
+        data.extend_from_slice(&serialise(&expiry)?);
+    }
+    Ok(data)
+}
+
 // Returns whether the signature is valid. It explicitly considers any signature for
 // `NO_OWNER_PUB_KEY` invalid.
 fn verify_detached(sig: &Signature, data: &[u8], pub_key: &PublicKey) -> bool {
@@ -72,6 +91,8 @@ pub enum Data {
     PubAppendable(PubAppendableData),
     /// `PrivAppendableData` data type.
     PrivAppendable(PrivAppendableData),
+    /// `MutableData` data type.
+    Mutable(MutableData),
 }
 
 impl Data {
@@ -82,6 +103,7 @@ impl Data {
             Data::Immutable(ref data) => data.name(),
             Data::PubAppendable(ref data) => data.name(),
             Data::PrivAppendable(ref data) => data.name(),
+            Data::Mutable(ref data) => data.name(),
         }
     }
 
@@ -92,14 +114,37 @@ impl Data {
             Data::Immutable(ref data) => data.identifier(),
             Data::PubAppendable(ref data) => data.identifier(),
             Data::PrivAppendable(ref data) => data.identifier(),
+            Data::Mutable(ref data) => data.identifier(),
+        }
+    }
+
+    /// Return the time after which the data expires, in seconds since the Unix epoch, if any.
+    pub fn expiry(&self) -> Option<u64> {
+        match *self {
+            Data::Structured(ref data) => data.get_expiry(),
+            Data::PubAppendable(ref data) => data.expiry,
+            Data::PrivAppendable(ref data) => data.expiry,
+            Data::Immutable(_) |
+            Data::Mutable(_) => None,
         }
     }
 
+    /// Return whether the data has expired at `now`, given in seconds since the Unix epoch.
+    pub fn is_expired_at(&self, now: u64) -> bool {
+        self.expiry().map_or(false, |expiry| expiry <= now)
+    }
+
+    /// Return whether the data has expired.
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(utils::unix_time_secs())
+    }
+
     /// Validate data size.
     pub fn validate_size(&self) -> bool {
         match *self {
             Data::Immutable(ref data) => data.validate_size(),
             Data::PrivAppendable(ref data) => data.validate_size(),
+            Data::Mutable(ref data) => data.validate_size(),
             Data::PubAppendable(ref data) => data.validate_size(),
             Data::Structured(ref data) => data.validate_size(),
         }
@@ -117,6 +162,8 @@ pub enum DataIdentifier {
     PubAppendable(XorName),
     /// Request for private appendable data.
     PrivAppendable(XorName),
+    /// Request for mutable data, (Identifier, TypeTag) pair.
+    Mutable(XorName, u64),
 }
 
 impl Debug for Data {
@@ -126,6 +173,7 @@ impl Debug for Data {
             Data::Immutable(ref data) => data.fmt(formatter),
             Data::PubAppendable(ref data) => data.fmt(formatter),
             Data::PrivAppendable(ref data) => data.fmt(formatter),
+            Data::Mutable(ref data) => data.fmt(formatter),
         }
     }
 }
@@ -137,7 +185,8 @@ impl DataIdentifier {
             DataIdentifier::Structured(ref name, _) |
             DataIdentifier::Immutable(ref name) |
             DataIdentifier::PubAppendable(ref name) |
-            DataIdentifier::PrivAppendable(ref name) => name,
+            DataIdentifier::PrivAppendable(ref name) |
+            DataIdentifier::Mutable(ref name, _) => name,
         }
     }
 }
@@ -145,6 +194,7 @@ impl DataIdentifier {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use maidsafe_utilities::serialisation::serialise;
     use rand;
     use rust_sodium::crypto::hash::sha256;
     use std::collections::BTreeSet;
@@ -185,4 +235,31 @@ mod tests {
         // name() resolves correctly for ImmutableData
         assert_eq!(&name, DataIdentifier::Immutable(name).name());
     }
+
+    #[test]
+    fn data_expiry() {
+        let mut structured_data =
+            unwrap!(StructuredData::new(0, rand::random(), 0, vec![], BTreeSet::new()));
+        assert!(!Data::Structured(structured_data.clone()).is_expired_at(u64::max_value()));
+
+        structured_data.set_expiry(Some(1000));
+        let data = Data::Structured(structured_data);
+        assert_eq!(data.expiry(), Some(1000));
+        assert!(!data.is_expired_at(999));
+        assert!(data.is_expired_at(1000));
+
+        let immutable_data = Data::Immutable(ImmutableData::new(vec![1, 2, 3]));
+        assert_eq!(immutable_data.expiry(), None);
+        assert!(!immutable_data.is_expired());
+    }
+
+    #[test]
+    fn expiry_extends_signed_data() {
+        let fields = (rand::random::<XorName>(), 5u64);
+        let legacy = unwrap!(serialise(&fields));
+        assert_eq!(unwrap!(data_to_sign(&fields, None)), legacy);
+        let with_expiry = unwrap!(data_to_sign(&fields, Some(1000)));
+        assert!(with_expiry.starts_with(&legacy));
+        assert!(with_expiry != unwrap!(data_to_sign(&fields, Some(1001))));
+    }
 }
@@ -0,0 +1,502 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::DataIdentifier;
+use client_errors::{EntryError, MutationError};
+use error::RoutingError;
+use maidsafe_utilities::serialisation::{serialise, serialised_size};
+use rust_sodium::crypto::sign::{self, PublicKey, SecretKey, Signature};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Debug, Formatter};
+use xor_name::XorName;
+
+/// Maximum allowed size for a mutable data to grow to
+pub const MAX_MUTABLE_DATA_SIZE_IN_BYTES: u64 = 1024 * 1024;
+/// Maximum allowed number of entries in a mutable data
+pub const MAX_MUTABLE_DATA_ENTRIES: usize = 100;
+
+/// A value stored under a key in `MutableData`.
+#[derive(Hash, Debug, Eq, PartialEq, PartialOrd, Ord, Clone, Deserialize, Serialize)]
+pub struct Value {
+    /// The content of the entry.
+    pub content: Vec<u8>,
+    /// The version of the entry: 0 when inserted, increased by one with every update.
+    pub entry_version: u64,
+}
+
+/// A mutation of a single entry of `MutableData`.
+#[derive(Hash, Debug, Eq, PartialEq, PartialOrd, Ord, Clone, Deserialize, Serialize)]
+pub enum EntryAction {
+    /// Inserts a new entry with the given content.
+    Insert(Vec<u8>),
+    /// Updates an existing entry. The new entry version must be the current one plus one.
+    Update(Value),
+    /// Deletes an existing entry. The given version must be the current one plus one.
+    Delete(u64),
+}
+
+/// An operation on `MutableData` which the owners can allow other keys to perform.
+#[derive(Hash, Debug, Eq, PartialEq, PartialOrd, Ord, Clone, Copy, Deserialize, Serialize)]
+pub enum Permission {
+    /// Inserting new entries.
+    Insert,
+    /// Updating existing entries.
+    Update,
+    /// Deleting existing entries.
+    Delete,
+    /// Changing the permissions of any key.
+    ManagePermissions,
+}
+
+/// Mutable key-value data.
+///
+/// Each entry has its own version. The owners may perform any mutation, other keys only those
+/// they have been given permission for. A mutation is a successor with the version increased by
+/// one, signed by the key performing it: it is validated by comparing it with the current data.
+#[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Deserialize, Serialize)]
+pub struct MutableData {
+    name: XorName,
+    tag: u64,
+    entries: BTreeMap<Vec<u8>, Value>,
+    permissions: BTreeMap<PublicKey, BTreeSet<Permission>>,
+    version: u64,
+    owners: BTreeSet<PublicKey>,
+    signatures: BTreeMap<PublicKey, Signature>,
+}
+
+impl MutableData {
+    /// Creates a new `MutableData`.
+    pub fn new(name: XorName,
+               tag: u64,
+               entries: BTreeMap<Vec<u8>, Value>,
+               permissions: BTreeMap<PublicKey, BTreeSet<Permission>>,
+               owners: BTreeSet<PublicKey>)
+               -> Result<MutableData, RoutingError> {
+        if owners.len() > 1 {
+            return Err(RoutingError::InvalidOwners);
+        }
+
+        Ok(MutableData {
+               name: name,
+               tag: tag,
+               entries: entries,
+               permissions: permissions,
+               version: 0,
+               owners: owners,
+               signatures: BTreeMap::new(),
+           })
+    }
+
+    /// Returns the name.
+    pub fn name(&self) -> &XorName {
+        &self.name
+    }
+
+    /// Returns `DataIdentifier` for this data element.
+    pub fn identifier(&self) -> DataIdentifier {
+        DataIdentifier::Mutable(self.name, self.tag)
+    }
+
+    /// Returns a copy with the version increased by one and no signatures, which can be mutated
+    /// and signed to become a valid successor.
+    pub fn successor(&self) -> MutableData {
+        MutableData {
+            version: self.version + 1,
+            signatures: BTreeMap::new(),
+            ..self.clone()
+        }
+    }
+
+    /// Applies all `actions` to the entries, or none of them if any is invalid.
+    pub fn mutate_entries(&mut self,
+                          actions: BTreeMap<Vec<u8>, EntryAction>)
+                          -> Result<(), MutationError> {
+        let mut entries = self.entries.clone();
+        let mut errors = BTreeMap::new();
+        for (key, action) in actions {
+            let current_version = entries.get(&key).map(|value| value.entry_version);
+            match (action, current_version) {
+                (EntryAction::Insert(content), None) => {
+                    let value = Value {
+                        content: content,
+                        entry_version: 0,
+                    };
+                    let _ = entries.insert(key, value);
+                }
+                (EntryAction::Insert(_), Some(_)) => {
+                    let _ = errors.insert(key, EntryError::EntryExists);
+                }
+                (EntryAction::Update(value), Some(version)) => {
+                    if value.entry_version == version + 1 {
+                        let _ = entries.insert(key, value);
+                    } else {
+                        let _ = errors.insert(key, EntryError::InvalidSuccessor(version));
+                    }
+                }
+                (EntryAction::Delete(entry_version), Some(version)) => {
+                    if entry_version == version + 1 {
+                        let _ = entries.remove(&key);
+                    } else {
+                        let _ = errors.insert(key, EntryError::InvalidSuccessor(version));
+                    }
+                }
+                (EntryAction::Update(_), None) |
+                (EntryAction::Delete(_), None) => {
+                    let _ = errors.insert(key, EntryError::NoSuchEntry);
+                }
+            }
+        }
+        if !errors.is_empty() {
+            return Err(MutationError::InvalidEntryActions(errors));
+        }
+        if entries.len() > MAX_MUTABLE_DATA_ENTRIES {
+            return Err(MutationError::TooManyEntries);
+        }
+        self.entries = entries;
+        self.signatures.clear();
+        Ok(())
+    }
+
+    /// Sets the permissions of the given key.
+    pub fn set_user_permissions(&mut self, user: PublicKey, permissions: BTreeSet<Permission>) {
+        let _ = self.permissions.insert(user, permissions);
+        self.signatures.clear();
+    }
+
+    /// Removes all permissions of the given key.
+    pub fn del_user_permissions(&mut self, user: &PublicKey) {
+        let _ = self.permissions.remove(user);
+        self.signatures.clear();
+    }
+
+    /// Transfers ownership to `new_owner`.
+    pub fn change_owner(&mut self, new_owner: PublicKey) {
+        self.owners = Some(new_owner).into_iter().collect();
+        self.signatures.clear();
+    }
+
+    /// Replaces this data item with the given updated version if the update is valid, otherwise
+    /// returns an error.
+    pub fn replace_with_other(&mut self, other: MutableData) -> Result<(), MutationError> {
+        self.validate_self_against_successor(&other)?;
+        *self = other;
+        Ok(())
+    }
+
+    /// Verifies that `other` is a valid update for `self`; returns an error otherwise.
+    ///
+    /// An update is valid if it doesn't change name or tag, increases the version by 1, is signed
+    /// by a single key, and that key is an owner or has the permissions for all changes made. Each
+    /// inserted entry must have version 0, and each updated entry its previous version plus one.
+    pub fn validate_self_against_successor(&self,
+                                           other: &MutableData)
+                                           -> Result<(), MutationError> {
+        if other.name != self.name || other.tag != self.tag || other.version != self.version + 1 ||
+           other.owners.len() > 1 {
+            return Err(MutationError::InvalidSuccessor);
+        }
+        let signer = match (other.signatures.len(), other.signatures.iter().next()) {
+            (1, Some((key, signature))) => {
+                let data = other
+                    .data_to_sign()
+                    .map_err(|error| MutationError::NetworkOther(format!("{:?}", error)))?;
+                if !super::verify_detached(signature, &data, key) {
+                    return Err(MutationError::InvalidSuccessor);
+                }
+                key
+            }
+            _ => return Err(MutationError::InvalidSuccessor),
+        };
+        if other.entries.len() > MAX_MUTABLE_DATA_ENTRIES {
+            return Err(MutationError::TooManyEntries);
+        }
+
+        let is_owner = self.owners.contains(signer);
+        let is_allowed = |permission| {
+            is_owner ||
+            self.permissions
+                .get(signer)
+                .map_or(false, |permissions| permissions.contains(&permission))
+        };
+        if (other.owners != self.owners && !is_owner) ||
+           (other.permissions != self.permissions &&
+            !is_allowed(Permission::ManagePermissions)) {
+            return Err(MutationError::AccessDenied);
+        }
+
+        let mut errors = BTreeMap::new();
+        for (key, value) in &other.entries {
+            let (permission, expected_version) = match self.entries.get(key) {
+                None => (Permission::Insert, 0),
+                Some(old_value) if old_value == value => continue,
+                Some(old_value) => (Permission::Update, old_value.entry_version + 1),
+            };
+            if !is_allowed(permission) {
+                return Err(MutationError::AccessDenied);
+            }
+            if value.entry_version != expected_version {
+                let current_version = expected_version.saturating_sub(1);
+                let _ = errors.insert(key.clone(), EntryError::InvalidSuccessor(current_version));
+            }
+        }
+        if self.entries
+               .keys()
+               .any(|key| !other.entries.contains_key(key)) &&
+           !is_allowed(Permission::Delete) {
+            return Err(MutationError::AccessDenied);
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(MutationError::InvalidEntryActions(errors))
+        }
+    }
+
+    fn data_to_sign(&self) -> Result<Vec<u8>, RoutingError> {
+        let data = SerialisableMutableData {
+            name: self.name,
+            tag: self.tag,
+            entries: &self.entries,
+            permissions: &self.permissions,
+            version: self.version,
+            owners: &self.owners,
+        };
+        serialise(&data).map_err(From::from)
+    }
+
+    /// Signs the data with the given keys, replacing any existing signature.
+    pub fn add_signature(&mut self, keys: &(PublicKey, SecretKey)) -> Result<(), RoutingError> {
+        let data = self.data_to_sign()?;
+        let sig = sign::sign_detached(&data, &keys.1);
+        self.signatures = Some((keys.0, sig)).into_iter().collect();
+        Ok(())
+    }
+
+    /// Get the tag
+    pub fn get_tag(&self) -> u64 {
+        self.tag
+    }
+
+    /// Get the version
+    pub fn get_version(&self) -> u64 {
+        self.version
+    }
+
+    /// Get all entries
+    pub fn get_entries(&self) -> &BTreeMap<Vec<u8>, Value> {
+        &self.entries
+    }
+
+    /// Get the entry with the given key
+    pub fn get(&self, key: &[u8]) -> Option<&Value> {
+        self.entries.get(key)
+    }
+
+    /// Get the permissions of all keys other than the owners
+    pub fn get_permissions(&self) -> &BTreeMap<PublicKey, BTreeSet<Permission>> {
+        &self.permissions
+    }
+
+    /// Get the current owner keys
+    pub fn get_owners(&self) -> &BTreeSet<PublicKey> {
+        &self.owners
+    }
+
+    /// Get the signatures of the current version
+    pub fn get_signatures(&self) -> &BTreeMap<PublicKey, Signature> {
+        &self.signatures
+    }
+
+    /// Return true if the size and the number of entries are valid
+    pub fn validate_size(&self) -> bool {
+        self.entries.len() <= MAX_MUTABLE_DATA_ENTRIES &&
+        serialised_size(self) <= MAX_MUTABLE_DATA_SIZE_IN_BYTES
+    }
+}
+
+impl Debug for MutableData {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter,
+               "MutableData {{ name: {}, tag: {}, version: {}, entries: {}, permissions: {:?}, \
+                owners: {:?}, signatures: {:?} }}",
+               self.name(),
+               self.tag,
+               self.version,
+               self.entries.len(),
+               self.permissions,
+               self.owners,
+               self.signatures)
+    }
+}
+
+#[derive(Serialize)]
+struct SerialisableMutableData<'a> {
+    name: XorName,
+    tag: u64,
+    entries: &'a BTreeMap<Vec<u8>, Value>,
+    permissions: &'a BTreeMap<PublicKey, BTreeSet<Permission>>,
+    version: u64,
+    owners: &'a BTreeSet<PublicKey>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use client_errors::{EntryError, MutationError};
+    use rand;
+    use rust_sodium::crypto::sign;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    fn entry_actions(actions: Vec<(&str, EntryAction)>) -> BTreeMap<Vec<u8>, EntryAction> {
+        actions
+            .into_iter()
+            .map(|(key, action)| (key.as_bytes().to_vec(), action))
+            .collect()
+    }
+
+    fn signed_successor<F>(data: &MutableData,
+                           keys: &(sign::PublicKey, sign::SecretKey),
+                           mutate: F)
+                           -> MutableData
+        where F: FnOnce(&mut MutableData)
+    {
+        let mut successor = data.successor();
+        mutate(&mut successor);
+        unwrap!(successor.add_signature(keys));
+        successor
+    }
+
+    #[test]
+    fn mutate_entries() {
+        let owner_keys = sign::gen_keypair();
+        let owners = Some(owner_keys.0).into_iter().collect();
+        let mut data =
+            unwrap!(MutableData::new(rand::random(), 0, BTreeMap::new(), BTreeMap::new(), owners));
+
+        let actions = entry_actions(vec![("a", EntryAction::Insert(vec![1])),
+                                         ("b", EntryAction::Insert(vec![2]))]);
+        unwrap!(data.mutate_entries(actions));
+        let value = Value {
+            content: vec![3],
+            entry_version: 1,
+        };
+        let actions = entry_actions(vec![("a", EntryAction::Update(value.clone())),
+                                         ("b", EntryAction::Delete(1))]);
+        unwrap!(data.mutate_entries(actions));
+        assert_eq!(data.get(b"a"), Some(&value));
+        assert_eq!(data.get(b"b"), None);
+
+        // Invalid actions are all reported, and none of the valid ones is applied.
+        let actions = entry_actions(vec![("a", EntryAction::Delete(1)),
+                                         ("b", EntryAction::Delete(1)),
+                                         ("c", EntryAction::Insert(vec![5]))]);
+        let errors = vec![(b"a".to_vec(), EntryError::InvalidSuccessor(1)),
+                          (b"b".to_vec(), EntryError::NoSuchEntry)]
+                .into_iter()
+                .collect();
+        assert_eq!(data.mutate_entries(actions),
+                   Err(MutationError::InvalidEntryActions(errors)));
+        assert_eq!(data.get_entries().len(), 1);
+
+        let actions = entry_actions(vec![("a", EntryAction::Insert(vec![4]))]);
+        let errors = Some((b"a".to_vec(), EntryError::EntryExists)).into_iter().collect();
+        assert_eq!(data.mutate_entries(actions),
+                   Err(MutationError::InvalidEntryActions(errors)));
+    }
+
+    #[test]
+    fn validate_successor() {
+        let owner_keys = sign::gen_keypair();
+        let user_keys = sign::gen_keypair();
+        let owners = Some(owner_keys.0).into_iter().collect();
+        let entries = Some((b"a".to_vec(),
+                            Value {
+                                content: vec![1],
+                                entry_version: 0,
+                            }))
+                .into_iter()
+                .collect();
+        let mut data =
+            unwrap!(MutableData::new(rand::random(), 0, entries, BTreeMap::new(), owners));
+
+        // The user has no permissions yet.
+        let insert = |data: &mut MutableData| {
+            let actions = entry_actions(vec![("b", EntryAction::Insert(vec![2]))]);
+            unwrap!(data.mutate_entries(actions));
+        };
+        let successor = signed_successor(&data, &user_keys, &insert);
+        assert_eq!(data.validate_self_against_successor(&successor),
+                   Err(MutationError::AccessDenied));
+
+        // The owner allows the user to insert.
+        let permissions: BTreeSet<_> = Some(Permission::Insert).into_iter().collect();
+        let successor = signed_successor(&data, &owner_keys, |data| {
+            data.set_user_permissions(user_keys.0, permissions.clone())
+        });
+        unwrap!(data.replace_with_other(successor));
+
+        let successor = signed_successor(&data, &user_keys, &insert);
+        unwrap!(data.validate_self_against_successor(&successor));
+
+        // But not to update, delete or manage permissions.
+        let value = Value {
+            content: vec![3],
+            entry_version: 1,
+        };
+        let update = entry_actions(vec![("a", EntryAction::Update(value))]);
+        let successor = signed_successor(&data, &user_keys, |data| {
+            unwrap!(data.mutate_entries(update.clone()))
+        });
+        assert_eq!(data.validate_self_against_successor(&successor),
+                   Err(MutationError::AccessDenied));
+        let delete = entry_actions(vec![("a", EntryAction::Delete(1))]);
+        let successor = signed_successor(&data, &user_keys, |data| {
+            unwrap!(data.mutate_entries(delete))
+        });
+        assert_eq!(data.validate_self_against_successor(&successor),
+                   Err(MutationError::AccessDenied));
+        let successor = signed_successor(&data, &user_keys, |data| {
+            data.del_user_permissions(&user_keys.0)
+        });
+        assert_eq!(data.validate_self_against_successor(&successor),
+                   Err(MutationError::AccessDenied));
+
+        // The owner may, but the version has to increase by one.
+        let successor = signed_successor(&data, &owner_keys, |data| {
+            unwrap!(data.mutate_entries(update))
+        });
+        unwrap!(data.validate_self_against_successor(&successor));
+        let mut skipped = signed_successor(&successor, &owner_keys, |_| ());
+        assert_eq!(data.validate_self_against_successor(&skipped),
+                   Err(MutationError::InvalidSuccessor));
+
+        // Entries must have valid versions, and the signature must match.
+        let value = Value {
+            content: vec![3],
+            entry_version: 2,
+        };
+        let mut invalid = data.successor();
+        let _ = invalid.entries.insert(b"a".to_vec(), value);
+        unwrap!(invalid.add_signature(&owner_keys));
+        let errors = Some((b"a".to_vec(), EntryError::InvalidSuccessor(0))).into_iter().collect();
+        assert_eq!(data.validate_self_against_successor(&invalid),
+                   Err(MutationError::InvalidEntryActions(errors)));
+        skipped.version = data.version + 1;
+        assert_eq!(data.validate_self_against_successor(&skipped),
+                   Err(MutationError::InvalidSuccessor));
+    }
+}
@@ -94,6 +94,8 @@ pub struct PrivAppendableData {
     /// The collection of appended data items. These are not signed by the owners, as they change
     /// even between `Post`s.
     pub data: BTreeSet<PrivAppendedData>, // Unsigned
+    /// The time after which the data expires, in seconds since the Unix epoch, if any.
+    pub expiry: Option<u64>,
 }
 
 impl PrivAppendableData {
@@ -119,6 +121,7 @@ impl PrivAppendableData {
                owners: owners,
                signatures: BTreeMap::new(),
                data: BTreeSet::new(),
+               expiry: None,
            })
     }
 
@@ -141,6 +144,7 @@ impl PrivAppendableData {
         self.deleted_data = other.deleted_data;
         self.signatures = other.signatures;
         self.owners = other.owners;
+        self.expiry = other.expiry;
         self.data.extend(other.data);
         for ad in &self.deleted_data {
             if self.data.contains(ad) {
@@ -216,7 +220,7 @@ impl PrivAppendableData {
             deleted_data: &self.deleted_data,
         };
 
-        serialise(&sd).map_err(From::from)
+        super::data_to_sign(&sd, self.expiry)
     }
 
     /// Adds a signature with the given `keys.1` to the `signatures` and returns
@@ -486,4 +490,31 @@ mod tests {
         assert!(ad_fail.add_signature(&keys).is_ok());
         assert!(ad.update_with_other(ad_fail).is_err());
     }
+
+    #[test]
+    fn expiry_is_signed() {
+        let keys = sign::gen_keypair();
+        let encrypt_keys = box_::gen_keypair();
+        let owners: BTreeSet<_> = Some(keys.0).into_iter().collect();
+        let mut data = unwrap!(PrivAppendableData::new(rand::random(),
+                                                       0,
+                                                       owners.clone(),
+                                                       BTreeSet::new(),
+                                                       Filter::white_list(None),
+                                                       encrypt_keys.0));
+
+        let mut successor = unwrap!(PrivAppendableData::new(*data.name(),
+                                                            1,
+                                                            owners,
+                                                            BTreeSet::new(),
+                                                            Filter::white_list(None),
+                                                            encrypt_keys.0));
+        successor.expiry = Some(1000);
+        assert!(successor.add_signature(&keys).is_ok());
+        let mut forged = successor.clone();
+        forged.expiry = None;
+        assert!(data.validate_self_against_successor(&forged).is_err());
+        assert!(data.update_with_other(successor).is_ok());
+        assert_eq!(data.expiry, Some(1000));
+    }
 }
@@ -17,7 +17,7 @@
 
 use super::{AppendWrapper, AppendedData, DataIdentifier, Filter, NO_OWNER_PUB_KEY};
 use error::RoutingError;
-use maidsafe_utilities::serialisation::{serialise, serialised_size};
+use maidsafe_utilities::serialisation::serialised_size;
 use rust_sodium::crypto::sign::{self, PublicKey, SecretKey, Signature};
 use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{self, Debug, Formatter};
@@ -50,6 +50,8 @@ pub struct PubAppendableData {
     /// The collection of appended data items. These are not signed by the owners, as they change
     /// even between `Post`s.
     pub data: BTreeSet<AppendedData>,
+    /// The time after which the data expires, in seconds since the Unix epoch, if any.
+    pub expiry: Option<u64>,
 }
 
 impl PubAppendableData {
@@ -72,6 +74,7 @@ impl PubAppendableData {
                owners: owners,
                signatures: BTreeMap::new(),
                data: BTreeSet::new(),
+               expiry: None,
            })
     }
 
@@ -93,6 +96,7 @@ impl PubAppendableData {
         self.deleted_data = other.deleted_data;
         self.owners = other.owners;
         self.signatures = other.signatures;
+        self.expiry = other.expiry;
         self.data.extend(other.data);
         for ad in &self.deleted_data {
             if self.data.contains(ad) {
@@ -168,7 +172,7 @@ impl PubAppendableData {
             deleted_data: &self.deleted_data,
         };
 
-        serialise(&sd).map_err(From::from)
+        super::data_to_sign(&sd, self.expiry)
     }
 
     /// Adds a signature with the given `keys.1` to the `signatures` and returns
@@ -399,4 +403,28 @@ mod test {
         assert!(ad_fail.add_signature(&keys).is_ok());
         assert!(ad.update_with_other(ad_fail).is_err());
     }
+
+    #[test]
+    fn expiry_is_signed() {
+        let keys = sign::gen_keypair();
+        let owners: BTreeSet<_> = Some(keys.0).into_iter().collect();
+        let mut data = unwrap!(PubAppendableData::new(rand::random(),
+                                                      0,
+                                                      owners.clone(),
+                                                      BTreeSet::new(),
+                                                      Filter::white_list(None)));
+
+        let mut successor = unwrap!(PubAppendableData::new(*data.name(),
+                                                           1,
+                                                           owners,
+                                                           BTreeSet::new(),
+                                                           Filter::white_list(None)));
+        successor.expiry = Some(1000);
+        assert!(successor.add_signature(&keys).is_ok());
+        let mut forged = successor.clone();
+        forged.expiry = None;
+        assert!(data.validate_self_against_successor(&forged).is_err());
+        assert!(data.update_with_other(successor).is_ok());
+        assert_eq!(data.expiry, Some(1000));
+    }
 }
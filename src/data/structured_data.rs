@@ -17,7 +17,7 @@
 
 use super::{DataIdentifier, NO_OWNER_PUB_KEY};
 use error::RoutingError;
-use maidsafe_utilities::serialisation::{serialise, serialised_size};
+use maidsafe_utilities::serialisation::serialised_size;
 use rust_sodium::crypto::sign::{self, PublicKey, SecretKey, Signature};
 use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{self, Debug, Formatter};
@@ -39,6 +39,7 @@ pub struct StructuredData {
     version: u64,
     owners: BTreeSet<PublicKey>,
     signatures: BTreeMap<PublicKey, Signature>,
+    expiry: Option<u64>,
 }
 
 impl StructuredData {
@@ -60,6 +61,7 @@ impl StructuredData {
                version: version,
                owners: owners,
                signatures: BTreeMap::new(),
+               expiry: None,
            })
     }
 
@@ -76,6 +78,7 @@ impl StructuredData {
         self.version = other.version;
         self.owners = other.owners;
         self.signatures = other.signatures;
+        self.expiry = other.expiry;
         Ok(())
     }
 
@@ -139,7 +142,7 @@ impl StructuredData {
             owners: &self.owners,
         };
 
-        serialise(&sd).map_err(From::from)
+        super::data_to_sign(&sd, self.expiry)
     }
 
     /// Adds a signature with the given `keys.1` to the `signatures` and returns
@@ -182,6 +185,17 @@ impl StructuredData {
         &self.owners
     }
 
+    /// Get the time after which the data expires, in seconds since the Unix epoch, if any
+    pub fn get_expiry(&self) -> Option<u64> {
+        self.expiry
+    }
+
+    /// Set the time after which the data expires, in seconds since the Unix epoch. The expiry is
+    /// signed, so this needs to be done before adding a signature.
+    pub fn set_expiry(&mut self, expiry: Option<u64>) {
+        self.expiry = expiry;
+    }
+
     /// Get previous owner signatures
     pub fn get_signatures(&self) -> &BTreeMap<PublicKey, Signature> {
         &self.signatures
@@ -293,4 +307,21 @@ mod tests {
         assert!(sd_fail.add_signature(&keys).is_ok());
         assert!(sd.replace_with_other(sd_fail).is_err());
     }
+
+    #[test]
+    fn expiry_is_signed() {
+        let keys = sign::gen_keypair();
+        let owner: BTreeSet<_> = Some(keys.0).into_iter().collect();
+        let name: XorName = rand::random();
+
+        let mut sd = unwrap!(StructuredData::new(0, name, 0, vec![], owner.clone()));
+        let mut sd_new = unwrap!(StructuredData::new(0, name, 1, vec![], owner.clone()));
+        sd_new.set_expiry(Some(1000));
+        assert!(sd_new.add_signature(&keys).is_ok());
+        let mut sd_forged = sd_new.clone();
+        sd_forged.set_expiry(None);
+        assert!(sd.validate_self_against_successor(&sd_forged).is_err());
+        assert!(sd.replace_with_other(sd_new).is_ok());
+        assert_eq!(sd.get_expiry(), Some(1000));
+    }
 }
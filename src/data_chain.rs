@@ -0,0 +1,738 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! A verifiable record of how a section's membership evolved.
+//!
+//! Whenever a section's membership changes - through nodes joining or leaving, splits or merges -
+//! its members sign a `BlockPayload` listing the new members and the hash of the previous block.
+//! Once a quorum of the members the new block has in common with its predecessor signed it, the
+//! block is appended to the section's `DataChain`. Anyone who trusts the chain's genesis block
+//! can therefore verify every later block, and with it the section's current members.
+//!
+//! The chain also defines each member's age: the number of membership changes it has survived in
+//! the section. Older members are trusted more, and the oldest are relocated periodically.
+
+use {QUORUM_DENOMINATOR, QUORUM_NUMERATOR};
+use error::RoutingError;
+use id::{FullId, PublicId};
+use itertools::Itertools;
+use maidsafe_utilities::serialisation::serialise;
+use routing_table::Prefix;
+use rust_sodium::crypto::sign::{self, Signature};
+use sha3::Digest256;
+use std::cmp;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Debug, Formatter};
+use std::iter;
+use tiny_keccak::sha3_256;
+use utils;
+use xor_name::XorName;
+
+/// The number of membership changes a member has to survive in its section before it becomes
+/// eligible for relocation to a random section.
+pub const RELOCATION_AGE: u32 = 16;
+/// The maximum number of blocks sent in a single `DataChainPage`.
+pub const MAX_BLOCKS_PER_PAGE: usize = 32;
+
+/// The part of a block which is signed: a section's members after a change, linked to the block
+/// before it.
+#[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Hash, Serialize, Deserialize)]
+pub struct BlockPayload {
+    /// The hash of the previous block's payload, or all zeros for a genesis block.
+    pub prev_hash: Digest256,
+    /// The section's prefix.
+    pub prefix: Prefix<XorName>,
+    /// The section's members.
+    pub members: BTreeSet<PublicId>,
+    /// The ages which members added with this block carried over from the section they were
+    /// relocated from.
+    pub ages: BTreeMap<PublicId, u32>,
+}
+
+impl BlockPayload {
+    /// Creates a new payload following the block with hash `prev_hash`.
+    pub fn new(prev_hash: Digest256,
+               prefix: Prefix<XorName>,
+               members: BTreeSet<PublicId>,
+               ages: BTreeMap<PublicId, u32>)
+               -> BlockPayload {
+        BlockPayload {
+            prev_hash: prev_hash,
+            prefix: prefix,
+            members: members,
+            ages: ages,
+        }
+    }
+
+    /// Returns the hash by which the next block refers to this one.
+    pub fn hash(&self) -> Result<Digest256, RoutingError> {
+        Ok(sha3_256(&serialise(self)?))
+    }
+
+    /// Returns `full_id`'s signature of this payload.
+    pub fn sign(&self, full_id: &FullId) -> Result<Signature, RoutingError> {
+        Ok(sign::sign_detached(&serialise(self)?, full_id.signing_private_key()))
+    }
+
+    /// Returns whether `sig` is a valid signature of this payload by `pub_id`.
+    pub fn verify_signature(&self, pub_id: &PublicId, sig: &Signature) -> bool {
+        match serialise(self) {
+            Ok(serialised) => {
+                sign::verify_detached(sig, &serialised, pub_id.signing_public_key())
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Returns whether both payloads describe the same section membership.
+    fn same_section(&self, other: &BlockPayload) -> bool {
+        self.prefix == other.prefix && self.members == other.members
+    }
+}
+
+impl Debug for BlockPayload {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter,
+               "BlockPayload {{ prev_hash: {}, prefix: {:?}, members: {} }}",
+               utils::format_binary_array(&self.prev_hash),
+               self.prefix,
+               self.members.len())
+    }
+}
+
+/// A payload together with its members' signatures.
+#[derive(Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Block {
+    payload: BlockPayload,
+    signatures: BTreeMap<PublicId, Signature>,
+}
+
+impl Block {
+    /// The signed section membership.
+    pub fn payload(&self) -> &BlockPayload {
+        &self.payload
+    }
+
+    /// The signatures of the payload.
+    pub fn signatures(&self) -> &BTreeMap<PublicId, Signature> {
+        &self.signatures
+    }
+
+    /// Checks that this block is correctly signed to follow `prev`, or to be a genesis block if
+    /// `prev` is `None`.
+    fn check(&self, prev: Option<&Block>) -> Result<(), RoutingError> {
+        let voters: BTreeSet<PublicId> = match prev {
+            Some(prev) => {
+                if self.payload.prev_hash != prev.payload.hash()? {
+                    return Err(RoutingError::HashMismatch);
+                }
+                prev.payload
+                    .members
+                    .intersection(&self.payload.members)
+                    .cloned()
+                    .collect()
+            }
+            None => {
+                if self.payload.prev_hash != [0; 32] {
+                    return Err(RoutingError::HashMismatch);
+                }
+                self.payload.members.clone()
+            }
+        };
+        // Only members added with this block can carry over an age.
+        let prev_members = prev.map(|prev| &prev.payload.members);
+        if self.payload
+               .ages
+               .keys()
+               .any(|pub_id| {
+                        !self.payload.members.contains(pub_id) ||
+                        prev_members.map_or(false, |members| members.contains(pub_id))
+                    }) {
+            return Err(RoutingError::InvalidMessage);
+        }
+        let mut valid_sigs = 0;
+        for (pub_id, sig) in self.signatures
+                .iter()
+                .filter(|&(pub_id, _)| voters.contains(pub_id)) {
+            if !self.payload.verify_signature(pub_id, sig) {
+                return Err(RoutingError::FailedSignature);
+            }
+            valid_sigs += 1;
+        }
+        if is_quorum(valid_sigs, voters.len()) {
+            Ok(())
+        } else {
+            Err(RoutingError::NotEnoughSignatures)
+        }
+    }
+}
+
+impl Debug for Block {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter,
+               "Block {{ {:?}, signatures: {} }}",
+               self.payload,
+               self.signatures.len())
+    }
+}
+
+/// A run of consecutive blocks of a chain, sent to a peer which is catching up with it.
+#[derive(Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct DataChainPage {
+    /// The index of the first block in `blocks`.
+    pub start: u64,
+    /// At most `MAX_BLOCKS_PER_PAGE` blocks.
+    pub blocks: Vec<Block>,
+    /// The length of the sender's chain.
+    pub chain_len: u64,
+}
+
+impl DataChainPage {
+    /// Returns the hash of the genesis block, if this page contains it.
+    pub fn genesis_hash(&self) -> Result<Option<Digest256>, RoutingError> {
+        match self.blocks.first() {
+            Some(block) if self.start == 0 => Ok(Some(block.payload.hash()?)),
+            _ => Ok(None),
+        }
+    }
+}
+
+impl Debug for DataChainPage {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter,
+               "DataChainPage {{ start: {}, blocks: {}, chain_len: {} }}",
+               self.start,
+               self.blocks.len(),
+               self.chain_len)
+    }
+}
+
+/// A hash-linked chain of quorum-signed blocks recording a section's membership changes.
+#[derive(Default, Eq, PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct DataChain {
+    blocks: Vec<Block>,
+}
+
+impl DataChain {
+    /// Creates a chain whose genesis block lists `full_id` as the only member of the section with
+    /// prefix `prefix`.
+    pub fn genesis(prefix: Prefix<XorName>, full_id: &FullId) -> Result<DataChain, RoutingError> {
+        let members = iter::once(*full_id.public_id()).collect();
+        let payload = BlockPayload::new([0; 32], prefix, members, BTreeMap::new());
+        let sig = payload.sign(full_id)?;
+        let block = Block {
+            payload: payload,
+            signatures: iter::once((*full_id.public_id(), sig)).collect(),
+        };
+        Ok(DataChain { blocks: vec![block] })
+    }
+
+    /// Returns the blocks, starting with the genesis block.
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+
+    /// Returns the number of blocks.
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Returns whether the chain doesn't even have a genesis block.
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Returns the latest block, describing the section's current members.
+    pub fn last(&self) -> Option<&Block> {
+        self.blocks.last()
+    }
+
+    /// Returns the hash of the genesis block.
+    pub fn genesis_hash(&self) -> Result<Option<Digest256>, RoutingError> {
+        match self.blocks.first() {
+            Some(block) => Ok(Some(block.payload.hash()?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Verifies that the chain starts with the genesis block with hash `genesis_hash`, and that
+    /// each block is signed by a quorum of the members it has in common with its predecessor.
+    pub fn verify(&self, genesis_hash: &Digest256) -> Result<(), RoutingError> {
+        if self.genesis_hash()?.as_ref() != Some(genesis_hash) {
+            return Err(RoutingError::HashMismatch);
+        }
+        let mut prev = None;
+        for block in &self.blocks {
+            block.check(prev)?;
+            prev = Some(block);
+        }
+        Ok(())
+    }
+
+    /// Returns the page of at most `MAX_BLOCKS_PER_PAGE` blocks beginning with block `start`.
+    pub fn page(&self, start: u64) -> DataChainPage {
+        let start_index = cmp::min(start as usize, self.blocks.len());
+        let end_index = cmp::min(start_index + MAX_BLOCKS_PER_PAGE, self.blocks.len());
+        DataChainPage {
+            start: start_index as u64,
+            blocks: self.blocks[start_index..end_index].to_vec(),
+            chain_len: self.blocks.len() as u64,
+        }
+    }
+
+    /// Appends the blocks of `page` which follow our latest one, after checking that each of them
+    /// is correctly signed, that the blocks we already have match ours and that the chain starts
+    /// with the genesis block with hash `genesis_hash`. Returns the number of blocks appended.
+    ///
+    /// On error, the valid blocks preceding the offending one are kept.
+    pub fn extend_from_page(&mut self,
+                            page: DataChainPage,
+                            genesis_hash: &Digest256)
+                            -> Result<usize, RoutingError> {
+        if page.blocks.len() > MAX_BLOCKS_PER_PAGE || page.start > self.blocks.len() as u64 {
+            return Err(RoutingError::InvalidMessage);
+        }
+        let mut appended = 0;
+        for (index, block) in (page.start as usize..).zip(page.blocks) {
+            if index < self.blocks.len() {
+                if self.blocks[index] != block {
+                    return Err(RoutingError::HashMismatch);
+                }
+                continue;
+            }
+            if index == 0 {
+                if block.payload.hash()? != *genesis_hash {
+                    return Err(RoutingError::HashMismatch);
+                }
+                block.check(None)?;
+            } else {
+                block.check(self.blocks.last())?;
+            }
+            self.blocks.push(block);
+            appended += 1;
+        }
+        Ok(appended)
+    }
+
+    /// Returns whether `signatures` contains valid signatures of `data` by a quorum of the members
+    /// listed in the latest block.
+    pub fn verify_section_signatures(&self,
+                                     data: &[u8],
+                                     signatures: &BTreeMap<PublicId, Signature>)
+                                     -> bool {
+        let members = match self.last() {
+            Some(block) => &block.payload.members,
+            None => return false,
+        };
+        let valid_sigs = signatures
+            .iter()
+            .filter(|&(pub_id, sig)| {
+                        members.contains(pub_id) &&
+                        sign::verify_detached(sig, data, pub_id.signing_public_key())
+                    })
+            .count();
+        is_quorum(valid_sigs, members.len())
+    }
+
+    /// Returns whether this chain consists of all of `other`'s blocks, followed by zero or more
+    /// further ones.
+    pub fn extends(&self, other: &DataChain) -> bool {
+        self.blocks.len() >= other.blocks.len() && self.blocks.starts_with(&other.blocks)
+    }
+
+    /// Returns whether the latest block already lists the section described by `payload`.
+    pub fn is_current(&self, payload: &BlockPayload) -> bool {
+        self.last()
+            .map_or(false, |block| block.payload.same_section(payload))
+    }
+
+    /// Returns the ages of the members listed in the latest block: the age each of them carried
+    /// over when it was added, plus the number of subsequent blocks it has been listed in without
+    /// interruption.
+    pub fn member_ages(&self) -> BTreeMap<PublicId, u32> {
+        self.member_tenures()
+            .into_iter()
+            .map(|(pub_id, (carried_age, tenure))| (pub_id, carried_age.saturating_add(tenure)))
+            .collect()
+    }
+
+    /// Returns the section's elders, which sign and accumulate messages on its behalf: the
+    /// `count` oldest members listed in the latest block, with ties broken by name.
+    pub fn elders(&self, count: usize) -> BTreeSet<PublicId> {
+        self.member_ages()
+            .into_iter()
+            .sorted_by(|lhs, rhs| rhs.1.cmp(&lhs.1).then(lhs.0.name().cmp(rhs.0.name())))
+            .into_iter()
+            .take(count)
+            .map(|(pub_id, _)| pub_id)
+            .collect()
+    }
+
+    /// Returns the member which the latest membership change selected for relocation, if any:
+    /// the oldest member which has been listed in the last `RELOCATION_AGE` blocks, with ties
+    /// broken by ID.
+    pub fn relocation_candidate(&self) -> Option<PublicId> {
+        self.member_tenures()
+            .into_iter()
+            .filter(|&(_, (_, tenure))| tenure >= RELOCATION_AGE)
+            .max_by_key(|&(pub_id, (carried_age, tenure))| {
+                            (carried_age.saturating_add(tenure), pub_id)
+                        })
+            .map(|(pub_id, _)| pub_id)
+    }
+
+    /// Returns, for each member listed in the latest block, the age it carried over when it was
+    /// added and the number of subsequent blocks it has been listed in without interruption.
+    fn member_tenures(&self) -> BTreeMap<PublicId, (u32, u32)> {
+        let mut tenures = BTreeMap::new();
+        let mut remaining = match self.last() {
+            Some(block) => block.payload.members.clone(),
+            None => return tenures,
+        };
+        for (index, block) in self.blocks.iter().enumerate().rev() {
+            let added: Vec<PublicId> = match index.checked_sub(1) {
+                Some(prev_index) => {
+                    remaining
+                        .difference(&self.blocks[prev_index].payload.members)
+                        .cloned()
+                        .collect()
+                }
+                None => remaining.iter().cloned().collect(),
+            };
+            let tenure = (self.blocks.len() - 1 - index) as u32;
+            for pub_id in added {
+                let _ = remaining.remove(&pub_id);
+                let carried_age = block.payload.ages.get(&pub_id).cloned().unwrap_or(0);
+                let _ = tenures.insert(pub_id, (carried_age, tenure));
+            }
+            if remaining.is_empty() {
+                break;
+            }
+        }
+        tenures
+    }
+
+    /// Returns the hash of the latest block.
+    pub fn last_hash(&self) -> Result<Option<Digest256>, RoutingError> {
+        match self.last() {
+            Some(block) => Ok(Some(block.payload.hash()?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Accumulates signatures of block payloads until they can be appended to a chain.
+#[derive(Default)]
+pub struct BlockAccumulator {
+    pending: BTreeMap<BlockPayload, BTreeMap<PublicId, Signature>>,
+}
+
+impl BlockAccumulator {
+    /// Adds `pub_id`'s signature of `payload`. The signature must have been verified already.
+    ///
+    /// Any signature `pub_id` gave for a different successor of the same block is dropped: the
+    /// later payload reflects the more recent membership.
+    pub fn add_signature(&mut self, payload: BlockPayload, pub_id: PublicId, sig: Signature) {
+        for (_, sigs) in self.pending
+                .iter_mut()
+                .filter(|&(pending, _)| {
+                            pending.prev_hash == payload.prev_hash && *pending != payload
+                        }) {
+            let _ = sigs.remove(&pub_id);
+        }
+        let _ = self.pending
+            .entry(payload)
+            .or_insert_with(BTreeMap::new)
+            .insert(pub_id, sig);
+        let unsigned = self.pending
+            .iter()
+            .filter(|&(_, sigs)| sigs.is_empty())
+            .map(|(payload, _)| payload.clone())
+            .collect_vec();
+        for payload in unsigned {
+            let _ = self.pending.remove(&payload);
+        }
+    }
+
+    /// Appends as many of the pending blocks as possible to `chain`, and drops the ones which
+    /// follow a block that isn't the latest one any more. Returns the number of blocks appended.
+    pub fn extend_chain(&mut self, chain: &mut DataChain) -> usize {
+        let mut appended = 0;
+        loop {
+            let last_hash = match chain.last_hash() {
+                Ok(Some(hash)) => hash,
+                Ok(None) | Err(_) => break,
+            };
+            let next = self.pending
+                .iter()
+                .filter(|&(payload, _)| payload.prev_hash == last_hash)
+                .map(|(payload, sigs)| {
+                         Block {
+                             payload: payload.clone(),
+                             signatures: sigs.clone(),
+                         }
+                     })
+                .find(|block| block.check(chain.last()).is_ok());
+            match next {
+                Some(block) => {
+                    let _ = self.pending.remove(&block.payload);
+                    chain.blocks.push(block);
+                    appended += 1;
+                }
+                None => break,
+            }
+        }
+
+        let superseded: BTreeSet<Digest256> = chain.blocks[..chain.len().saturating_sub(1)]
+            .iter()
+            .filter_map(|block| block.payload.hash().ok())
+            .collect();
+        let stale = self.pending
+            .keys()
+            .filter(|payload| superseded.contains(&payload.prev_hash))
+            .cloned()
+            .collect_vec();
+        for payload in stale {
+            let _ = self.pending.remove(&payload);
+        }
+        appended
+    }
+}
+
+fn is_quorum(votes: usize, voters: usize) -> bool {
+    votes * QUORUM_DENOMINATOR > voters * QUORUM_NUMERATOR
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use id::FullId;
+    use routing_table::Prefix;
+    use rust_sodium::crypto::sign::Signature;
+    use std::collections::BTreeMap;
+
+    fn sign_all(payload: &BlockPayload, full_ids: &[FullId]) -> Vec<(PublicId, Signature)> {
+        full_ids
+            .iter()
+            .map(|full_id| (*full_id.public_id(), unwrap!(payload.sign(full_id))))
+            .collect()
+    }
+
+    fn next_payload(chain: &DataChain, full_ids: &[FullId]) -> BlockPayload {
+        let prefix = unwrap!(chain.last()).payload().prefix;
+        let members = full_ids.iter().map(|full_id| *full_id.public_id()).collect();
+        BlockPayload::new(unwrap!(unwrap!(chain.last_hash())), prefix, members, BTreeMap::new())
+    }
+
+    #[test]
+    fn extend_and_verify() {
+        let full_ids = (0..5).map(|_| FullId::new()).collect::<Vec<_>>();
+        let mut chain = unwrap!(DataChain::genesis(Prefix::default(), &full_ids[0]));
+        let genesis_hash = unwrap!(unwrap!(chain.genesis_hash()));
+        let mut accumulator = BlockAccumulator::default();
+
+        // Members join one at a time. Each block needs a quorum of the previous members.
+        for count in 2..(full_ids.len() + 1) {
+            let payload = next_payload(&chain, &full_ids[..count]);
+            let sigs = sign_all(&payload, &full_ids[..(count - 1)]);
+            let quorum = (count - 1) / 2 + 1;
+            for &(pub_id, sig) in &sigs[..(quorum - 1)] {
+                accumulator.add_signature(payload.clone(), pub_id, sig);
+                assert_eq!(accumulator.extend_chain(&mut chain), 0);
+            }
+            let (pub_id, sig) = sigs[quorum - 1];
+            accumulator.add_signature(payload.clone(), pub_id, sig);
+            assert_eq!(accumulator.extend_chain(&mut chain), 1);
+            assert!(chain.is_current(&payload));
+        }
+        assert_eq!(chain.len(), full_ids.len());
+        unwrap!(chain.verify(&genesis_hash));
+
+        // A chain with a different genesis block doesn't verify.
+        let other_chain = unwrap!(DataChain::genesis(Prefix::default(), &full_ids[1]));
+        assert!(other_chain.verify(&genesis_hash).is_err());
+
+        // A quorum of the latest members' signatures is accepted, fewer are not.
+        let data = b"data";
+        let mut signatures = BTreeMap::new();
+        for full_id in &full_ids[..2] {
+            let sig = sign::sign_detached(data, full_id.signing_private_key());
+            let _ = signatures.insert(*full_id.public_id(), sig);
+        }
+        assert!(!chain.verify_section_signatures(data, &signatures));
+        let sig = sign::sign_detached(data, full_ids[2].signing_private_key());
+        let _ = signatures.insert(*full_ids[2].public_id(), sig);
+        assert!(chain.verify_section_signatures(data, &signatures));
+    }
+
+    #[test]
+    fn ages_and_relocation() {
+        let full_ids = (0..3).map(|_| FullId::new()).collect::<Vec<_>>();
+        let mut chain = unwrap!(DataChain::genesis(Prefix::default(), &full_ids[0]));
+        let mut accumulator = BlockAccumulator::default();
+        let mut append = |chain: &mut DataChain, members: &[FullId]| {
+            let payload = next_payload(chain, members);
+            let prev_members = unwrap!(chain.last()).payload().members.clone();
+            let signatories = members
+                .iter()
+                .filter(|full_id| prev_members.contains(full_id.public_id()))
+                .cloned()
+                .collect::<Vec<_>>();
+            for (pub_id, sig) in sign_all(&payload, &signatories) {
+                accumulator.add_signature(payload.clone(), pub_id, sig);
+            }
+            assert_eq!(accumulator.extend_chain(chain), 1);
+        };
+
+        append(&mut chain, &full_ids[..2]);
+        append(&mut chain, &full_ids);
+        let ages = chain.member_ages();
+        assert_eq!(ages[full_ids[0].public_id()], 2);
+        assert_eq!(ages[full_ids[1].public_id()], 1);
+        assert_eq!(ages[full_ids[2].public_id()], 0);
+
+        // The oldest members are the elders.
+        let elders = chain.elders(2);
+        assert_eq!(elders.len(), 2);
+        assert!(!elders.contains(full_ids[2].public_id()));
+        assert_eq!(chain.elders(5).len(), 3);
+
+        // Leaving resets a member's age.
+        append(&mut chain, &full_ids[..2]);
+        append(&mut chain, &full_ids);
+        assert_eq!(chain.member_ages()[full_ids[2].public_id()], 0);
+
+        // The oldest member is relocated once it reaches the relocation age.
+        while chain.member_ages()[full_ids[0].public_id()] < RELOCATION_AGE {
+            assert_eq!(chain.relocation_candidate(), None);
+            let members = if chain.len() % 2 == 0 {
+                &full_ids[..2]
+            } else {
+                &full_ids[..]
+            };
+            append(&mut chain, members);
+        }
+        assert_eq!(chain.relocation_candidate(), Some(*full_ids[0].public_id()));
+    }
+
+    #[test]
+    fn carried_ages() {
+        let full_ids = (0..3).map(|_| FullId::new()).collect::<Vec<_>>();
+        let mut chain = unwrap!(DataChain::genesis(Prefix::default(), &full_ids[0]));
+        let genesis_hash = unwrap!(unwrap!(chain.genesis_hash()));
+        let mut accumulator = BlockAccumulator::default();
+
+        // The second member is relocated to this section and keeps its age.
+        let mut payload = next_payload(&chain, &full_ids[..2]);
+        let _ = payload.ages.insert(*full_ids[1].public_id(), RELOCATION_AGE);
+        for (pub_id, sig) in sign_all(&payload, &full_ids[..1]) {
+            accumulator.add_signature(payload.clone(), pub_id, sig);
+        }
+        assert_eq!(accumulator.extend_chain(&mut chain), 1);
+        unwrap!(chain.verify(&genesis_hash));
+        let ages = chain.member_ages();
+        assert_eq!(ages[full_ids[0].public_id()], 1);
+        assert_eq!(ages[full_ids[1].public_id()], RELOCATION_AGE);
+
+        // It isn't relocated again before it has spent `RELOCATION_AGE` churn events here.
+        assert_eq!(chain.relocation_candidate(), None);
+
+        // Members which were already listed can't carry over an age.
+        let mut payload = next_payload(&chain, &full_ids);
+        let _ = payload.ages.insert(*full_ids[0].public_id(), RELOCATION_AGE);
+        for (pub_id, sig) in sign_all(&payload, &full_ids[..2]) {
+            accumulator.add_signature(payload.clone(), pub_id, sig);
+        }
+        assert_eq!(accumulator.extend_chain(&mut chain), 0);
+    }
+
+    #[test]
+    fn tampered_chain_fails_verification() {
+        let full_ids = (0..3).map(|_| FullId::new()).collect::<Vec<_>>();
+        let mut chain = unwrap!(DataChain::genesis(Prefix::default(), &full_ids[0]));
+        let genesis_hash = unwrap!(unwrap!(chain.genesis_hash()));
+        let mut accumulator = BlockAccumulator::default();
+        let payload = next_payload(&chain, &full_ids);
+        for (pub_id, sig) in sign_all(&payload, &full_ids[..1]) {
+            accumulator.add_signature(payload.clone(), pub_id, sig);
+        }
+        assert_eq!(accumulator.extend_chain(&mut chain), 1);
+
+        // Replacing the members invalidates the signatures.
+        let mut tampered = chain.clone();
+        let _ = tampered.blocks[1]
+            .payload
+            .members
+            .remove(full_ids[2].public_id());
+        assert!(tampered.verify(&genesis_hash).is_err());
+
+        // Signatures by non-members don't count.
+        let mut tampered = chain.clone();
+        let outsider = FullId::new();
+        let payload = tampered.blocks[1].payload.clone();
+        tampered.blocks[1].signatures = sign_all(&payload, &[outsider]).into_iter().collect();
+        assert!(tampered.verify(&genesis_hash).is_err());
+
+        unwrap!(chain.verify(&genesis_hash));
+    }
+
+    #[test]
+    fn transfer_in_pages() {
+        let full_ids = (0..3).map(|_| FullId::new()).collect::<Vec<_>>();
+        let mut chain = unwrap!(DataChain::genesis(Prefix::default(), &full_ids[0]));
+        let genesis_hash = unwrap!(unwrap!(chain.genesis_hash()));
+        let mut accumulator = BlockAccumulator::default();
+        while chain.len() <= 2 * MAX_BLOCKS_PER_PAGE {
+            let members = if chain.len() % 2 == 0 {
+                &full_ids[..2]
+            } else {
+                &full_ids[..]
+            };
+            let payload = next_payload(&chain, members);
+            for (pub_id, sig) in sign_all(&payload, &full_ids[..2]) {
+                accumulator.add_signature(payload.clone(), pub_id, sig);
+            }
+            assert_eq!(accumulator.extend_chain(&mut chain), 1);
+        }
+
+        // A new member catches up page by page.
+        let mut copy = DataChain::default();
+        while copy.len() < chain.len() {
+            let page = chain.page(copy.len() as u64);
+            assert!(page.blocks.len() <= MAX_BLOCKS_PER_PAGE);
+            assert_eq!(page.chain_len, chain.len() as u64);
+            assert!(unwrap!(copy.extend_from_page(page, &genesis_hash)) > 0);
+        }
+        assert_eq!(copy, chain);
+
+        // Pages overlapping the blocks we have are fine as long as they match ours.
+        assert_eq!(unwrap!(copy.extend_from_page(chain.page(1), &genesis_hash)), 0);
+        let other_chain = unwrap!(DataChain::genesis(Prefix::default(), &full_ids[1]));
+        assert!(copy.extend_from_page(other_chain.page(0), &genesis_hash).is_err());
+
+        // A genesis block other than the trusted one is rejected.
+        let mut copy = DataChain::default();
+        assert!(copy.extend_from_page(other_chain.page(0), &genesis_hash).is_err());
+
+        // So are pages which skip blocks, or contain too many.
+        assert!(copy.extend_from_page(chain.page(1), &genesis_hash).is_err());
+        let mut page = chain.page(0);
+        page.blocks = chain.blocks().to_vec();
+        assert!(copy.extend_from_page(page, &genesis_hash).is_err());
+        assert!(copy.is_empty());
+    }
+}
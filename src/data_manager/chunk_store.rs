@@ -0,0 +1,346 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use data::{Data, DataIdentifier};
+use error::RoutingError;
+use hex::{FromHex, ToHex};
+use maidsafe_utilities::serialisation::{deserialise, serialise};
+use resource_prover::RESOURCE_PROOF_TARGET_SIZE;
+use routing_table::Prefix;
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use xor_name::XorName;
+
+/// The default maximum size of a chunk store, in bytes: the amount of storage a node proves it can
+/// provide when it joins the network.
+pub const DEFAULT_CHUNK_STORE_SIZE: u64 = RESOURCE_PROOF_TARGET_SIZE as u64;
+
+/// A storage backend for the data held by a `DataManager`.
+///
+/// Data is stored in serialised form, and the serialised sizes count towards the store's
+/// `max_space()`.
+pub trait ChunkStore {
+    /// Returns the data with the given identifier, or `None` if it isn't stored.
+    fn get(&self, data_id: &DataIdentifier) -> Result<Option<Data>, RoutingError>;
+
+    /// Stores `data`, replacing any data with the same identifier.
+    ///
+    /// Returns `ChunkStoreFull` if this would exceed the store's maximum size.
+    fn put(&mut self, data: Data) -> Result<(), RoutingError>;
+
+    /// Removes the data with the given identifier, if stored.
+    fn delete(&mut self, data_id: &DataIdentifier) -> Result<(), RoutingError>;
+
+    /// Returns the identifiers of all stored data.
+    fn keys(&self) -> Result<Vec<DataIdentifier>, RoutingError>;
+
+    /// Returns the identifiers of all stored data whose name matches `prefix`.
+    fn keys_with_prefix(&self,
+                        prefix: &Prefix<XorName>)
+                        -> Result<Vec<DataIdentifier>, RoutingError> {
+        Ok(self.keys()?
+               .into_iter()
+               .filter(|data_id| prefix.matches(data_id.name()))
+               .collect())
+    }
+
+    /// Returns the number of bytes currently used.
+    fn used_space(&self) -> u64;
+
+    /// Returns the maximum number of bytes this store may use.
+    fn max_space(&self) -> u64;
+}
+
+/// A `ChunkStore` which keeps all data in memory.
+pub struct MemoryChunkStore {
+    chunks: BTreeMap<DataIdentifier, Vec<u8>>,
+    used_space: u64,
+    max_space: u64,
+}
+
+impl MemoryChunkStore {
+    /// Creates a new, empty store with the default maximum size.
+    pub fn new() -> Self {
+        Self::with_max_space(DEFAULT_CHUNK_STORE_SIZE)
+    }
+
+    /// Creates a new, empty store which may hold up to `max_space` bytes.
+    pub fn with_max_space(max_space: u64) -> Self {
+        MemoryChunkStore {
+            chunks: BTreeMap::new(),
+            used_space: 0,
+            max_space: max_space,
+        }
+    }
+}
+
+impl Default for MemoryChunkStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChunkStore for MemoryChunkStore {
+    fn get(&self, data_id: &DataIdentifier) -> Result<Option<Data>, RoutingError> {
+        match self.chunks.get(data_id) {
+            Some(bytes) => Ok(Some(deserialise(bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn put(&mut self, data: Data) -> Result<(), RoutingError> {
+        let data_id = data.identifier();
+        let bytes = serialise(&data)?;
+        let old_size = self.chunks.get(&data_id).map_or(0, |old| old.len() as u64);
+        let used_space = self.used_space - old_size + bytes.len() as u64;
+        if used_space > self.max_space {
+            return Err(RoutingError::ChunkStoreFull);
+        }
+        self.used_space = used_space;
+        let _ = self.chunks.insert(data_id, bytes);
+        Ok(())
+    }
+
+    fn delete(&mut self, data_id: &DataIdentifier) -> Result<(), RoutingError> {
+        if let Some(bytes) = self.chunks.remove(data_id) {
+            self.used_space -= bytes.len() as u64;
+        }
+        Ok(())
+    }
+
+    fn keys(&self) -> Result<Vec<DataIdentifier>, RoutingError> {
+        Ok(self.chunks.keys().cloned().collect())
+    }
+
+    fn used_space(&self) -> u64 {
+        self.used_space
+    }
+
+    fn max_space(&self) -> u64 {
+        self.max_space
+    }
+}
+
+/// The extension of the temporary file a chunk is written to before it is moved into place.
+const TEMP_FILE_EXTENSION: &'static str = "tmp";
+
+/// A `ChunkStore` which keeps each chunk in a separate file inside a directory.
+///
+/// The file names are derived from the data identifiers, so a store opened on a directory used by
+/// an earlier instance serves the data stored there.
+pub struct DiskChunkStore {
+    root: PathBuf,
+    sizes: BTreeMap<DataIdentifier, u64>,
+    used_space: u64,
+    max_space: u64,
+}
+
+impl DiskChunkStore {
+    /// Opens a store in the directory `root`, creating the directory if it doesn't exist. Data
+    /// already in the directory is kept and counts towards `max_space`. Temporary files left by an
+    /// interrupted `put` are removed.
+    pub fn new<P: Into<PathBuf>>(root: P, max_space: u64) -> Result<Self, RoutingError> {
+        let root = root.into();
+        fs::create_dir_all(&root)?;
+        let mut sizes = BTreeMap::new();
+        for entry in fs::read_dir(&root)? {
+            let entry = entry?;
+            if entry.path().extension().and_then(OsStr::to_str) == Some(TEMP_FILE_EXTENSION) {
+                fs::remove_file(entry.path())?;
+                continue;
+            }
+            match entry.file_name().to_str().and_then(parse_file_name) {
+                Some(data_id) => {
+                    let _ = sizes.insert(data_id, entry.metadata()?.len());
+                }
+                None => debug!("Ignoring unknown file {:?} in chunk store.", entry.path()),
+            }
+        }
+        let used_space = sizes.values().sum();
+        Ok(DiskChunkStore {
+               root: root,
+               sizes: sizes,
+               used_space: used_space,
+               max_space: max_space,
+           })
+    }
+
+    fn file_path(&self, data_id: &DataIdentifier) -> Result<PathBuf, RoutingError> {
+        Ok(self.root.join(serialise(data_id)?.to_hex()))
+    }
+}
+
+impl ChunkStore for DiskChunkStore {
+    fn get(&self, data_id: &DataIdentifier) -> Result<Option<Data>, RoutingError> {
+        if !self.sizes.contains_key(data_id) {
+            return Ok(None);
+        }
+        let mut bytes = Vec::new();
+        let _ = File::open(self.file_path(data_id)?)?.read_to_end(&mut bytes)?;
+        Ok(Some(deserialise(&bytes)?))
+    }
+
+    fn put(&mut self, data: Data) -> Result<(), RoutingError> {
+        let data_id = data.identifier();
+        let bytes = serialise(&data)?;
+        let old_size = self.sizes.get(&data_id).cloned().unwrap_or(0);
+        let used_space = self.used_space - old_size + bytes.len() as u64;
+        if used_space > self.max_space {
+            return Err(RoutingError::ChunkStoreFull);
+        }
+        // Write to a temporary file first, so an interrupted write never leaves a truncated chunk.
+        let path = self.file_path(&data_id)?;
+        let temp_path = path.with_extension(TEMP_FILE_EXTENSION);
+        {
+            let mut file = File::create(&temp_path)?;
+            file.write_all(&bytes)?;
+            file.sync_all()?;
+        }
+        fs::rename(temp_path, path)?;
+        self.used_space = used_space;
+        let _ = self.sizes.insert(data_id, bytes.len() as u64);
+        Ok(())
+    }
+
+    fn delete(&mut self, data_id: &DataIdentifier) -> Result<(), RoutingError> {
+        if let Some(size) = self.sizes.remove(data_id) {
+            fs::remove_file(self.file_path(data_id)?)?;
+            self.used_space -= size;
+        }
+        Ok(())
+    }
+
+    fn keys(&self) -> Result<Vec<DataIdentifier>, RoutingError> {
+        Ok(self.sizes.keys().cloned().collect())
+    }
+
+    fn used_space(&self) -> u64 {
+        self.used_space
+    }
+
+    fn max_space(&self) -> u64 {
+        self.max_space
+    }
+}
+
+// Returns the data identifier encoded in a chunk file's name, or `None` if it isn't a chunk file.
+fn parse_file_name(file_name: &str) -> Option<DataIdentifier> {
+    let bytes: Vec<u8> = match FromHex::from_hex(file_name) {
+        Ok(bytes) => bytes,
+        Err(_) => return None,
+    };
+    deserialise(&bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChunkStore, DiskChunkStore, MemoryChunkStore, TEMP_FILE_EXTENSION};
+    use data::{Data, ImmutableData};
+    use error::RoutingError;
+    use maidsafe_utilities::serialisation::serialise;
+    use rand;
+    use routing_table::Prefix;
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use xor_name::XorName;
+
+    fn random_data(size: usize) -> Data {
+        Data::Immutable(ImmutableData::new((0..size).map(|_| rand::random()).collect()))
+    }
+
+    fn serialised_size(data: &Data) -> u64 {
+        unwrap!(serialise(data)).len() as u64
+    }
+
+    #[test]
+    fn memory_store_respects_max_space() {
+        let data0 = random_data(100);
+        let data1 = random_data(100);
+        let size = serialised_size(&data0);
+        let mut store = MemoryChunkStore::with_max_space(size + size / 2);
+
+        unwrap!(store.put(data0.clone()));
+        assert_eq!(store.used_space(), size);
+        // Replacing a chunk only counts its new size.
+        unwrap!(store.put(data0.clone()));
+        assert_eq!(store.used_space(), size);
+        match store.put(data1.clone()) {
+            Err(RoutingError::ChunkStoreFull) => (),
+            result => panic!("Unexpected result {:?}", result),
+        }
+        assert_eq!(unwrap!(store.get(&data1.identifier())), None);
+
+        unwrap!(store.delete(&data0.identifier()));
+        assert_eq!(store.used_space(), 0);
+        unwrap!(store.put(data1.clone()));
+        assert_eq!(unwrap!(store.get(&data1.identifier())), Some(data1));
+    }
+
+    #[test]
+    fn keys_with_prefix() {
+        let mut store = MemoryChunkStore::new();
+        let data: Vec<_> = (0..10).map(|_| random_data(10)).collect();
+        for item in &data {
+            unwrap!(store.put(item.clone()));
+        }
+        let prefix = Prefix::new(1, XorName([0; 32]));
+        let mut expected: Vec<_> = data.iter()
+            .filter(|item| prefix.matches(item.name()))
+            .map(Data::identifier)
+            .collect();
+        expected.sort();
+        assert_eq!(unwrap!(store.keys_with_prefix(&prefix)), expected);
+    }
+
+    #[test]
+    fn disk_store_survives_restart() {
+        let root = env::temp_dir().join(format!("routing_chunk_store_{:016x}",
+                                                rand::random::<u64>()));
+        let data0 = random_data(100);
+        let data1 = random_data(200);
+        let size = serialised_size(&data0) + serialised_size(&data1);
+        {
+            let mut store = unwrap!(DiskChunkStore::new(root.clone(), size));
+            unwrap!(store.put(data0.clone()));
+            unwrap!(store.put(data1.clone()));
+            assert_eq!(store.used_space(), size);
+            match store.put(random_data(1)) {
+                Err(RoutingError::ChunkStoreFull) => (),
+                result => panic!("Unexpected result {:?}", result),
+            }
+        }
+        {
+            let mut store = unwrap!(DiskChunkStore::new(root.clone(), size));
+            assert_eq!(store.used_space(), size);
+            assert_eq!(unwrap!(store.get(&data0.identifier())), Some(data0.clone()));
+            assert_eq!(unwrap!(store.get(&data1.identifier())), Some(data1.clone()));
+            unwrap!(store.delete(&data0.identifier()));
+        }
+        // A temporary file left by an interrupted write is discarded.
+        let temp_path = root.join("00").with_extension(TEMP_FILE_EXTENSION);
+        unwrap!(unwrap!(File::create(&temp_path)).write_all(&[0; 10]));
+        let store = unwrap!(DiskChunkStore::new(root.clone(), size));
+        assert_eq!(unwrap!(store.keys()), vec![data1.identifier()]);
+        assert_eq!(store.used_space(), size - serialised_size(&data0));
+        assert!(!temp_path.exists());
+        unwrap!(fs::remove_dir_all(root));
+    }
+}
@@ -0,0 +1,961 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+mod chunk_store;
+
+pub use self::chunk_store::{ChunkStore, DEFAULT_CHUNK_STORE_SIZE, DiskChunkStore, MemoryChunkStore};
+use client_errors::{GetError, MutationError};
+use data::{AppendWrapper, Data, DataIdentifier};
+use error::RoutingError;
+use event::Event;
+use key_rotation::KeyRotation;
+use lru_time_cache::LruCache;
+use maidsafe_utilities::serialisation::{deserialise, serialise};
+use messages::{Request, Response};
+use node::Node;
+use routing_table::{Authority, Churn};
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+use types::MessageId;
+use utils;
+use xor_name::XorName;
+
+/// The number of chunks a new client account may store.
+pub const DEFAULT_ACCOUNT_SIZE: u64 = 1000;
+
+/// Time (in seconds) after its expiry during which data is still served and kept, so that nodes
+/// whose clocks differ slightly agree on whether it has expired.
+pub const EXPIRY_GRACE_PERIOD_SECS: u64 = 5 * 60;
+
+/// Duration for which a `Put` forwarded to the data's `NaeManager` awaits a response, in seconds.
+const PENDING_PUT_TIMEOUT_SECS: u64 = 600;
+
+/// A client's account, held by its `ClientManager`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+struct Account {
+    data_stored: u64,
+    space_available: u64,
+    key_rotations: Vec<KeyRotation>,
+}
+
+impl Default for Account {
+    fn default() -> Account {
+        Account {
+            data_stored: 0,
+            space_available: DEFAULT_ACCOUNT_SIZE,
+            key_rotations: vec![],
+        }
+    }
+}
+
+/// A `Put` forwarded by a `ClientManager` to the data's `NaeManager`.
+enum PendingPut {
+    /// A single `Put`, with the `ClientManager` and client authorities to respond to.
+    Single(Authority<XorName>, Authority<XorName>),
+    /// An item of a `PutBatch`, with the batch's message ID and the item's index.
+    BatchItem(MessageId, usize),
+}
+
+/// A `PutBatch` whose items have been forwarded to their `NaeManager`s.
+struct PendingBatch {
+    manager: Authority<XorName>,
+    client: Authority<XorName>,
+    /// The result for each item, or `None` if its `NaeManager` hasn't responded yet.
+    results: Vec<(DataIdentifier, Option<Result<(), Vec<u8>>>)>,
+}
+
+/// The content of the refresh messages used to replicate accounts and data after churn.
+#[derive(Serialize, Deserialize)]
+// FIXME - See https://maidsafe.atlassian.net/browse/MAID-2026 for info on removing this exclusion.
+#[cfg_attr(feature="cargo-clippy", allow(large_enum_variant))]
+enum RefreshContent {
+    Account { client_name: XorName, account: Account },
+    Data(Data),
+}
+
+/// A replicated data store for vaults, built on a `Node`.
+///
+/// The `DataManager` acts as the `ClientManager` for client accounts and as the `NaeManager` for
+/// data. It handles every data request raised by the node, validating mutations against the
+/// stored data, and keeps the close group's copies in sync through churn using refresh messages.
+/// The data itself is kept in a pluggable `ChunkStore`.
+///
+/// Clients send `Put` and `PutBatch` requests to their `ClientManager`, which charges their account
+/// and forwards each item to its `NaeManager`. All other data requests are sent to the
+/// `NaeManager` directly; a `GetBatch` is answered with the items that `NaeManager` holds.
+pub struct DataManager {
+    store: Box<ChunkStore>,
+    accounts: BTreeMap<XorName, Account>,
+    /// `Put` requests forwarded to the data's `NaeManager`, by message ID.
+    pending_puts: LruCache<MessageId, PendingPut>,
+    /// `PutBatch` requests awaiting responses for their items, by message ID.
+    pending_batches: LruCache<MessageId, PendingBatch>,
+    /// The expiry times of stored data, as `(expiry, data_id)` pairs. An entry can be outdated if
+    /// the data was updated or removed since, so the stored data is checked before purging it.
+    expiries: BTreeSet<(u64, DataIdentifier)>,
+}
+
+impl DataManager {
+    /// Creates a new `DataManager` keeping its data in `store`.
+    pub fn new(store: Box<ChunkStore>) -> DataManager {
+        let pending_put_timeout = Duration::from_secs(PENDING_PUT_TIMEOUT_SECS);
+        let expiries = expiry_index(&*store);
+        DataManager {
+            store: store,
+            accounts: BTreeMap::new(),
+            pending_puts: LruCache::with_expiry_duration(pending_put_timeout),
+            pending_batches: LruCache::with_expiry_duration(pending_put_timeout),
+            expiries: expiries,
+        }
+    }
+
+    /// Handles an event raised by `node`.
+    ///
+    /// Data requests and responses are consumed. Churn and tick events are handled and returned,
+    /// as is any other event, so the caller can process them further. Expired data is dropped on
+    /// every tick.
+    pub fn handle_event(&mut self,
+                        node: &mut Node,
+                        event: Event)
+                        -> Result<Option<Event>, RoutingError> {
+        match event {
+            Event::Request { request, src, dst } => self.handle_request(node, request, src, dst),
+            Event::Response { response, src, dst } => {
+                self.handle_response(node, response, src, dst)
+            }
+            Event::NodeAdded(name, churn) => {
+                self.handle_churn(node, MessageId::from_added_node(name), &churn)?;
+                Ok(Some(Event::NodeAdded(name, churn)))
+            }
+            Event::NodeLost(name, churn) => {
+                self.handle_churn(node, MessageId::from_lost_node(name), &churn)?;
+                Ok(Some(Event::NodeLost(name, churn)))
+            }
+            Event::SectionSplit(prefix, churn) => {
+                let id = MessageId::from_added_node(prefix.lower_bound());
+                self.handle_churn(node, id, &churn)?;
+                Ok(Some(Event::SectionSplit(prefix, churn)))
+            }
+            Event::SectionMerge(prefix, churn) => {
+                let id = MessageId::from_lost_node(prefix.lower_bound());
+                self.handle_churn(node, id, &churn)?;
+                Ok(Some(Event::SectionMerge(prefix, churn)))
+            }
+            Event::Tick => {
+                self.purge_expired()?;
+                Ok(Some(Event::Tick))
+            }
+            event => Ok(Some(event)),
+        }
+    }
+
+    fn handle_request(&mut self,
+                      node: &mut Node,
+                      request: Request,
+                      src: Authority<XorName>,
+                      dst: Authority<XorName>)
+                      -> Result<Option<Event>, RoutingError> {
+        match (request, dst) {
+            (Request::Refresh(content, _), _) => self.handle_refresh(&content)?,
+            (Request::Get(data_id, id), Authority::NaeManager(_)) => {
+                self.handle_get(node, data_id, id, src, dst)?
+            }
+            (Request::GetBatch(data_ids, id), Authority::NaeManager(_)) => {
+                self.handle_get_batch(node, data_ids, id, src, dst)?
+            }
+            (Request::Put(data, id), Authority::ClientManager(_)) => {
+                self.handle_client_put(node, data, id, src, dst)?
+            }
+            (Request::PutBatch(data, id), Authority::ClientManager(_)) => {
+                self.handle_client_put_batch(node, data, id, src, dst)?
+            }
+            (Request::Put(data, id), Authority::NaeManager(_)) => {
+                self.handle_put(node, data, id, src, dst)?
+            }
+            (Request::Post(data, id), Authority::NaeManager(_)) => {
+                self.handle_post(node, data, id, src, dst)?
+            }
+            (Request::Delete(data, id), Authority::NaeManager(_)) => {
+                self.handle_delete(node, data, id, src, dst)?
+            }
+            (Request::Append(wrapper, id), Authority::NaeManager(_)) => {
+                self.handle_append(node, wrapper, id, src, dst)?
+            }
+            (Request::GetAccountInfo(id), Authority::ClientManager(_)) => {
+                self.handle_get_account_info(node, id, src, dst)?
+            }
+            (Request::RotateKey(rotation, id), Authority::ClientManager(_)) => {
+                self.handle_rotate_key(node, rotation, id, src, dst)?
+            }
+            (Request::MigrateAccount(rotation, account, id), Authority::ClientManager(_)) => {
+                self.handle_migrate_account(rotation, &account, id, src, dst)?
+            }
+            (request, dst) => {
+                return Ok(Some(Event::Request {
+                                   request: request,
+                                   src: src,
+                                   dst: dst,
+                               }))
+            }
+        }
+        Ok(None)
+    }
+
+    fn handle_response(&mut self,
+                       node: &mut Node,
+                       response: Response,
+                       src: Authority<XorName>,
+                       dst: Authority<XorName>)
+                       -> Result<Option<Event>, RoutingError> {
+        let id = match (&response, dst) {
+            (&Response::PutSuccess(_, id), Authority::ClientManager(_)) |
+            (&Response::PutFailure { id, .. }, Authority::ClientManager(_)) => Some(id),
+            _ => None,
+        };
+        let id = match id {
+            Some(id) => id,
+            None => {
+                return Ok(Some(Event::Response {
+                                   response: response,
+                                   src: src,
+                                   dst: dst,
+                               }))
+            }
+        };
+        let pending_put = match self.pending_puts.remove(&id) {
+            Some(pending_put) => pending_put,
+            None => {
+                debug!("DataManager received a response for unknown Put {:?}.", id);
+                return Ok(None);
+            }
+        };
+        let (data_id, result) = match response {
+            Response::PutSuccess(data_id, _) => (data_id, Ok(())),
+            Response::PutFailure {
+                data_id,
+                external_error_indicator,
+                ..
+            } => (data_id, Err(external_error_indicator)),
+            _ => return Ok(None),
+        };
+        if result.is_ok() {
+            if let Some(account) = self.accounts.get_mut(&dst.name()) {
+                account.data_stored += 1;
+                account.space_available = account.space_available.saturating_sub(1);
+            }
+        }
+        match (pending_put, result) {
+            (PendingPut::Single(manager, client), Ok(())) => {
+                node.send_put_success(manager, client, data_id, id)?
+            }
+            (PendingPut::Single(manager, client), Err(error)) => {
+                node.send_put_failure(manager, client, data_id, error, id)?
+            }
+            (PendingPut::BatchItem(batch_id, index), result) => {
+                self.handle_batch_item_result(node, batch_id, index, result)?
+            }
+        }
+        Ok(None)
+    }
+
+    /// Records the result for an item of a `PutBatch`, and responds to the client once all items
+    /// have one.
+    fn handle_batch_item_result(&mut self,
+                                node: &mut Node,
+                                batch_id: MessageId,
+                                index: usize,
+                                result: Result<(), Vec<u8>>)
+                                -> Result<(), RoutingError> {
+        let mut batch = match self.pending_batches.remove(&batch_id) {
+            Some(batch) => batch,
+            None => {
+                debug!("DataManager received a result for unknown PutBatch {:?}.",
+                       batch_id);
+                return Ok(());
+            }
+        };
+        if let Some(&mut (_, ref mut item_result)) = batch.results.get_mut(index) {
+            *item_result = Some(result);
+        }
+        self.send_batch_response_if_complete(node, batch_id, batch)
+    }
+
+    /// Sends the response to a `PutBatch` if all items have a result, otherwise keeps waiting.
+    fn send_batch_response_if_complete(&mut self,
+                                       node: &mut Node,
+                                       batch_id: MessageId,
+                                       batch: PendingBatch)
+                                       -> Result<(), RoutingError> {
+        if batch.results.iter().any(|&(_, ref result)| result.is_none()) {
+            let _ = self.pending_batches.insert(batch_id, batch);
+            return Ok(());
+        }
+        let results = batch
+            .results
+            .into_iter()
+            .filter_map(|(data_id, result)| result.map(|result| (data_id, result)))
+            .collect();
+        Ok(node.send_put_batch_response(batch.manager, batch.client, results, batch_id)?)
+    }
+
+    /// Returns the data with the given identifier, or the error to respond with if it isn't
+    /// stored, has been deleted or has expired more than `EXPIRY_GRACE_PERIOD_SECS` ago.
+    fn get_stored(&self, data_id: &DataIdentifier) -> Result<Result<Data, GetError>, RoutingError> {
+        match self.store.get(data_id)? {
+            Some(Data::Structured(ref data)) if data.is_deleted() => Ok(Err(GetError::NoSuchData)),
+            Some(ref data) if is_past_grace_period(data, utils::unix_time_secs()) => {
+                Ok(Err(GetError::DataExpired))
+            }
+            Some(data) => Ok(Ok(data)),
+            None => Ok(Err(GetError::NoSuchData)),
+        }
+    }
+
+    /// Removes the data with the given identifier from the store if it has expired more than
+    /// `EXPIRY_GRACE_PERIOD_SECS` ago, so that mutations treat it as nonexistent.
+    fn purge_if_expired(&mut self, data_id: &DataIdentifier) -> Result<(), RoutingError> {
+        let is_expired = match self.store.get(data_id)? {
+            Some(data) => is_past_grace_period(&data, utils::unix_time_secs()),
+            None => false,
+        };
+        if is_expired {
+            self.store.delete(data_id)?;
+        }
+        Ok(())
+    }
+
+    /// Removes all data from the store which expired more than `EXPIRY_GRACE_PERIOD_SECS` ago,
+    /// looking only at the data whose expiry time has passed according to the index.
+    fn purge_expired(&mut self) -> Result<(), RoutingError> {
+        let now = utils::unix_time_secs();
+        let due: Vec<_> = self.expiries
+            .iter()
+            .take_while(|&&(expiry, _)| expiry.saturating_add(EXPIRY_GRACE_PERIOD_SECS) <= now)
+            .cloned()
+            .collect();
+        for entry in due {
+            let _ = self.expiries.remove(&entry);
+            let is_expired = match self.store.get(&entry.1)? {
+                Some(data) => is_past_grace_period(&data, now),
+                None => false,
+            };
+            if is_expired {
+                self.store.delete(&entry.1)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_get(&mut self,
+                  node: &mut Node,
+                  data_id: DataIdentifier,
+                  id: MessageId,
+                  src: Authority<XorName>,
+                  dst: Authority<XorName>)
+                  -> Result<(), RoutingError> {
+        match self.get_stored(&data_id)? {
+            Ok(data) => Ok(node.send_get_success(dst, src, data, id)?),
+            Err(error) => {
+                let error = serialise(&error)?;
+                Ok(node.send_get_failure(dst, src, data_id, error, id)?)
+            }
+        }
+    }
+
+    fn handle_get_batch(&mut self,
+                        node: &mut Node,
+                        data_ids: Vec<DataIdentifier>,
+                        id: MessageId,
+                        src: Authority<XorName>,
+                        dst: Authority<XorName>)
+                        -> Result<(), RoutingError> {
+        let mut results = Vec::with_capacity(data_ids.len());
+        for data_id in data_ids {
+            let result = if !is_managed_by(node, data_id.name(), &dst.name())? {
+                Err(serialise(&GetError::WrongAuthority)?)
+            } else {
+                match self.get_stored(&data_id)? {
+                    Ok(data) => Ok(data),
+                    Err(error) => Err(serialise(&error)?),
+                }
+            };
+            results.push((data_id, result));
+        }
+        Ok(node.send_get_batch_response(dst, src, results, id)?)
+    }
+
+    fn handle_client_put(&mut self,
+                         node: &mut Node,
+                         data: Data,
+                         id: MessageId,
+                         src: Authority<XorName>,
+                         dst: Authority<XorName>)
+                         -> Result<(), RoutingError> {
+        let is_full = self.accounts
+            .entry(dst.name())
+            .or_insert_with(Account::default)
+            .space_available == 0;
+        if is_full {
+            let error = serialise(&MutationError::LowBalance)?;
+            return Ok(node.send_put_failure(dst, src, data.identifier(), error, id)?);
+        }
+        let _ = self.pending_puts.insert(id, PendingPut::Single(dst, src));
+        let nae_manager = Authority::NaeManager(*data.name());
+        Ok(node.send_put_request(dst, nae_manager, data, id)?)
+    }
+
+    fn handle_client_put_batch(&mut self,
+                               node: &mut Node,
+                               data: Vec<Data>,
+                               id: MessageId,
+                               src: Authority<XorName>,
+                               dst: Authority<XorName>)
+                               -> Result<(), RoutingError> {
+        let space_available = self.accounts
+            .entry(dst.name())
+            .or_insert_with(Account::default)
+            .space_available;
+        let mut results = Vec::with_capacity(data.len());
+        for (index, data) in data.into_iter().enumerate() {
+            let data_id = data.identifier();
+            if index as u64 >= space_available {
+                let error = serialise(&MutationError::LowBalance)?;
+                results.push((data_id, Some(Err(error))));
+                continue;
+            }
+            let item_id = MessageId::from_batch_item(&id, index);
+            let _ = self.pending_puts.insert(item_id, PendingPut::BatchItem(id, index));
+            let nae_manager = Authority::NaeManager(*data.name());
+            node.send_put_request(dst, nae_manager, data, item_id)?;
+            results.push((data_id, None));
+        }
+        let batch = PendingBatch {
+            manager: dst,
+            client: src,
+            results: results,
+        };
+        self.send_batch_response_if_complete(node, id, batch)
+    }
+
+    fn handle_put(&mut self,
+                  node: &mut Node,
+                  data: Data,
+                  id: MessageId,
+                  src: Authority<XorName>,
+                  dst: Authority<XorName>)
+                  -> Result<(), RoutingError> {
+        let data_id = data.identifier();
+        self.purge_if_expired(&data_id)?;
+        let result = if !data.validate_size() {
+            Err(MutationError::DataTooLarge)
+        } else if data.is_expired() {
+            Err(MutationError::DataExpired)
+        } else if self.store.get(&data_id)?.is_some() {
+            // Storing the same immutable data twice is harmless, as its name is its hash.
+            match data {
+                Data::Immutable(_) => Ok(()),
+                _ => Err(MutationError::DataExists),
+            }
+        } else {
+            self.put_to_store(data)
+        };
+        match result {
+            Ok(()) => Ok(node.send_put_success(dst, src, data_id, id)?),
+            Err(error) => {
+                let error = serialise(&error)?;
+                Ok(node.send_put_failure(dst, src, data_id, error, id)?)
+            }
+        }
+    }
+
+    fn handle_post(&mut self,
+                   node: &mut Node,
+                   data: Data,
+                   id: MessageId,
+                   src: Authority<XorName>,
+                   dst: Authority<XorName>)
+                   -> Result<(), RoutingError> {
+        let data_id = data.identifier();
+        self.purge_if_expired(&data_id)?;
+        let result = if !data.validate_size() {
+            Err(MutationError::DataTooLarge)
+        } else if data.is_expired() {
+            Err(MutationError::DataExpired)
+        } else {
+            match self.store.get(&data_id)? {
+                Some(stored) => update_data(stored, data),
+                None => Err(MutationError::NoSuchData),
+            }
+        };
+        match result.and_then(|updated| self.put_to_store(updated)) {
+            Ok(()) => Ok(node.send_post_success(dst, src, data_id, id)?),
+            Err(error) => {
+                let error = serialise(&error)?;
+                Ok(node.send_post_failure(dst, src, data_id, error, id)?)
+            }
+        }
+    }
+
+    fn handle_delete(&mut self,
+                     node: &mut Node,
+                     data: Data,
+                     id: MessageId,
+                     src: Authority<XorName>,
+                     dst: Authority<XorName>)
+                     -> Result<(), RoutingError> {
+        let data_id = data.identifier();
+        self.purge_if_expired(&data_id)?;
+        let result = match (self.store.get(&data_id)?, data) {
+            (Some(Data::Structured(ref stored)), _) if stored.is_deleted() => {
+                Err(MutationError::NoSuchData)
+            }
+            (Some(Data::Structured(mut stored)), Data::Structured(new)) => {
+                // Keep the deleted data, so its version can't be reused.
+                stored
+                    .delete_if_valid_successor(&new)
+                    .map(|()| Data::Structured(stored))
+                    .map_err(|_| MutationError::InvalidSuccessor)
+            }
+            (Some(_), _) => Err(MutationError::InvalidOperation),
+            (None, _) => Err(MutationError::NoSuchData),
+        };
+        match result.and_then(|deleted| self.put_to_store(deleted)) {
+            Ok(()) => Ok(node.send_delete_success(dst, src, data_id, id)?),
+            Err(error) => {
+                let error = serialise(&error)?;
+                Ok(node.send_delete_failure(dst, src, data_id, error, id)?)
+            }
+        }
+    }
+
+    fn handle_append(&mut self,
+                     node: &mut Node,
+                     wrapper: AppendWrapper,
+                     id: MessageId,
+                     src: Authority<XorName>,
+                     dst: Authority<XorName>)
+                     -> Result<(), RoutingError> {
+        let data_id = wrapper.identifier();
+        self.purge_if_expired(&data_id)?;
+        let result = match self.store.get(&data_id)? {
+            Some(Data::PubAppendable(mut data)) => {
+                if data.apply_wrapper(wrapper) {
+                    Ok(Data::PubAppendable(data))
+                } else {
+                    Err(MutationError::InvalidSuccessor)
+                }
+            }
+            Some(Data::PrivAppendable(mut data)) => {
+                if data.apply_wrapper(wrapper) {
+                    Ok(Data::PrivAppendable(data))
+                } else {
+                    Err(MutationError::InvalidSuccessor)
+                }
+            }
+            Some(_) => Err(MutationError::InvalidOperation),
+            None => Err(MutationError::NoSuchData),
+        };
+        let result = match result {
+            Ok(ref data) if !data.validate_size() => Err(MutationError::DataTooLarge),
+            Ok(data) => self.put_to_store(data),
+            Err(error) => Err(error),
+        };
+        match result {
+            Ok(()) => Ok(node.send_append_success(dst, src, data_id, id)?),
+            Err(error) => {
+                let error = serialise(&error)?;
+                Ok(node.send_append_failure(dst, src, data_id, error, id)?)
+            }
+        }
+    }
+
+    fn handle_get_account_info(&mut self,
+                               node: &mut Node,
+                               id: MessageId,
+                               src: Authority<XorName>,
+                               dst: Authority<XorName>)
+                               -> Result<(), RoutingError> {
+        if let Some(account) = self.accounts.get(&dst.name()) {
+            return Ok(node.send_get_account_info_success(dst,
+                                                         src,
+                                                         account.data_stored,
+                                                         account.space_available,
+                                                         account.key_rotations.clone(),
+                                                         id)?);
+        }
+        let error = serialise(&GetError::NoSuchAccount)?;
+        Ok(node.send_get_account_info_failure(dst, src, error, id)?)
+    }
+
+    fn handle_rotate_key(&mut self,
+                         node: &mut Node,
+                         rotation: KeyRotation,
+                         id: MessageId,
+                         src: Authority<XorName>,
+                         dst: Authority<XorName>)
+                         -> Result<(), RoutingError> {
+        if let Err(error) = rotation.check_rotate_key_authorities(&src, &dst) {
+            debug!("DataManager received invalid key rotation {:?}: {:?}",
+                   rotation,
+                   error);
+            let error = serialise(&MutationError::InvalidOperation)?;
+            return Ok(node.send_rotate_key_failure(dst, src, error, id)?);
+        }
+        let mut account = match self.accounts.remove(&rotation.old_name()) {
+            Some(account) => account,
+            None => {
+                let error = serialise(&MutationError::NoSuchAccount)?;
+                return Ok(node.send_rotate_key_failure(dst, src, error, id)?);
+            }
+        };
+        account.key_rotations.push(rotation.clone());
+        let new_manager = Authority::ClientManager(rotation.new_name());
+        let content = serialise(&account)?;
+        node.send_migrate_account_request(dst, new_manager, rotation, content, id)?;
+        Ok(node.send_rotate_key_success(dst, src, id)?)
+    }
+
+    fn handle_migrate_account(&mut self,
+                              rotation: KeyRotation,
+                              account: &[u8],
+                              id: MessageId,
+                              src: Authority<XorName>,
+                              dst: Authority<XorName>)
+                              -> Result<(), RoutingError> {
+        rotation.check_migrate_account_authorities(&src, &dst)?;
+        if self.accounts.contains_key(&rotation.new_name()) {
+            debug!("DataManager received MigrateAccount {:?} for existing account {:?}.",
+                   id,
+                   rotation.new_name());
+            return Ok(());
+        }
+        let _ = self.accounts
+            .insert(rotation.new_name(), deserialise(account)?);
+        Ok(())
+    }
+
+    /// Receiving a refresh message means that a quorum of the close group agrees on its content,
+    /// so we update our copy accordingly.
+    fn handle_refresh(&mut self, content: &[u8]) -> Result<(), RoutingError> {
+        let now = utils::unix_time_secs();
+        match deserialise(content)? {
+            RefreshContent::Account {
+                client_name,
+                account,
+            } => {
+                let _ = self.accounts.insert(client_name, account);
+            }
+            RefreshContent::Data(ref data) if is_past_grace_period(data, now) => (),
+            RefreshContent::Data(data) => {
+                let is_newer = match self.store.get(&data.identifier())? {
+                    Some(stored) => version(&data) > version(&stored),
+                    None => true,
+                };
+                if is_newer {
+                    if let Err(error) = self.put_to_store(data) {
+                        debug!("DataManager failed to store refreshed data: {:?}", error);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends refresh messages for the accounts and data which have new holders as a result of the
+    /// churn, and drops the ones we aren't responsible for anymore.
+    fn handle_churn(&mut self,
+                    node: &mut Node,
+                    id: MessageId,
+                    churn: &Churn<XorName>)
+                    -> Result<(), RoutingError> {
+        let account_diff = churn.responsibility_diff(self.accounts.keys());
+        for (client_name, account) in &self.accounts {
+            if account_diff.held.contains(client_name) &&
+               account_diff.hand_over.contains_key(client_name) {
+                let refresh = RefreshContent::Account {
+                    client_name: *client_name,
+                    account: account.clone(),
+                };
+                let auth = Authority::ClientManager(*client_name);
+                node.send_refresh_request(auth, auth, serialise(&refresh)?, id)?;
+            }
+        }
+        for client_name in &account_diff.lost {
+            let _ = self.accounts.remove(client_name);
+        }
+
+        let data_ids = self.store.keys()?;
+        let names: Vec<XorName> = data_ids.iter().map(|data_id| *data_id.name()).collect();
+        let data_diff = churn.responsibility_diff(&names);
+        for data_id in data_ids {
+            if data_diff.lost.contains(data_id.name()) {
+                self.store.delete(&data_id)?;
+            } else if data_diff.hand_over.contains_key(data_id.name()) {
+                if let Some(data) = self.store.get(&data_id)? {
+                    let auth = Authority::NaeManager(*data_id.name());
+                    let refresh = serialise(&RefreshContent::Data(data))?;
+                    node.send_refresh_request(auth, auth, refresh, id)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Stores `data`, reporting a full chunk store as `NetworkFull` to the requester.
+    fn put_to_store(&mut self, data: Data) -> Result<(), MutationError> {
+        let expiry = data.expiry().map(|expiry| (expiry, data.identifier()));
+        match self.store.put(data) {
+            Ok(()) => {
+                if let Some(entry) = expiry {
+                    let _ = self.expiries.insert(entry);
+                }
+                Ok(())
+            }
+            Err(RoutingError::ChunkStoreFull) => Err(MutationError::NetworkFull),
+            Err(error) => Err(MutationError::NetworkOther(format!("{:?}", error))),
+        }
+    }
+}
+
+/// Returns `stored` updated with `new`, if `new` is a valid successor.
+fn update_data(stored: Data, new: Data) -> Result<Data, MutationError> {
+    match (stored, new) {
+        (Data::Structured(ref stored), _) if stored.is_deleted() => Err(MutationError::NoSuchData),
+        (Data::Structured(mut stored), Data::Structured(new)) => {
+            stored
+                .replace_with_other(new)
+                .map(|()| Data::Structured(stored))
+                .map_err(|_| MutationError::InvalidSuccessor)
+        }
+        (Data::PubAppendable(mut stored), Data::PubAppendable(new)) => {
+            stored
+                .update_with_other(new)
+                .map(|()| Data::PubAppendable(stored))
+                .map_err(|_| MutationError::InvalidSuccessor)
+        }
+        (Data::PrivAppendable(mut stored), Data::PrivAppendable(new)) => {
+            stored
+                .update_with_other(new)
+                .map(|()| Data::PrivAppendable(stored))
+                .map_err(|_| MutationError::InvalidSuccessor)
+        }
+        (Data::Mutable(mut stored), Data::Mutable(new)) => {
+            stored.replace_with_other(new).map(|()| Data::Mutable(stored))
+        }
+        (_, _) => Err(MutationError::InvalidOperation),
+    }
+}
+
+/// Returns the version of mutable data, or 0 for immutable data.
+fn version(data: &Data) -> u64 {
+    match *data {
+        Data::Structured(ref data) => data.get_version(),
+        Data::PubAppendable(ref data) => data.get_version(),
+        Data::PrivAppendable(ref data) => data.get_version(),
+        Data::Mutable(ref data) => data.get_version(),
+        Data::Immutable(_) => 0,
+    }
+}
+
+/// Returns whether `data` expired more than `EXPIRY_GRACE_PERIOD_SECS` before `now`, given in
+/// seconds since the Unix epoch.
+fn is_past_grace_period(data: &Data, now: u64) -> bool {
+    data.expiry()
+        .map_or(false, |expiry| expiry.saturating_add(EXPIRY_GRACE_PERIOD_SECS) <= now)
+}
+
+/// Returns the expiry index for the data already in `store`.
+fn expiry_index(store: &ChunkStore) -> BTreeSet<(u64, DataIdentifier)> {
+    let data_ids = match store.keys() {
+        Ok(data_ids) => data_ids,
+        Err(error) => {
+            debug!("DataManager failed to read the chunk store's keys: {:?}", error);
+            return BTreeSet::new();
+        }
+    };
+    data_ids
+        .into_iter()
+        .filter_map(|data_id| match store.get(&data_id) {
+                        Ok(Some(data)) => data.expiry().map(|expiry| (expiry, data_id)),
+                        _ => None,
+                    })
+        .collect()
+}
+
+/// Returns whether data called `name` is managed by the same group as `manager`, according to
+/// `node`'s routing table. All members of the group agree on this, so their responses accumulate.
+fn is_managed_by(node: &Node, name: &XorName, manager: &XorName) -> Result<bool, RoutingError> {
+    let routing_table = node.routing_table()?;
+    let group = |name: &XorName| {
+        routing_table
+            .closest_names(name, routing_table.min_section_size())
+            .map(|names| names.into_iter().collect::<BTreeSet<_>>())
+    };
+    Ok(group(name).is_some() && group(name) == group(manager))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChunkStore, DataManager, EXPIRY_GRACE_PERIOD_SECS, MemoryChunkStore,
+                RefreshContent, update_data, version};
+    use client_errors::{GetError, MutationError};
+    use data::{Data, EntryAction, ImmutableData, MutableData, StructuredData};
+    use maidsafe_utilities::serialisation::serialise;
+    use rand;
+    use rust_sodium::crypto::sign;
+    use std::collections::{BTreeMap, BTreeSet};
+    use utils;
+    use xor_name::XorName;
+
+    fn structured_data(keys: &(sign::PublicKey, sign::SecretKey),
+                       name: XorName,
+                       version: u64)
+                       -> StructuredData {
+        let owners: BTreeSet<_> = Some(keys.0).into_iter().collect();
+        let mut data = unwrap!(StructuredData::new(1, name, version, vec![version as u8], owners));
+        if version > 0 {
+            let _ = unwrap!(data.add_signature(keys));
+        }
+        data
+    }
+
+    #[test]
+    fn update_structured_data() {
+        let keys = sign::gen_keypair();
+        let name = rand::random();
+        let stored = Data::Structured(structured_data(&keys, name, 0));
+
+        let successor = Data::Structured(structured_data(&keys, name, 1));
+        let updated = unwrap!(update_data(stored.clone(), successor.clone()));
+        assert_eq!(updated, successor);
+        assert_eq!(version(&updated), 1);
+
+        // Skipping a version is not allowed.
+        let invalid = Data::Structured(structured_data(&keys, name, 2));
+        assert_eq!(update_data(stored.clone(), invalid),
+                   Err(MutationError::InvalidSuccessor));
+
+        // Neither is signing with a key other than the owner's.
+        let other_keys = sign::gen_keypair();
+        let mut forged = structured_data(&keys, name, 1);
+        forged.replace_signatures(BTreeMap::new());
+        let _ = unwrap!(forged.add_signature(&other_keys));
+        assert_eq!(update_data(stored, Data::Structured(forged)),
+                   Err(MutationError::InvalidSuccessor));
+    }
+
+    #[test]
+    fn update_deleted_or_immutable_data() {
+        let keys = sign::gen_keypair();
+        let name = rand::random();
+        let mut deleted = structured_data(&keys, name, 0);
+        unwrap!(deleted.delete_if_valid_successor(&structured_data(&keys, name, 1)));
+        let successor = Data::Structured(structured_data(&keys, name, 2));
+        assert_eq!(update_data(Data::Structured(deleted), successor),
+                   Err(MutationError::NoSuchData));
+
+        let immutable = Data::Immutable(ImmutableData::new(vec![1, 2, 3]));
+        assert_eq!(update_data(immutable.clone(), immutable),
+                   Err(MutationError::InvalidOperation));
+    }
+
+    #[test]
+    fn update_mutable_data() {
+        let keys = sign::gen_keypair();
+        let owners = Some(keys.0).into_iter().collect();
+        let data =
+            unwrap!(MutableData::new(rand::random(), 1, BTreeMap::new(), BTreeMap::new(), owners));
+
+        let mut successor = data.successor();
+        let actions = Some((vec![1], EntryAction::Insert(vec![2]))).into_iter().collect();
+        unwrap!(successor.mutate_entries(actions));
+        unwrap!(successor.add_signature(&keys));
+        let updated = unwrap!(update_data(Data::Mutable(data.clone()),
+                                          Data::Mutable(successor.clone())));
+        assert_eq!(updated, Data::Mutable(successor));
+        assert_eq!(version(&updated), 1);
+
+        // Mutations by keys without permissions are rejected with a specific error.
+        let mut forged = data.successor();
+        forged.change_owner(sign::gen_keypair().0);
+        unwrap!(forged.add_signature(&sign::gen_keypair()));
+        assert_eq!(update_data(Data::Mutable(data), Data::Mutable(forged)),
+                   Err(MutationError::AccessDenied));
+    }
+
+    fn expiring_data(expiry: Option<u64>) -> Data {
+        let mut data = unwrap!(StructuredData::new(1, rand::random(), 0, vec![], BTreeSet::new()));
+        data.set_expiry(expiry);
+        Data::Structured(data)
+    }
+
+    #[test]
+    fn purge_expired_data() {
+        let mut store = MemoryChunkStore::new();
+        let expired_before_start = expiring_data(Some(1));
+        unwrap!(store.put(expired_before_start.clone()));
+        let mut manager = DataManager::new(Box::new(store));
+
+        let expired = expiring_data(Some(2));
+        let not_expired = expiring_data(Some(u64::max_value()));
+        let permanent = expiring_data(None);
+        for data in &[&expired, &not_expired, &permanent] {
+            unwrap!(manager.put_to_store((*data).clone()));
+        }
+        assert_eq!(manager.expiries.len(), 3);
+
+        unwrap!(manager.purge_expired());
+        assert_eq!(manager.expiries.len(), 1);
+        assert!(unwrap!(manager.store.get(&expired_before_start.identifier())).is_none());
+        assert!(unwrap!(manager.store.get(&expired.identifier())).is_none());
+        assert!(unwrap!(manager.store.get(&not_expired.identifier())).is_some());
+        assert!(unwrap!(manager.store.get(&permanent.identifier())).is_some());
+    }
+
+    #[test]
+    fn expired_data_is_kept_during_grace_period() {
+        let mut manager = DataManager::new(Box::new(MemoryChunkStore::new()));
+        let now = utils::unix_time_secs();
+        let recently_expired = expiring_data(Some(now - 1));
+        let long_expired = expiring_data(Some(now - EXPIRY_GRACE_PERIOD_SECS));
+        for data in &[&recently_expired, &long_expired] {
+            unwrap!(manager.put_to_store((*data).clone()));
+        }
+
+        assert_eq!(unwrap!(manager.get_stored(&recently_expired.identifier())),
+                   Ok(recently_expired.clone()));
+        assert_eq!(unwrap!(manager.get_stored(&long_expired.identifier())),
+                   Err(GetError::DataExpired));
+
+        unwrap!(manager.purge_expired());
+        assert!(unwrap!(manager.store.get(&recently_expired.identifier())).is_some());
+        assert!(unwrap!(manager.store.get(&long_expired.identifier())).is_none());
+    }
+
+    #[test]
+    fn refuse_expired_refresh() {
+        let mut manager = DataManager::new(Box::new(MemoryChunkStore::new()));
+        let expired = expiring_data(Some(1));
+        let not_expired = expiring_data(Some(u64::max_value()));
+        for data in &[&expired, &not_expired] {
+            let refresh = unwrap!(serialise(&RefreshContent::Data((*data).clone())));
+            unwrap!(manager.handle_refresh(&refresh));
+        }
+        assert!(unwrap!(manager.store.get(&expired.identifier())).is_none());
+        assert!(unwrap!(manager.store.get(&not_expired.identifier())).is_some());
+    }
+}
@@ -0,0 +1,285 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use TYPE_TAG_DNS_PACKET;
+use data::{DataIdentifier, StructuredData};
+use error::RoutingError;
+use maidsafe_utilities::serialisation::{deserialise, serialise};
+use rust_sodium::crypto::sign::{self, PublicKey, SecretKey, Signature};
+use std::collections::{BTreeMap, BTreeSet};
+use tiny_keccak::sha3_256;
+use xor_name::XorName;
+
+/// The maximum length of a domain name, in bytes.
+pub const MAX_DOMAIN_LEN: usize = 253;
+/// The maximum length of a single label of a domain name, in bytes.
+pub const MAX_LABEL_LEN: usize = 63;
+
+/// Returns the canonical form of `domain`: lower case and without a trailing dot.
+///
+/// A domain consists of labels separated by dots. Each label must be 1 to `MAX_LABEL_LEN` ASCII
+/// letters, digits or hyphens, and must not start or end with a hyphen.
+pub fn normalise_domain(domain: &str) -> Result<String, RoutingError> {
+    let domain = if domain.ends_with('.') {
+        &domain[..domain.len() - 1]
+    } else {
+        domain
+    };
+    let domain = domain.to_lowercase();
+    if domain.is_empty() || domain.len() > MAX_DOMAIN_LEN {
+        return Err(RoutingError::InvalidDomainName);
+    }
+    for label in domain.split('.') {
+        if label.is_empty() || label.len() > MAX_LABEL_LEN || label.starts_with('-') ||
+           label.ends_with('-') || !label.chars().all(is_label_char) {
+            return Err(RoutingError::InvalidDomainName);
+        }
+    }
+    Ok(domain)
+}
+
+/// Returns the name of the `StructuredData` holding the DNS record for `domain`: the SHA3-256 hash
+/// of its canonical form. So `"Example.com"` and `"example.com."` resolve to the same record.
+pub fn dns_name(domain: &str) -> Result<XorName, RoutingError> {
+    let domain = normalise_domain(domain)?;
+    Ok(XorName(sha3_256(domain.as_bytes())))
+}
+
+fn is_label_char(c: char) -> bool {
+    match c {
+        'a'...'z' | '0'...'9' | '-' => true,
+        _ => false,
+    }
+}
+
+/// A service published under a domain, e.g. `"www"` or `"email"`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Service {
+    /// The data holding the service's content, e.g. a serialised data map.
+    pub data_id: DataIdentifier,
+    /// The key of the service's operator, e.g. to verify its content or to contact it.
+    pub public_key: PublicKey,
+}
+
+/// A DNS record mapping the services of a domain to data identifiers and public keys.
+///
+/// The record is signed by its owner, who must also be the sole owner of the `StructuredData` of
+/// type `TYPE_TAG_DNS_PACKET` that it is stored in. The data's name is `dns_name(domain)`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DnsRecord {
+    domain: String,
+    owner: PublicKey,
+    services: BTreeMap<String, Service>,
+    signature: Signature,
+}
+
+impl DnsRecord {
+    /// Creates a record for `domain`, owned and signed by `keys`.
+    pub fn new(domain: &str,
+               services: BTreeMap<String, Service>,
+               keys: &(PublicKey, SecretKey))
+               -> Result<DnsRecord, RoutingError> {
+        let domain = normalise_domain(domain)?;
+        let signature = {
+            let payload = serialise(&(&domain, &keys.0, &services))?;
+            sign::sign_detached(&payload, &keys.1)
+        };
+        Ok(DnsRecord {
+               domain: domain,
+               owner: keys.0,
+               services: services,
+               signature: signature,
+           })
+    }
+
+    /// Extracts the record from `data`, checking that it is a valid DNS record owned by the
+    /// data's owner.
+    pub fn from_structured_data(data: &StructuredData) -> Result<DnsRecord, RoutingError> {
+        if data.get_type_tag() != TYPE_TAG_DNS_PACKET {
+            return Err(RoutingError::InvalidDnsRecord);
+        }
+        let record: DnsRecord = deserialise(data.get_data())?;
+        if normalise_domain(&record.domain)? != record.domain ||
+           dns_name(&record.domain)? != *data.name() {
+            return Err(RoutingError::InvalidDnsRecord);
+        }
+        if data.get_owners().len() != 1 || !data.get_owners().contains(&record.owner) {
+            return Err(RoutingError::InvalidOwners);
+        }
+        record.verify()?;
+        Ok(record)
+    }
+
+    /// Returns a `StructuredData` with the given `version` containing this record, owned by the
+    /// record's owner and signed by `keys`.
+    ///
+    /// The network only accepts the data if `keys` belong to the owner of the data it replaces, or
+    /// to the record's owner if the domain is new. So to transfer a domain, the current owner
+    /// signs a record owned by the new owner.
+    pub fn to_structured_data(&self,
+                              version: u64,
+                              keys: &(PublicKey, SecretKey))
+                              -> Result<StructuredData, RoutingError> {
+        let owners: BTreeSet<_> = Some(self.owner).into_iter().collect();
+        let mut data = StructuredData::new(TYPE_TAG_DNS_PACKET,
+                                           dns_name(&self.domain)?,
+                                           version,
+                                           serialise(self)?,
+                                           owners)?;
+        let _ = data.add_signature(keys)?;
+        Ok(data)
+    }
+
+    /// Checks the owner's signature.
+    pub fn verify(&self) -> Result<(), RoutingError> {
+        let payload = serialise(&(&self.domain, &self.owner, &self.services))?;
+        if sign::verify_detached(&self.signature, &payload, &self.owner) {
+            Ok(())
+        } else {
+            Err(RoutingError::FailedSignature)
+        }
+    }
+
+    /// Returns the canonical domain name.
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    /// Returns the owner's public signing key.
+    pub fn owner(&self) -> &PublicKey {
+        &self.owner
+    }
+
+    /// Returns all services, by name.
+    pub fn services(&self) -> &BTreeMap<String, Service> {
+        &self.services
+    }
+
+    /// Returns the service with the given name, if any.
+    pub fn service(&self, name: &str) -> Option<&Service> {
+        self.services.get(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use TYPE_TAG_DNS_PACKET;
+    use data::{DataIdentifier, StructuredData};
+    use error::RoutingError;
+    use maidsafe_utilities::serialisation::serialise;
+    use rand;
+    use rust_sodium::crypto::sign;
+    use std::collections::BTreeMap;
+
+    fn services() -> BTreeMap<String, Service> {
+        let service = Service {
+            data_id: DataIdentifier::Immutable(rand::random()),
+            public_key: sign::gen_keypair().0,
+        };
+        Some(("www".to_owned(), service)).into_iter().collect()
+    }
+
+    #[test]
+    fn domain_names() {
+        assert_eq!(unwrap!(normalise_domain("Example.COM.")), "example.com");
+        assert_eq!(unwrap!(dns_name("example.com")),
+                   unwrap!(dns_name("EXAMPLE.com.")));
+        assert!(unwrap!(dns_name("example.com")) != unwrap!(dns_name("example.org")));
+
+        let too_long = vec!["a"; MAX_DOMAIN_LEN / 2 + 2].join(".");
+        let long_label: String = (0..MAX_LABEL_LEN + 1).map(|_| 'a').collect();
+        for domain in &["", ".", "a..b", "-a.com", "a-.com", "a_b.com", "ä.com",
+                        too_long.as_str(), long_label.as_str()] {
+            match normalise_domain(domain) {
+                Err(RoutingError::InvalidDomainName) => (),
+                result => panic!("Unexpected result for {:?}: {:?}", domain, result),
+            }
+        }
+    }
+
+    #[test]
+    fn record_round_trip() {
+        let keys = sign::gen_keypair();
+        let record = unwrap!(DnsRecord::new("Example.com", services(), &keys));
+        assert_eq!(record.domain(), "example.com");
+        assert!(record.service("www").is_some());
+
+        let data = unwrap!(record.to_structured_data(0, &keys));
+        assert_eq!(*data.name(), unwrap!(dns_name("example.com")));
+        assert_eq!(unwrap!(DnsRecord::from_structured_data(&data)), record);
+
+    }
+
+    #[test]
+    fn transfer_record() {
+        let keys = sign::gen_keypair();
+        let record = unwrap!(DnsRecord::new("example.com", services(), &keys));
+        let data = unwrap!(record.to_structured_data(0, &keys));
+
+        // The current owner signs the data holding the new owner's record.
+        let new_keys = sign::gen_keypair();
+        let transferred = unwrap!(DnsRecord::new("example.com", services(), &new_keys));
+        let new_data = unwrap!(transferred.to_structured_data(1, &keys));
+        unwrap!(data.validate_self_against_successor(&new_data));
+        assert_eq!(unwrap!(DnsRecord::from_structured_data(&new_data)), transferred);
+
+        // Signed by the new owner alone, the transfer is invalid.
+        let new_data = unwrap!(transferred.to_structured_data(1, &new_keys));
+        assert!(data.validate_self_against_successor(&new_data).is_err());
+    }
+
+    #[test]
+    fn invalid_records() {
+        let keys = sign::gen_keypair();
+        let record = unwrap!(DnsRecord::new("example.com", services(), &keys));
+
+        // A record with forged services is rejected.
+        let mut forged = record.clone();
+        forged.services = services();
+        let data = unwrap!(forged.to_structured_data(0, &keys));
+        match DnsRecord::from_structured_data(&data) {
+            Err(RoutingError::FailedSignature) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        // A record stored under another domain's name is rejected.
+        let other_name = unwrap!(dns_name("example.org"));
+        let owners = Some(keys.0).into_iter().collect();
+        let data = unwrap!(StructuredData::new(TYPE_TAG_DNS_PACKET,
+                                               other_name,
+                                               0,
+                                               unwrap!(serialise(&record)),
+                                               owners));
+        match DnsRecord::from_structured_data(&data) {
+            Err(RoutingError::InvalidDnsRecord) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        // A record stored in data belonging to someone else is rejected.
+        let other_owners = Some(sign::gen_keypair().0).into_iter().collect();
+        let data = unwrap!(StructuredData::new(TYPE_TAG_DNS_PACKET,
+                                               unwrap!(dns_name("example.com")),
+                                               0,
+                                               unwrap!(serialise(&record)),
+                                               other_owners));
+        match DnsRecord::from_structured_data(&data) {
+            Err(RoutingError::InvalidOwners) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+}
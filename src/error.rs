@@ -22,6 +22,7 @@ use event::Event;
 use maidsafe_utilities::event_sender::{EventSenderError, MaidSafeEventCategory};
 use maidsafe_utilities::serialisation;
 use std::sync::mpsc::{RecvError, SendError};
+use xor_name::XorName;
 
 /// The type of errors that can occur if routing is unable to handle a send request.
 #[derive(Debug)]
@@ -125,6 +126,28 @@ pub enum RoutingError {
     CandidateIsTunnelling,
     /// Content of a received message is inconsistent.
     InvalidMessage,
+    /// Received a plaintext message on a connection that is encrypted.
+    UnencryptedMessage,
+    /// The peer doesn't agree to encrypt the connection, but we require it.
+    EncryptionRefused,
+    /// Stored keys could not be decrypted: the passphrase is missing or wrong.
+    InvalidPassphrase,
+    /// Stored keys use an unknown format version.
+    UnknownKeyFormat(u16),
+    /// Stored secret keys are malformed or don't match the public keys.
+    InvalidKeys,
+    /// Storing the data would exceed the chunk store's maximum size.
+    ChunkStoreFull,
+    /// A self-encrypted chunk could not be retrieved.
+    ChunkNotFound(XorName),
+    /// A self-encrypted chunk doesn't match its data map.
+    InvalidChunk(XorName),
+    /// A self-encryption data map lists fewer than three chunks.
+    InvalidDataMap,
+    /// A domain name is empty, too long or contains invalid characters.
+    InvalidDomainName,
+    /// A DNS record is malformed or doesn't match the `StructuredData` it is stored in.
+    InvalidDnsRecord,
 }
 
 impl From<RoutingTableError> for RoutingError {
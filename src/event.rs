@@ -15,10 +15,15 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software.
 
+use id::PublicId;
 use messages::{Request, Response};
-use routing_table::{Prefix, RoutingTable};
+use routing_table::{Churn, Prefix};
 use routing_table::Authority;
+use rust_sodium::crypto::sign;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{self, Debug, Formatter};
+use types::MessageId;
+use utils;
 use xor_name::XorName;
 
 /// An Event raised by a `Node` or `Client` via its event sender.
@@ -50,15 +55,69 @@ pub enum Event {
         /// The destination authority that receives the response.
         dst: Authority<XorName>,
     },
-    /// A node has connected to us.
-    NodeAdded(XorName, RoutingTable<XorName>),
-    /// A node has disconnected from us.
-    NodeLost(XorName, RoutingTable<XorName>),
+    /// Received a response from a section authority, but it could not be verified to be signed by
+    /// a quorum of that section's members. Only raised by clients created with
+    /// `Client::with_response_verification`, in place of `Event::Response`.
+    ResponseVerificationFailed {
+        /// The unverified response message.
+        response: Response,
+        /// The source authority the response claims to come from.
+        src: Authority<XorName>,
+        /// The destination authority that receives the response.
+        dst: Authority<XorName>,
+    },
+    /// A node has connected to us. The `Churn` holds our routing table before and after the change.
+    NodeAdded(XorName, Churn<XorName>),
+    /// A node has disconnected from us. The `Churn` holds our routing table before and after the
+    /// change.
+    NodeLost(XorName, Churn<XorName>),
     /// Our own section has been split, resulting in the included `Prefix` for our new section.
-    SectionSplit(Prefix<XorName>),
+    SectionSplit(Prefix<XorName>, Churn<XorName>),
     /// Our own section requires merged with others, resulting in the included `Prefix` for our new
     /// section.
-    SectionMerge(Prefix<XorName>),
+    SectionMerge(Prefix<XorName>, Churn<XorName>),
+    /// The sections we know of other than our own have changed, as a result of remote sections
+    /// splitting, merging or updating their versions. Each map contains section prefixes with their
+    /// versions.
+    RemoteSectionsChanged {
+        /// Sections which were added, with their versions.
+        added: BTreeMap<Prefix<XorName>, u64>,
+        /// Sections which were removed, with the last versions we knew of.
+        removed: BTreeMap<Prefix<XorName>, u64>,
+        /// Sections which remained, but whose versions changed, with their new versions.
+        updated: BTreeMap<Prefix<XorName>, u64>,
+    },
+    /// Another member of our section proposed a value for agreement. Use `Node::vote` to support
+    /// it.
+    SectionProposal {
+        /// The proposed value.
+        value: Vec<u8>,
+        /// The name of the node which proposed it.
+        proposer: XorName,
+    },
+    /// A quorum of our section voted for a value.
+    SectionAgreement {
+        /// The agreed value.
+        value: Vec<u8>,
+        /// The names of the nodes which voted for it.
+        voters: BTreeSet<XorName>,
+    },
+    /// A proposed value didn't reach quorum in time and has been dropped.
+    SectionAgreementTimeout(Vec<u8>),
+    /// The section managing the name we asked about with `send_section_info_request` replied. A
+    /// quorum of its members has signed the prefix and member list.
+    SectionInfo {
+        /// The section's prefix.
+        prefix: Prefix<XorName>,
+        /// The section's version.
+        version: u64,
+        /// The section's members.
+        members: BTreeSet<PublicId>,
+        /// The members' signatures of the prefix and member list.
+        signatures: BTreeMap<PublicId, sign::Signature>,
+        /// The identifier of the request.
+        message_id: MessageId,
+    },
     /// The client has successfully connected to a proxy node on the network.
     Connected,
     /// Disconnected or failed to connect - restart required.
@@ -95,19 +154,80 @@ impl Debug for Event {
                        src,
                        dst)
             }
+            Event::ResponseVerificationFailed {
+                ref response,
+                ref src,
+                ref dst,
+            } => {
+                write!(formatter,
+                       "Event::ResponseVerificationFailed {{ response: {:?}, src: {:?}, dst: \
+                        {:?} }}",
+                       response,
+                       src,
+                       dst)
+            }
             Event::NodeAdded(ref node_name, _) => {
                 write!(formatter,
-                       "Event::NodeAdded({:?}, routing_table)",
+                       "Event::NodeAdded({:?}, churn)",
                        node_name)
             }
             Event::NodeLost(ref node_name, _) => {
-                write!(formatter, "Event::NodeLost({:?}, routing_table)", node_name)
+                write!(formatter, "Event::NodeLost({:?}, churn)", node_name)
+            }
+            Event::SectionSplit(ref prefix, _) => {
+                write!(formatter, "Event::SectionSplit({:?}, churn)", prefix)
+            }
+            Event::SectionMerge(ref prefix, _) => {
+                write!(formatter, "Event::SectionMerge({:?}, churn)", prefix)
+            }
+            Event::RemoteSectionsChanged {
+                ref added,
+                ref removed,
+                ref updated,
+            } => {
+                write!(formatter,
+                       "Event::RemoteSectionsChanged {{ added: {:?}, removed: {:?}, updated: \
+                        {:?} }}",
+                       added,
+                       removed,
+                       updated)
+            }
+            Event::SectionProposal {
+                ref value,
+                ref proposer,
+            } => {
+                write!(formatter,
+                       "Event::SectionProposal {{ {}, proposer: {:?} }}",
+                       utils::format_binary_array(value),
+                       proposer)
+            }
+            Event::SectionAgreement {
+                ref value,
+                ref voters,
+            } => {
+                write!(formatter,
+                       "Event::SectionAgreement {{ {}, voters: {:?} }}",
+                       utils::format_binary_array(value),
+                       voters)
             }
-            Event::SectionSplit(ref prefix) => {
-                write!(formatter, "Event::SectionSplit({:?})", prefix)
+            Event::SectionAgreementTimeout(ref value) => {
+                write!(formatter,
+                       "Event::SectionAgreementTimeout({})",
+                       utils::format_binary_array(value))
             }
-            Event::SectionMerge(ref prefix) => {
-                write!(formatter, "Event::SectionMerge({:?})", prefix)
+            Event::SectionInfo {
+                ref prefix,
+                version,
+                ref members,
+                ref message_id,
+                ..
+            } => {
+                write!(formatter,
+                       "Event::SectionInfo {{ {:?}, version: {}, members: {:?}, {:?} }}",
+                       prefix,
+                       version,
+                       members,
+                       message_id)
             }
             Event::Connected => write!(formatter, "Event::Connected"),
             Event::RestartRequired => write!(formatter, "Event::RestartRequired"),
@@ -15,11 +15,43 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software.
 
-use rust_sodium::crypto::{box_, hash, sign};
+use error::RoutingError;
+use maidsafe_utilities::serialisation::{deserialise, serialise};
+use rust_sodium::crypto::{box_, hash, pwhash, secretbox, sign};
+use rust_sodium::crypto::scalarmult::curve25519;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt::{self, Debug, Formatter};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
 use xor_name::XorName;
 
+/// The current version of the format written by `FullId::to_bytes`.
+const FULL_ID_FORMAT_VERSION: u16 = 1;
+
+/// Version 1 of the stored `FullId`.
+#[derive(Serialize, Deserialize)]
+struct StoredFullIdV1 {
+    /// The public keys, always in plaintext, so the identity can be inspected without the
+    /// passphrase.
+    public_id: PublicId,
+    /// The private encryption key followed by the private signing key.
+    secret_keys: StoredSecretKeys,
+}
+
+#[derive(Serialize, Deserialize)]
+enum StoredSecretKeys {
+    Plain(Vec<u8>),
+    Encrypted {
+        /// Salt for deriving the symmetric key from the passphrase.
+        salt: [u8; pwhash::SALTBYTES],
+        nonce: [u8; secretbox::NONCEBYTES],
+        ciphertext: Vec<u8>,
+    },
+}
+
 /// Network identity component containing name, and public and private keys.
 #[derive(Clone)]
 pub struct FullId {
@@ -86,6 +118,107 @@ impl FullId {
     pub fn encrypting_private_key(&self) -> &box_::SecretKey {
         &self.private_encrypt_key
     }
+
+    /// Serialises the keys into a stable, versioned format.
+    ///
+    /// If a `passphrase` is given, the secret keys are encrypted with a key derived from it. The
+    /// public keys are always stored in plaintext.
+    pub fn to_bytes(&self, passphrase: Option<&str>) -> Result<Vec<u8>, RoutingError> {
+        let mut plain = Vec::with_capacity(box_::SECRETKEYBYTES + sign::SECRETKEYBYTES);
+        plain.extend_from_slice(&self.private_encrypt_key.0);
+        plain.extend_from_slice(&self.private_sign_key.0);
+
+        let secret_keys = if let Some(passphrase) = passphrase {
+            let salt = pwhash::gen_salt();
+            let key = derive_key(passphrase, &salt)?;
+            let nonce = secretbox::gen_nonce();
+            StoredSecretKeys::Encrypted {
+                salt: salt.0,
+                nonce: nonce.0,
+                ciphertext: secretbox::seal(&plain, &nonce, &key),
+            }
+        } else {
+            StoredSecretKeys::Plain(plain)
+        };
+        let stored = StoredFullIdV1 {
+            public_id: self.public_id,
+            secret_keys: secret_keys,
+        };
+        Ok(serialise(&(FULL_ID_FORMAT_VERSION, serialise(&stored)?))?)
+    }
+
+    /// Parses keys serialised by `to_bytes`.
+    ///
+    /// The `passphrase` is required if the keys were encrypted, and ignored otherwise.
+    pub fn from_bytes(bytes: &[u8], passphrase: Option<&str>) -> Result<FullId, RoutingError> {
+        let (version, body): (u16, Vec<u8>) = deserialise(bytes)?;
+        if version != FULL_ID_FORMAT_VERSION {
+            return Err(RoutingError::UnknownKeyFormat(version));
+        }
+        let stored: StoredFullIdV1 = deserialise(&body)?;
+        let plain = match stored.secret_keys {
+            StoredSecretKeys::Plain(plain) => plain,
+            StoredSecretKeys::Encrypted {
+                salt,
+                nonce,
+                ciphertext,
+            } => {
+                let passphrase = passphrase.ok_or(RoutingError::InvalidPassphrase)?;
+                let key = derive_key(passphrase, &pwhash::Salt(salt))?;
+                secretbox::open(&ciphertext, &secretbox::Nonce(nonce), &key)
+                    .map_err(|()| RoutingError::InvalidPassphrase)?
+            }
+        };
+        if plain.len() != box_::SECRETKEYBYTES + sign::SECRETKEYBYTES {
+            return Err(RoutingError::InvalidKeys);
+        }
+        let (encrypt_bytes, sign_bytes) = plain.split_at(box_::SECRETKEYBYTES);
+        let private_encrypt_key = box_::SecretKey::from_slice(encrypt_bytes)
+            .ok_or(RoutingError::InvalidKeys)?;
+        let private_sign_key = sign::SecretKey::from_slice(sign_bytes)
+            .ok_or(RoutingError::InvalidKeys)?;
+
+        // Make sure the secret keys actually belong to the stored public ones.
+        let public_encrypt_key =
+            curve25519::scalarmult_base(&curve25519::Scalar(private_encrypt_key.0));
+        let public_sign_key = &private_sign_key.0[sign::SECRETKEYBYTES - sign::PUBLICKEYBYTES..];
+        if public_encrypt_key.0 != stored.public_id.public_encrypt_key.0 ||
+           public_sign_key != &stored.public_id.public_sign_key.0[..] {
+            return Err(RoutingError::InvalidKeys);
+        }
+
+        Ok(FullId {
+               public_id: stored.public_id,
+               private_encrypt_key: private_encrypt_key,
+               private_sign_key: private_sign_key,
+           })
+    }
+
+    /// Writes the keys to the file at `path`, encrypted with the `passphrase` if one is given.
+    ///
+    /// On Unix, the file is only readable and writable by its owner.
+    pub fn save_to<P: AsRef<Path>>(&self,
+                                   path: P,
+                                   passphrase: Option<&str>)
+                                   -> Result<(), RoutingError> {
+        let bytes = self.to_bytes(passphrase)?;
+        let mut options = OpenOptions::new();
+        let _ = options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        let _ = options.mode(0o600);
+        let mut file = options.open(path)?;
+        file.write_all(&bytes)?;
+        Ok(file.sync_all()?)
+    }
+
+    /// Reads keys written by `save_to` from the file at `path`.
+    pub fn load_from<P: AsRef<Path>>(path: P,
+                                     passphrase: Option<&str>)
+                                     -> Result<FullId, RoutingError> {
+        let mut bytes = Vec::new();
+        let _ = File::open(path)?.read_to_end(&mut bytes)?;
+        FullId::from_bytes(&bytes, passphrase)
+    }
 }
 
 impl Default for FullId {
@@ -94,6 +227,25 @@ impl Default for FullId {
     }
 }
 
+impl Debug for FullId {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter,
+               "FullId {{ public_id: {:?}, secret keys: <redacted> }}",
+               self.public_id)
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &pwhash::Salt) -> Result<secretbox::Key, RoutingError> {
+    let mut key = secretbox::Key([0; secretbox::KEYBYTES]);
+    let _ = pwhash::derive_key(&mut key.0,
+                               passphrase.as_bytes(),
+                               salt,
+                               pwhash::OPSLIMIT_INTERACTIVE,
+                               pwhash::MEMLIMIT_INTERACTIVE)
+            .map_err(|()| RoutingError::InvalidPassphrase)?;
+    Ok(key)
+}
+
 #[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
 /// Network identity component containing name and public keys.
 ///
@@ -170,4 +322,84 @@ mod tests {
         let parsed = unwrap!(serialisation::deserialise(&serialised));
         assert_eq!(*full_id.public_id(), parsed);
     }
+
+    fn assert_same_keys(full_id: &FullId, parsed: &FullId) {
+        assert_eq!(full_id.public_id(), parsed.public_id());
+        assert_eq!(full_id.encrypting_private_key().0,
+                   parsed.encrypting_private_key().0);
+        assert_eq!(&full_id.signing_private_key().0[..],
+                   &parsed.signing_private_key().0[..]);
+    }
+
+    #[test]
+    fn storage_round_trip() {
+        let mut rng = SeededRng::thread_rng();
+        unwrap!(rust_sodium::init_with_rng(&mut rng));
+
+        let full_id = FullId::new();
+        let bytes = unwrap!(full_id.to_bytes(None));
+        assert_same_keys(&full_id, &unwrap!(FullId::from_bytes(&bytes, None)));
+        // A passphrase is ignored for unencrypted keys.
+        assert_same_keys(&full_id, &unwrap!(FullId::from_bytes(&bytes, Some("unused"))));
+    }
+
+    #[test]
+    fn encrypted_storage() {
+        let mut rng = SeededRng::thread_rng();
+        unwrap!(rust_sodium::init_with_rng(&mut rng));
+
+        let full_id = FullId::new();
+        let bytes = unwrap!(full_id.to_bytes(Some("correct horse")));
+        assert_same_keys(&full_id,
+                         &unwrap!(FullId::from_bytes(&bytes, Some("correct horse"))));
+
+        match FullId::from_bytes(&bytes, Some("battery staple")) {
+            Err(RoutingError::InvalidPassphrase) => (),
+            result => panic!("Unexpected result {:?}", result),
+        }
+        match FullId::from_bytes(&bytes, None) {
+            Err(RoutingError::InvalidPassphrase) => (),
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn mismatched_keys_are_rejected() {
+        let mut rng = SeededRng::thread_rng();
+        unwrap!(rust_sodium::init_with_rng(&mut rng));
+
+        let (full_id, other_id) = (FullId::new(), FullId::new());
+        let mut plain = full_id.encrypting_private_key().0.to_vec();
+        plain.extend_from_slice(&full_id.signing_private_key().0);
+        let stored = StoredFullIdV1 {
+            public_id: *other_id.public_id(),
+            secret_keys: StoredSecretKeys::Plain(plain),
+        };
+        let body = unwrap!(serialisation::serialise(&stored));
+        let bytes = unwrap!(serialisation::serialise(&(FULL_ID_FORMAT_VERSION, body)));
+        match FullId::from_bytes(&bytes, None) {
+            Err(RoutingError::InvalidKeys) => (),
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        let bytes = unwrap!(serialisation::serialise(&(FULL_ID_FORMAT_VERSION + 1,
+                                                       Vec::<u8>::new())));
+        match FullId::from_bytes(&bytes, None) {
+            Err(RoutingError::UnknownKeyFormat(version)) => {
+                assert_eq!(version, FULL_ID_FORMAT_VERSION + 1)
+            }
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn debug_hides_secret_keys() {
+        let mut rng = SeededRng::thread_rng();
+        unwrap!(rust_sodium::init_with_rng(&mut rng));
+
+        let full_id = FullId::new();
+        let output = format!("{:?}", full_id);
+        assert!(output.contains("redacted"));
+        assert!(!output.contains(&format!("{:?}", &full_id.signing_private_key().0[..8])));
+    }
 }
@@ -0,0 +1,183 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use error::RoutingError;
+use id::FullId;
+use maidsafe_utilities::serialisation::serialise;
+use routing_table::Authority;
+use rust_sodium::crypto::{hash, sign};
+use xor_name::XorName;
+
+/// A client's signed statement that its signing key is replaced by a new one.
+///
+/// A client's `ClientManager` authority is the hash of its public signing key, so replacing the key
+/// moves the account to a new location. The rotation is signed with both the old key, authorising
+/// the new one, and with the new key, proving that the client holds it. The `ClientManager` section
+/// receiving a `Request::RotateKey` is expected to move the account to the new location via
+/// `Request::MigrateAccount`, and to keep the chain of rotations as part of the account.
+#[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Hash, Serialize, Deserialize, Debug)]
+pub struct KeyRotation {
+    old_key: sign::PublicKey,
+    new_key: sign::PublicKey,
+    signature_using_old: sign::Signature,
+    signature_using_new: sign::Signature,
+}
+
+impl KeyRotation {
+    /// Creates a rotation from the keys in `old_id` to the ones in `new_id`.
+    pub fn new(old_id: &FullId, new_id: &FullId) -> Result<KeyRotation, RoutingError> {
+        let old_key = *old_id.public_id().signing_public_key();
+        let new_key = *new_id.public_id().signing_public_key();
+        let to_sign = serialise(&(old_key, new_key))?;
+        Ok(KeyRotation {
+               old_key: old_key,
+               new_key: new_key,
+               signature_using_old: sign::sign_detached(&to_sign, old_id.signing_private_key()),
+               signature_using_new: sign::sign_detached(&to_sign, new_id.signing_private_key()),
+           })
+    }
+
+    /// The signing key being replaced.
+    pub fn old_key(&self) -> &sign::PublicKey {
+        &self.old_key
+    }
+
+    /// The replacement signing key.
+    pub fn new_key(&self) -> &sign::PublicKey {
+        &self.new_key
+    }
+
+    /// The name of the client account before the rotation.
+    pub fn old_name(&self) -> XorName {
+        XorName(hash::sha256::hash(&self.old_key[..]).0)
+    }
+
+    /// The name of the client account after the rotation.
+    pub fn new_name(&self) -> XorName {
+        XorName(hash::sha256::hash(&self.new_key[..]).0)
+    }
+
+    /// Returns `true` if both signatures are valid.
+    pub fn verify(&self) -> bool {
+        let signed = match serialise(&(self.old_key, self.new_key)) {
+            Ok(signed) => signed,
+            Err(_) => return false,
+        };
+        self.old_key != self.new_key &&
+        sign::verify_detached(&self.signature_using_old, &signed, &self.old_key) &&
+        sign::verify_detached(&self.signature_using_new, &signed, &self.new_key)
+    }
+
+    /// Checks that the rotation is valid and was sent by the owner of the old key to its
+    /// `ClientManager`.
+    pub fn check_rotate_key_authorities(&self,
+                                        src: &Authority<XorName>,
+                                        dst: &Authority<XorName>)
+                                        -> Result<(), RoutingError> {
+        match (src, dst) {
+            (&Authority::Client { ref client_key, .. }, &Authority::ClientManager(ref name))
+                if *client_key == self.old_key && *name == self.old_name() => self.check(),
+            _ => Err(RoutingError::BadAuthority),
+        }
+    }
+
+    /// Checks that the rotation is valid and the account is migrated from the `ClientManager` of
+    /// the old key to the one of the new key.
+    pub fn check_migrate_account_authorities(&self,
+                                             src: &Authority<XorName>,
+                                             dst: &Authority<XorName>)
+                                             -> Result<(), RoutingError> {
+        match (src, dst) {
+            (&Authority::ClientManager(ref src_name), &Authority::ClientManager(ref dst_name))
+                if *src_name == self.old_name() && *dst_name == self.new_name() => self.check(),
+            _ => Err(RoutingError::BadAuthority),
+        }
+    }
+
+    fn check(&self) -> Result<(), RoutingError> {
+        if self.verify() {
+            Ok(())
+        } else {
+            Err(RoutingError::FailedSignature)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crust::PeerId;
+    use maidsafe_utilities::SeededRng;
+    use rand;
+    use rust_sodium;
+
+    #[cfg(not(feature = "use-mock-crust"))]
+    fn make_peer_id() -> PeerId {
+        PeerId(*FullId::new().public_id().encrypting_public_key())
+    }
+    #[cfg(feature = "use-mock-crust")]
+    fn make_peer_id() -> PeerId {
+        PeerId(0)
+    }
+
+    #[test]
+    fn verify() {
+        let mut rng = SeededRng::thread_rng();
+        unwrap!(rust_sodium::init_with_rng(&mut rng));
+
+        let old_id = FullId::new();
+        let new_id = FullId::new();
+        let rotation = unwrap!(KeyRotation::new(&old_id, &new_id));
+        assert!(rotation.verify());
+        assert_eq!(rotation.old_name(), *old_id.public_id().name());
+        assert_eq!(rotation.new_name(), *new_id.public_id().name());
+
+        // Replacing the new key invalidates both signatures.
+        let mut forged = rotation.clone();
+        forged.new_key = sign::gen_keypair().0;
+        assert!(!forged.verify());
+
+        // The new key must have signed it.
+        let other_id = FullId::new();
+        let mut forged = rotation.clone();
+        forged.signature_using_new = unwrap!(KeyRotation::new(&old_id, &other_id))
+            .signature_using_new;
+        assert!(!forged.verify());
+    }
+
+    #[test]
+    fn authorities() {
+        let mut rng = SeededRng::thread_rng();
+        unwrap!(rust_sodium::init_with_rng(&mut rng));
+
+        let old_id = FullId::new();
+        let new_id = FullId::new();
+        let rotation = unwrap!(KeyRotation::new(&old_id, &new_id));
+        let client = Authority::Client {
+            client_key: *old_id.public_id().signing_public_key(),
+            proxy_node_name: rand::random(),
+            peer_id: make_peer_id(),
+        };
+        let old_manager = Authority::ClientManager(rotation.old_name());
+        let new_manager = Authority::ClientManager(rotation.new_name());
+
+        assert!(rotation.check_rotate_key_authorities(&client, &old_manager).is_ok());
+        assert!(rotation.check_rotate_key_authorities(&client, &new_manager).is_err());
+        assert!(rotation.check_migrate_account_authorities(&old_manager, &new_manager).is_ok());
+        assert!(rotation.check_migrate_account_authorities(&new_manager, &old_manager).is_err());
+    }
+}
@@ -134,7 +134,7 @@ extern crate maidsafe_utilities;
 extern crate quick_error;
 #[macro_use]
 extern crate unwrap;
-#[cfg(not(feature = "use-mock-crust"))]
+#[cfg(not(any(feature = "use-mock-crust", feature = "use-tcp-crust")))]
 extern crate crust;
 extern crate itertools;
 extern crate lru_time_cache;
@@ -156,19 +156,25 @@ mod action;
 mod client;
 mod cache;
 mod data;
+mod data_chain;
+mod data_manager;
 mod error;
 mod event;
 mod event_stream;
 mod section_list_cache;
 mod id;
+mod key_rotation;
 mod message_filter;
 mod messages;
 mod node;
 mod outbox;
 mod peer_manager;
+mod relocation;
 mod resource_prover;
 mod routing_message_filter;
 mod routing_table;
+mod section_agreement;
+mod session_keys;
 mod signature_accumulator;
 mod state_machine;
 mod states;
@@ -182,6 +188,9 @@ mod xor_name;
 /// Mock crust
 #[cfg(feature = "use-mock-crust")]
 pub mod mock_crust;
+/// Plain TCP crust replacement for local multi-process networks
+#[cfg(all(feature = "use-tcp-crust", not(feature = "use-mock-crust")))]
+pub mod tcp_crust;
 
 /// SHA-3 type alias.
 pub mod sha3;
@@ -190,6 +199,10 @@ pub mod sha3;
 pub mod messaging;
 /// Error communication between vaults and core
 pub mod client_errors;
+/// Splitting large content into encrypted `ImmutableData` chunks, and reassembling it
+pub mod self_encryption;
+/// Human-readable domain names for services, stored as `StructuredData`
+pub mod dns;
 
 /// Structured Data Tag for Session Packet Type
 pub const TYPE_TAG_SESSION_PACKET: u64 = 0;
@@ -203,26 +216,35 @@ pub const QUORUM_NUMERATOR: usize = 1;
 /// See `QUORUM_NUMERATOR`.
 pub const QUORUM_DENOMINATOR: usize = 2;
 
-pub use cache::{Cache, NullCache};
+pub use cache::{Cache, LruCache, NullCache};
 pub use client::Client;
-pub use data::{AppendWrapper, AppendedData, Data, DataIdentifier, Filter, ImmutableData,
-               MAX_IMMUTABLE_DATA_SIZE_IN_BYTES, MAX_PRIV_APPENDABLE_DATA_SIZE_IN_BYTES,
+pub use data_chain::{Block, BlockPayload, DataChain, RELOCATION_AGE};
+pub use data_manager::{ChunkStore, DEFAULT_ACCOUNT_SIZE, DEFAULT_CHUNK_STORE_SIZE, DataManager,
+                       DiskChunkStore, EXPIRY_GRACE_PERIOD_SECS, MemoryChunkStore};
+pub use data::{AppendWrapper, AppendedData, Data, DataIdentifier, EntryAction, Filter,
+               ImmutableData, MAX_IMMUTABLE_DATA_SIZE_IN_BYTES, MAX_MUTABLE_DATA_ENTRIES,
+               MAX_MUTABLE_DATA_SIZE_IN_BYTES, MAX_PRIV_APPENDABLE_DATA_SIZE_IN_BYTES,
                MAX_PUB_APPENDABLE_DATA_SIZE_IN_BYTES, MAX_STRUCTURED_DATA_SIZE_IN_BYTES,
-               NO_OWNER_PUB_KEY, PrivAppendableData, PrivAppendedData, PubAppendableData,
-               StructuredData};
+               MutableData, NO_OWNER_PUB_KEY, Permission, PrivAppendableData, PrivAppendedData,
+               PubAppendableData, StructuredData, Value};
 pub use error::{InterfaceError, RoutingError};
 pub use event::Event;
 pub use event_stream::EventStream;
 pub use id::{FullId, PublicId};
+pub use key_rotation::KeyRotation;
 pub use messages::{Request, Response};
 #[cfg(feature = "use-mock-crust")]
 pub use mock_crust::crust;
+#[cfg(all(feature = "use-tcp-crust", not(feature = "use-mock-crust")))]
+pub use tcp_crust::crust;
 pub use node::{Node, NodeBuilder};
-pub use routing_table::{Authority, Prefix, RoutingTable, Xorable};
+pub use relocation::{BalancingRelocation, HashingRelocation, RelocationStrategy};
+pub use routing_table::{Authority, Churn, Prefix, ResponsibilityDiff, RoutingTable, Xorable};
 pub use routing_table::Error as RoutingTableError;
 #[cfg(any(test, feature = "use-mock-crust"))]
 pub use routing_table::verify_network_invariant;
 pub use types::MessageId;
+pub use utils::unix_time_secs;
 pub use xor_name::{XOR_NAME_BITS, XOR_NAME_LEN, XorName, XorNameFromHexError};
 
 #[cfg(test)]
@@ -17,13 +17,16 @@
 
 use super::{QUORUM_DENOMINATOR, QUORUM_NUMERATOR};
 use ack_manager::Ack;
+use cache;
 #[cfg(not(feature = "use-mock-crust"))]
 use crust::PeerId;
 use data::{AppendWrapper, Data, DataIdentifier};
+use data_chain::{BlockPayload, DataChainPage};
 use error::RoutingError;
 use event::Event;
 use id::{FullId, PublicId};
 use itertools::Itertools;
+use key_rotation::KeyRotation;
 use lru_time_cache::LruCache;
 use maidsafe_utilities::serialisation::{deserialise, serialise};
 #[cfg(feature = "use-mock-crust")]
@@ -84,11 +87,19 @@ pub enum Message {
         /// The receiver
         dst: PeerId,
     },
+    /// A serialised message, encrypted with the session key of the direct connection it is sent on
+    Encrypted {
+        /// The nonce used for encryption
+        nonce: [u8; box_::NONCEBYTES],
+        /// The encrypted, serialised `Message`
+        content: Vec<u8>,
+    },
 }
 
 impl Message {
     pub fn priority(&self) -> u8 {
         match *self {
+            Message::Encrypted { .. } => 0,
             Message::Direct(ref content) |
             Message::TunnelDirect { ref content, .. } => content.priority(),
             Message::Hop(ref content) |
@@ -109,10 +120,21 @@ pub enum DirectMessage {
     MessageSignature(sha256::Digest, sign::Signature),
     /// A signature for the current `BTreeSet` of section's node names
     SectionListSignature(SectionList, sign::Signature),
+    /// A signature for the next block of our section's data chain.
+    BlockSignature(BlockPayload, sign::Signature),
+    /// Sent to a node which joined our section, or in response to `DataChainRequest`: a page of
+    /// the section's data chain.
+    DataChainPage(DataChainPage),
+    /// Requests the page of the section's data chain beginning with the given block index.
+    DataChainRequest(u64),
     /// Sent from the bootstrap node to a client in response to `ClientIdentify`.
     BootstrapIdentify {
         /// The bootstrap node's keys and name.
         public_id: PublicId,
+        /// Whether the bootstrap node wants the connection to be encrypted.
+        session_encryption: bool,
+        /// Signature of the serialised `public_id` followed by `session_encryption`.
+        signature: sign::Signature,
     },
     /// Sent to the client to indicate that this node is not available as a bootstrap node.
     BootstrapDeny,
@@ -121,23 +143,28 @@ pub enum DirectMessage {
     ClientIdentify {
         /// Serialised keys and claimed name.
         serialised_public_id: Vec<u8>,
-        /// Signature of the client.
+        /// Signature of the client over `serialised_public_id` followed by `session_encryption`.
         signature: sign::Signature,
         /// Indicate whether we intend to remain a client, as opposed to becoming a routing node.
         client_restriction: bool,
+        /// Whether the client wants the connection to be encrypted.
+        session_encryption: bool,
     },
     /// Sent from an established node (i.e. one which has successfully joined the network) to
     /// another node, to allow the latter to add the former to its routing table.
     NodeIdentify {
         /// Keys and claimed name, serialised outside routing.
         serialised_public_id: Vec<u8>,
-        /// Signature of the originator of this message.
+        /// Signature of the originator over `serialised_public_id` followed by
+        /// `session_encryption`.
         signature: sign::Signature,
         /// FIXME: Should be deprecated.
         /// Tunnel connection indicator from sender which would override
         /// intermediate peer_mgr states for routing table connection type.
         /// Should not influence JoiningNode / Proxy states which are expected to be direct only.
         is_tunnel: bool,
+        /// Whether the sender wants the connection to be encrypted.
+        session_encryption: bool,
     },
     /// Sent from a node which is still joining the network to another node, to allow the latter to
     /// add the former to its routing table.
@@ -146,10 +173,11 @@ pub enum DirectMessage {
         old_public_id: PublicId,
         /// `PublicId` from after relocation.
         new_public_id: PublicId,
-        /// Signature of concatenated `PublicId`s using the pre-relocation key.
+        /// Signature of concatenated `PublicId`s followed by `session_encryption` using the
+        /// pre-relocation key.
         signature_using_old: sign::Signature,
-        /// Signature of concatenated `PublicId`s and `signature_using_old` using the
-        /// post-relocation key.
+        /// Signature of concatenated `PublicId`s, `session_encryption` and `signature_using_old`
+        /// using the post-relocation key.
         signature_using_new: sign::Signature,
         /// Client authority from after relocation.
         new_client_auth: Authority<XorName>,
@@ -158,6 +186,8 @@ pub enum DirectMessage {
         /// intermediate peer_mgr states for routing table connection type.
         /// Should not influence JoiningNode / Proxy states which are expected to be direct only.
         is_tunnel: bool,
+        /// Whether the sender wants the connection to be encrypted.
+        session_encryption: bool,
     },
     /// Sent from a node that needs a tunnel to be able to connect to the given peer.
     TunnelRequest(PeerId),
@@ -258,22 +288,59 @@ impl HopMessage {
 #[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Hash, Serialize, Deserialize, Debug)]
 pub struct SectionList {
     pub prefix: Prefix<XorName>,
+    /// The section's version. It is part of the signed list, so that an outdated list can't be
+    /// presented as the current one.
+    pub version: u64,
     // TODO(MAID-1677): pub signatures: BTreeSet<(PublicId, sign::Signature)>,
     pub_ids: BTreeSet<PublicId>,
 }
 
 impl SectionList {
     /// Create
-    pub fn new(prefix: Prefix<XorName>, pub_ids: BTreeSet<PublicId>) -> Self {
+    pub fn new(versioned_prefix: VersionedPrefix<XorName>, pub_ids: BTreeSet<PublicId>) -> Self {
+        let (prefix, version) = versioned_prefix.into();
         SectionList {
             prefix: prefix,
+            version: version,
             pub_ids: pub_ids,
         }
     }
 
     /// Create from any object convertable to an iterator
-    pub fn from<I: IntoIterator<Item = PublicId>>(prefix: Prefix<XorName>, pub_ids: I) -> Self {
-        Self::new(prefix, pub_ids.into_iter().collect())
+    pub fn from<I: IntoIterator<Item = PublicId>>(versioned_prefix: VersionedPrefix<XorName>,
+                                                  pub_ids: I)
+                                                  -> Self {
+        Self::new(versioned_prefix, pub_ids.into_iter().collect())
+    }
+
+    /// The section's prefix and version.
+    pub fn versioned_prefix(&self) -> VersionedPrefix<XorName> {
+        self.prefix.with_version(self.version)
+    }
+
+    /// The section's members.
+    pub fn pub_ids(&self) -> &BTreeSet<PublicId> {
+        &self.pub_ids
+    }
+
+    /// Checks that `signatures` contains valid signatures of this list by a quorum of the listed
+    /// members. Signatures by anyone else are ignored.
+    pub fn verify_signatures(&self,
+                             signatures: &BTreeMap<PublicId, sign::Signature>)
+                             -> Result<(), RoutingError> {
+        let serialised = serialise(self)?;
+        let valid_count = signatures
+            .iter()
+            .filter(|&(pub_id, sig)| {
+                        self.pub_ids.contains(pub_id) &&
+                        sign::verify_detached(sig, &serialised, pub_id.signing_public_key())
+                    })
+            .count();
+        if valid_count * QUORUM_DENOMINATOR > self.pub_ids.len() * QUORUM_NUMERATOR {
+            Ok(())
+        } else {
+            Err(RoutingError::NotEnoughSignatures)
+        }
     }
 }
 
@@ -325,6 +392,23 @@ impl SignedMessage {
         self.signatures.contains_key(pub_id)
     }
 
+    /// Returns whether a quorum of `members` signed the message. Unlike `check_integrity`, this
+    /// doesn't rely on the sender's claim about the source section's members.
+    ///
+    /// The signatures are not validated again, so `check_integrity` must have succeeded before.
+    pub fn signed_by_quorum_of(&self, members: &BTreeSet<PublicId>) -> bool {
+        let signed_count = members
+            .iter()
+            .filter(|pub_id| self.signatures.contains_key(pub_id))
+            .count();
+        signed_count * QUORUM_DENOMINATOR > members.len() * QUORUM_NUMERATOR
+    }
+
+    /// Returns the lists of the source authority's members which are expected to sign.
+    pub fn src_sections(&self) -> &[SectionList] {
+        &self.src_sections
+    }
+
     /// Returns the number of nodes in the source authority.
     pub fn src_size(&self) -> usize {
         self.src_sections
@@ -538,8 +622,9 @@ impl RoutingMessage {
 ///
 /// Once in `JoiningNode` state, A sends a `Relocate` request to the `NaeManager` section authority
 /// X of A's current name. X computes a target destination Y to which A should relocate and sends
-/// that section's `NaeManager`s an `ExpectCandidate` containing A's current public ID. Each member
-/// of Y caches A's public ID, and sends `AcceptAsCandidate` to self section. Once Y receives
+/// that section's `NaeManager`s an `ExpectCandidate` containing A's current public ID and, if A
+/// was a member of X which X's data chain selected for relocation, A's age. Each member of Y
+/// caches A's public ID and age, and sends `AcceptAsCandidate` to self section. Once Y receives
 /// `AcceptAsCandidate`, sends a `RelocateResponse` back to A, which includes an address space range
 /// into which A should relocate and also the public IDs of the members of Y. A then disconnects
 /// from the network and reconnects with a new ID which falls within the specified address range.
@@ -590,6 +675,8 @@ pub enum MessageContent {
         old_public_id: PublicId,
         /// The joining node's current authority.
         old_client_auth: Authority<XorName>,
+        /// The age the joining node carries over, if it is being relocated from another section.
+        age: u32,
         /// The message's unique identifier.
         message_id: MessageId,
     },
@@ -634,6 +721,9 @@ pub enum MessageContent {
         versioned_prefix: VersionedPrefix<XorName>,
         /// Members of the section
         members: BTreeSet<PublicId>,
+        /// The members which sign and accumulate messages on behalf of the section, and to which
+        /// other sections should route messages.
+        elders: BTreeSet<XorName>,
     },
     /// Sent to all connected peers when our own section splits
     SectionSplit(VersionedPrefix<XorName>, XorName),
@@ -675,6 +765,8 @@ pub enum MessageContent {
         old_public_id: PublicId,
         /// The joining node's current authority.
         old_client_auth: Authority<XorName>,
+        /// The age the joining node carries over, if it is being relocated from another section.
+        age: u32,
         /// The interval into which the joining node should join.
         target_interval: (XorName, XorName),
         /// The message's unique identifier.
@@ -688,6 +780,8 @@ pub enum MessageContent {
         new_public_id: PublicId,
         /// Client authority of the candidate.
         new_client_auth: Authority<XorName>,
+        /// The age the candidate carries over from its previous section, or 0 if it has none.
+        age: u32,
         /// The `PublicId`s of all routing table contacts shared by the nodes in our section.
         sections: SectionMap,
     },
@@ -699,6 +793,28 @@ pub enum MessageContent {
         /// contacts.
         sections: SectionMap,
     },
+    /// A vote for an opaque value the section should agree on.
+    ///
+    /// Sent from a `ManagedNode` to its own `Section`.
+    SectionVote(Vec<u8>),
+    /// Ask the section managing the destination name for its prefix and members.
+    ///
+    /// Sent from a `Client` or `ManagedNode` to a `Section`.
+    GetSectionInfo(MessageId),
+    /// Reply to `GetSectionInfo` with the section's prefix and members, together with the
+    /// signatures of a quorum of the members on the corresponding `SectionList`.
+    ///
+    /// Sent from a `ManagedNode` to the requesting `Client` or `ManagedNode`.
+    SectionInfo {
+        /// The section's prefix and version.
+        versioned_prefix: VersionedPrefix<XorName>,
+        /// The section's members.
+        members: BTreeSet<PublicId>,
+        /// The members' signatures of the `SectionList` with the prefix and members.
+        signatures: BTreeMap<PublicId, sign::Signature>,
+        /// The message's unique identifier.
+        message_id: MessageId,
+    },
 }
 
 impl MessageContent {
@@ -724,7 +840,12 @@ impl Debug for DirectMessage {
             SectionListSignature(ref sec_list, _) => {
                 write!(formatter, "SectionListSignature({:?}, ..)", sec_list.prefix)
             }
-            BootstrapIdentify { ref public_id } => {
+            BlockSignature(ref payload, _) => {
+                write!(formatter, "BlockSignature({:?}, ..)", payload)
+            }
+            DataChainPage(ref page) => write!(formatter, "{:?}", page),
+            DataChainRequest(start) => write!(formatter, "DataChainRequest({})", start),
+            BootstrapIdentify { ref public_id, .. } => {
                 write!(formatter, "BootstrapIdentify {{ {:?} }}", public_id)
             }
             BootstrapDeny => write!(formatter, "BootstrapDeny"),
@@ -805,12 +926,14 @@ impl Debug for MessageContent {
             ExpectCandidate {
                 ref old_public_id,
                 ref old_client_auth,
+                age,
                 ref message_id,
             } => {
                 write!(formatter,
-                       "ExpectCandidate {{ {:?}, {:?}, {:?} }}",
+                       "ExpectCandidate {{ {:?}, {:?}, age {}, {:?} }}",
                        old_public_id,
                        old_client_auth,
+                       age,
                        message_id)
             }
             ConnectionInfoRequest {
@@ -847,11 +970,13 @@ impl Debug for MessageContent {
             SectionUpdate {
                 ref versioned_prefix,
                 ref members,
+                ref elders,
             } => {
                 write!(formatter,
-                       "SectionUpdate {{ {:?}, {:?} }}",
+                       "SectionUpdate {{ {:?}, {:?}, elders: {:?} }}",
                        versioned_prefix,
-                       members)
+                       members,
+                       elders)
             }
             SectionSplit(ref ver_pfx, ref joining_node) => {
                 write!(formatter, "SectionSplit({:?}, {:?})", ver_pfx, joining_node)
@@ -883,13 +1008,15 @@ impl Debug for MessageContent {
             AcceptAsCandidate {
                 ref old_public_id,
                 ref old_client_auth,
+                age,
                 ref target_interval,
                 ref message_id,
             } => {
                 write!(formatter,
-                       "AcceptAsCandidate {{ {:?}, {:?}, {:?}, {:?} }}",
+                       "AcceptAsCandidate {{ {:?}, {:?}, age {}, {:?}, {:?} }}",
                        old_public_id,
                        old_client_auth,
+                       age,
                        target_interval,
                        message_id)
             }
@@ -897,17 +1024,37 @@ impl Debug for MessageContent {
                 ref old_public_id,
                 ref new_public_id,
                 ref new_client_auth,
+                age,
                 ref sections,
             } => {
                 write!(formatter,
-                       "CandidateApproval {{ old: {:?}, new: {:?},  new: {:?}, sections: \
-                        {:?} }}",
+                       "CandidateApproval {{ old: {:?}, new: {:?},  new: {:?}, age: {}, \
+                        sections: {:?} }}",
                        old_public_id,
                        new_public_id,
                        new_client_auth,
+                       age,
                        sections)
             }
             NodeApproval { ref sections } => write!(formatter, "NodeApproval {{ {:?} }}", sections),
+            SectionVote(ref value) => {
+                write!(formatter,
+                       "SectionVote({})",
+                       utils::format_binary_array(value))
+            }
+            GetSectionInfo(ref message_id) => write!(formatter, "GetSectionInfo({:?})", message_id),
+            SectionInfo {
+                ref versioned_prefix,
+                ref members,
+                ref message_id,
+                ..
+            } => {
+                write!(formatter,
+                       "SectionInfo {{ {:?}, {:?}, {:?}, .. }}",
+                       versioned_prefix,
+                       members,
+                       message_id)
+            }
         }
     }
 }
@@ -961,6 +1108,23 @@ impl UserMessage {
         }
     }
 
+    /// Checks that a key rotation request is validly signed and sent between the authorities it
+    /// applies to. All other messages are accepted.
+    pub fn check_key_rotation(&self,
+                              src: &Authority<XorName>,
+                              dst: &Authority<XorName>)
+                              -> Result<(), RoutingError> {
+        match *self {
+            UserMessage::Request(Request::RotateKey(ref rotation, _)) => {
+                rotation.check_rotate_key_authorities(src, dst)
+            }
+            UserMessage::Request(Request::MigrateAccount(ref rotation, ..)) => {
+                rotation.check_migrate_account_authorities(src, dst)
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Returns an event indicating that this message was received with the given source and
     /// destination authorities.
     pub fn into_event(self, src: Authority<XorName>, dst: Authority<XorName>) -> Event {
@@ -982,10 +1146,18 @@ impl UserMessage {
         }
     }
 
+    // Whether relaying nodes should pass this message to their cache: either to look up or store
+    // a response, or to invalidate cached data. Messages for structured data are included, for
+    // caches which handle it.
     fn is_cacheable(&self) -> bool {
         match *self {
-            UserMessage::Request(ref request) => request.is_cacheable(),
-            UserMessage::Response(ref response) => response.is_cacheable(),
+            UserMessage::Request(ref request) => {
+                request.is_cacheable() || cache::is_structured_data_get(request)
+            }
+            UserMessage::Response(ref response) => {
+                response.is_cacheable() || cache::is_structured_data_response(response) ||
+                response.invalidated_data().is_some()
+            }
         }
     }
 }
@@ -1009,6 +1181,32 @@ pub enum Request {
     Append(AppendWrapper, MessageId),
     /// Get account information for Client with given ID
     GetAccountInfo(MessageId),
+    /// Replace the client's signing key, moving its account to the `ClientManager` of the new key.
+    ///
+    /// Sent from the `Client` with the old key to its `ClientManager`.
+    RotateKey(KeyRotation, MessageId),
+    /// Move an account to a new `ClientManager` after a key rotation, with the serialised account
+    /// data.
+    ///
+    /// Sent from the `ClientManager` of the old key to the one of the new key.
+    MigrateAccount(KeyRotation, Vec<u8>, MessageId),
+    /// An application-defined request. Routing only delivers it; the meaning of `tag` and
+    /// `payload` is up to the application.
+    Custom {
+        /// Application-defined type of the request
+        tag: u64,
+        /// Application-defined content of the request
+        payload: Vec<u8>,
+        /// Whether relaying nodes should look up a response in their `Cache`. Only caches supplied
+        /// by the application can match a custom response to its request: `LruCache` ignores it.
+        cacheable: bool,
+        /// Unique message identifier
+        id: MessageId,
+    },
+    /// Ask for several data items managed by the same authority at once
+    GetBatch(Vec<DataIdentifier>, MessageId),
+    /// Put several data items to the network at once, via the client's `ClientManager`
+    PutBatch(Vec<Data>, MessageId),
 }
 
 /// Response message types
@@ -1035,6 +1233,8 @@ pub enum Response {
         data_stored: u64,
         /// Amount of network space available to this Client
         space_available: u64,
+        /// The key rotations that led to the Client's current key, oldest first
+        key_rotations: Vec<KeyRotation>,
     },
     /// Error for `Get`, includes signed request to prevent injection attacks
     GetFailure {
@@ -1088,6 +1288,43 @@ pub enum Response {
         /// Error type sent back, may be injected from upper layers
         external_error_indicator: Vec<u8>,
     },
+    /// Success token for `RotateKey`: the account has been moved to the new key's `ClientManager`
+    RotateKeySuccess(MessageId),
+    /// Error for `RotateKey`
+    RotateKeyFailure {
+        /// Unique message identifier
+        id: MessageId,
+        /// Error type sent back, may be injected from upper layers
+        external_error_indicator: Vec<u8>,
+    },
+    /// An application-defined response, e.g. to a `Request::Custom`.
+    Custom {
+        /// Application-defined type of the response
+        tag: u64,
+        /// Application-defined content of the response
+        payload: Vec<u8>,
+        /// Whether relaying nodes should pass the response to their `Cache`. `LruCache` ignores
+        /// custom responses.
+        cacheable: bool,
+        /// Unique message identifier
+        id: MessageId,
+    },
+    /// Results of a `GetBatch`, per item and in the order requested. Failures contain the error
+    /// sent back, which may be injected from upper layers.
+    GetBatch {
+        /// Unique message identifier
+        id: MessageId,
+        /// The requested data or the error for each item
+        results: Vec<(DataIdentifier, Result<Data, Vec<u8>>)>,
+    },
+    /// Results of a `PutBatch`, per item and in the order requested. Failures contain the error
+    /// sent back, which may be injected from upper layers.
+    PutBatch {
+        /// Unique message identifier
+        id: MessageId,
+        /// Success or the error for each item
+        results: Vec<(DataIdentifier, Result<(), Vec<u8>>)>,
+    },
 }
 
 impl Request {
@@ -1096,13 +1333,19 @@ impl Request {
         match *self {
             Request::Refresh(..) => 2,
             Request::Get(..) |
-            Request::GetAccountInfo(..) => 3,
-            Request::Append(..) => 4,
+            Request::GetBatch(..) |
+            Request::GetAccountInfo(..) |
+            Request::RotateKey(..) => 3,
+            Request::Append(..) |
+            Request::MigrateAccount(..) |
+            Request::Custom { .. } => 4,
+            Request::PutBatch(..) => 5,
             Request::Put(ref data, _) |
             Request::Post(ref data, _) |
             Request::Delete(ref data, _) => {
                 match *data {
-                    Data::Structured(..) => 4,
+                    Data::Structured(..) |
+                    Data::Mutable(..) => 4,
                     _ => 5,
                 }
             }
@@ -1111,10 +1354,10 @@ impl Request {
 
     /// Is the response corresponding to this request cacheable?
     pub fn is_cacheable(&self) -> bool {
-        if let Request::Get(DataIdentifier::Immutable(..), _) = *self {
-            true
-        } else {
-            false
+        match *self {
+            Request::Get(DataIdentifier::Immutable(..), _) => true,
+            Request::Custom { cacheable, .. } => cacheable,
+            _ => false,
         }
     }
 }
@@ -1125,10 +1368,12 @@ impl Response {
         match *self {
             Response::GetSuccess(ref data, _) => {
                 match *data {
-                    Data::Structured(..) => 4,
+                    Data::Structured(..) |
+                    Data::Mutable(..) => 4,
                     _ => 5,
                 }
             }
+            Response::GetBatch { .. } => 5,
             Response::PutSuccess(..) |
             Response::PostSuccess(..) |
             Response::DeleteSuccess(..) |
@@ -1139,16 +1384,31 @@ impl Response {
             Response::PostFailure { .. } |
             Response::DeleteFailure { .. } |
             Response::AppendFailure { .. } |
-            Response::GetAccountInfoFailure { .. } => 3,
+            Response::GetAccountInfoFailure { .. } |
+            Response::RotateKeySuccess(..) |
+            Response::RotateKeyFailure { .. } |
+            Response::PutBatch { .. } |
+            Response::Custom { .. } => 3,
         }
     }
 
     /// Is this response cacheable?
     pub fn is_cacheable(&self) -> bool {
-        if let Response::GetSuccess(Data::Immutable(..), _) = *self {
-            true
-        } else {
-            false
+        match *self {
+            Response::GetSuccess(Data::Immutable(..), _) => true,
+            Response::Custom { cacheable, .. } => cacheable,
+            _ => false,
+        }
+    }
+
+    /// If this response shows that some data has been mutated, returns its identifier. Cached
+    /// copies of that data must not be served anymore.
+    pub fn invalidated_data(&self) -> Option<DataIdentifier> {
+        match *self {
+            Response::PostSuccess(data_id, _) |
+            Response::DeleteSuccess(data_id, _) |
+            Response::AppendSuccess(data_id, _) => Some(data_id),
+            _ => None,
         }
     }
 }
@@ -1177,9 +1437,41 @@ impl Debug for Request {
             Request::Append(ref wrapper, ref message_id) => {
                 write!(formatter, "Append({:?}, {:?})", wrapper, message_id)
             }
+            Request::GetBatch(ref data_ids, ref message_id) => {
+                write!(formatter, "GetBatch({:?}, {:?})", data_ids, message_id)
+            }
+            Request::PutBatch(ref data, ref message_id) => {
+                write!(formatter, "PutBatch({} items, {:?})", data.len(), message_id)
+            }
             Request::GetAccountInfo(ref message_id) => {
                 write!(formatter, "GetAccountInfo({:?})", message_id)
             }
+            Request::RotateKey(ref rotation, ref message_id) => {
+                write!(formatter,
+                       "RotateKey({} -> {}, {:?})",
+                       rotation.old_name(),
+                       rotation.new_name(),
+                       message_id)
+            }
+            Request::MigrateAccount(ref rotation, _, ref message_id) => {
+                write!(formatter,
+                       "MigrateAccount({} -> {}, {:?})",
+                       rotation.old_name(),
+                       rotation.new_name(),
+                       message_id)
+            }
+            Request::Custom {
+                tag,
+                ref payload,
+                ref id,
+                ..
+            } => {
+                write!(formatter,
+                       "Custom {{ {}, {}, {:?}, .. }}",
+                       tag,
+                       utils::format_binary_array(payload),
+                       id)
+            }
         }
     }
 }
@@ -1233,6 +1525,36 @@ impl Debug for Response {
             Response::GetAccountInfoFailure { ref id, .. } => {
                 write!(formatter, "GetAccountInfoFailure {{ {:?}, .. }}", id)
             }
+            Response::RotateKeySuccess(ref message_id) => {
+                write!(formatter, "RotateKeySuccess({:?})", message_id)
+            }
+            Response::RotateKeyFailure { ref id, .. } => {
+                write!(formatter, "RotateKeyFailure {{ {:?}, .. }}", id)
+            }
+            Response::GetBatch { ref id, ref results } => {
+                write!(formatter,
+                       "GetBatch {{ {:?}, {} results, .. }}",
+                       id,
+                       results.len())
+            }
+            Response::PutBatch { ref id, ref results } => {
+                write!(formatter,
+                       "PutBatch {{ {:?}, {} results, .. }}",
+                       id,
+                       results.len())
+            }
+            Response::Custom {
+                tag,
+                ref payload,
+                ref id,
+                ..
+            } => {
+                write!(formatter,
+                       "Custom {{ {}, {}, {:?}, .. }}",
+                       tag,
+                       utils::format_binary_array(payload),
+                       id)
+            }
         }
     }
 }
@@ -1287,7 +1609,7 @@ mod tests {
     #[cfg(not(feature = "use-mock-crust"))]
     use crust::PeerId;
     use data::{Data, ImmutableData};
-    use id::FullId;
+    use id::{FullId, PublicId};
     use maidsafe_utilities::serialisation::serialise;
     #[cfg(feature = "use-mock-crust")]
     use mock_crust::crust::PeerId;
@@ -1370,7 +1692,7 @@ mod tests {
             content: part,
         };
 
-        let src_sections = vec![SectionList::from(prefix,
+        let src_sections = vec![SectionList::from(prefix.with_version(0),
                                                   vec![*full_id_0.public_id(),
                                                        *full_id_1.public_id(),
                                                        *full_id_2.public_id()])];
@@ -1422,6 +1744,59 @@ mod tests {
                      .contains_key(irrelevant_full_id.public_id()));
     }
 
+    #[test]
+    fn msg_signed_by_quorum_of() {
+        let full_ids: Vec<FullId> = (0..5).map(|_| FullId::new()).collect();
+        let pub_ids: BTreeSet<PublicId> = full_ids.iter().map(|id| *id.public_id()).collect();
+        let prefix = Prefix::new(0, *full_ids[0].public_id().name());
+        let name: XorName = rand::random();
+        let routing_message = RoutingMessage {
+            src: Authority::NaeManager(name),
+            dst: Authority::ManagedNode(name),
+            content: MessageContent::GetSectionInfo(MessageId::new()),
+        };
+
+        // The sender claims the section only consists of the first three nodes.
+        let src_sections = vec![SectionList::from(prefix.with_version(0),
+                                                  full_ids[..3].iter().map(|id| *id.public_id()))];
+        let mut signed_msg =
+            unwrap!(SignedMessage::new(routing_message, &full_ids[0], src_sections));
+        match unwrap!(signed_msg
+                          .routing_message()
+                          .to_signature(full_ids[1].signing_private_key())) {
+            DirectMessage::MessageSignature(_, sig) => {
+                signed_msg.add_signature(*full_ids[1].public_id(), sig)
+            }
+            msg => panic!("Unexpected message: {:?}", msg),
+        }
+        unwrap!(signed_msg.check_integrity(3));
+
+        // Two signatures are a quorum of the claimed section, but not of the actual one.
+        let claimed = full_ids[..3].iter().map(|id| *id.public_id()).collect();
+        assert!(signed_msg.signed_by_quorum_of(&claimed));
+        assert!(!signed_msg.signed_by_quorum_of(&pub_ids));
+    }
+
+    #[test]
+    fn section_list_signatures_cover_version() {
+        let full_ids: Vec<FullId> = (0..3).map(|_| FullId::new()).collect();
+        let prefix = Prefix::new(0, *full_ids[0].public_id().name());
+        let list = SectionList::from(prefix.with_version(2),
+                                     full_ids.iter().map(|id| *id.public_id()));
+        let serialised = unwrap!(serialise(&list));
+        let signatures = full_ids
+            .iter()
+            .map(|id| {
+                     (*id.public_id(), sign::sign_detached(&serialised, id.signing_private_key()))
+                 })
+            .collect();
+        unwrap!(list.verify_signatures(&signatures));
+
+        // The same members with a different version are not covered by the signatures.
+        let outdated = SectionList::new(prefix.with_version(1), list.pub_ids().clone());
+        assert!(outdated.verify_signatures(&signatures).is_err());
+    }
+
     #[test]
     fn hop_message_verify() {
         let name: XorName = rand::random();
@@ -1484,4 +1859,29 @@ mod tests {
         let deserialised_user_msg = unwrap!(UserMessage::from_parts(msg_hash, payloads.iter()));
         assert_eq!(user_msg, deserialised_user_msg);
     }
+
+    #[test]
+    fn batch_response_parts() {
+        let results: Vec<_> = (0..200)
+            .map(|i| {
+                     let data = Data::Immutable(ImmutableData::new(vec![i as u8; 1024]));
+                     (data.identifier(), Ok(data))
+                 })
+            .collect();
+        let user_msg = UserMessage::Response(Response::GetBatch {
+                                                 id: MessageId::new(),
+                                                 results: results,
+                                             });
+        let msg_hash = sha3_256(&unwrap!(serialise(&user_msg)));
+        let payloads: Vec<Vec<u8>> = unwrap!(user_msg.to_parts(5))
+            .into_iter()
+            .map(|msg| match msg {
+                     MessageContent::UserMessagePart { payload, .. } => payload,
+                     msg => panic!("Unexpected message {:?}", msg),
+                 })
+            .collect();
+        assert!(payloads.len() > 1);
+        let deserialised_user_msg = unwrap!(UserMessage::from_parts(msg_hash, payloads.iter()));
+        assert_eq!(user_msg, deserialised_user_msg);
+    }
 }
@@ -26,6 +26,8 @@ use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::collections::btree_map::Entry;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::rc::{Rc, Weak};
+use std::time::Duration;
+use timer;
 
 /// Mock network. Create one before testing with mocks. Use it to create `ServiceHandle`s.
 #[derive(Clone)]
@@ -108,6 +110,12 @@ impl Network {
         }
     }
 
+    /// Advances the virtual clock shared by all nodes in this network and raises the timeout
+    /// events which expired meanwhile, in order of their deadlines.
+    pub fn advance_time(&self, duration: Duration) {
+        timer::advance_time(duration);
+    }
+
     /// Causes all packets from `sender` to `receiver` to fail.
     pub fn block_connection(&self, sender: Endpoint, receiver: Endpoint) {
         let mut imp = self.0.borrow_mut();
@@ -20,16 +20,20 @@ use cache::{Cache, NullCache};
 #[cfg(feature = "use-mock-crust")]
 use crust::PeerId;
 use data::{Data, DataIdentifier};
+use data_chain::DataChain;
 use error::{InterfaceError, RoutingError};
 use event::Event;
 use event_stream::{EventStepper, EventStream};
 use id::FullId;
+use key_rotation::KeyRotation;
 #[cfg(feature = "use-mock-crust")]
 use id::PublicId;
 use messages::{CLIENT_GET_PRIORITY, DEFAULT_PRIORITY, RELOCATE_PRIORITY, Request, Response,
                UserMessage};
 use outbox::{EventBox, EventBuf};
+use relocation::{HashingRelocation, RelocationStrategy};
 use routing_table::{Authority, RoutingTable};
+use session_keys::SessionKeys;
 #[cfg(feature = "use-mock-crust")]
 use routing_table::Prefix;
 #[cfg(not(feature = "use-mock-crust"))]
@@ -51,10 +55,13 @@ pub struct NodeBuilder {
     cache: Box<Cache>,
     first: bool,
     deny_other_local_nodes: bool,
+    encrypt_links: bool,
+    relocation_strategy: Box<RelocationStrategy>,
 }
 
 impl NodeBuilder {
-    /// Configures the node to use the given request cache.
+    /// Configures the node to use the given request cache. By default, nothing is cached;
+    /// `LruCache::default()` is a sensible choice for nodes acting as proxies.
     pub fn cache(self, cache: Box<Cache>) -> NodeBuilder {
         NodeBuilder {
             cache: cache,
@@ -78,6 +85,26 @@ impl NodeBuilder {
         }
     }
 
+    /// Encrypts the direct connections to all peers, using keys derived from the peers' public IDs.
+    /// Peers which don't agree to encrypt the connection are refused. Clients encrypt their
+    /// connections if their proxy wants them to.
+    pub fn encrypt_links(self) -> NodeBuilder {
+        NodeBuilder {
+            encrypt_links: true,
+            ..self
+        }
+    }
+
+    /// Configures how the node, as a member of a section, decides where joining nodes are
+    /// relocated to. Defaults to `HashingRelocation`; `BalancingRelocation` directs new nodes to
+    /// the least developed sections instead.
+    pub fn relocation_strategy(self, strategy: Box<RelocationStrategy>) -> NodeBuilder {
+        NodeBuilder {
+            relocation_strategy: strategy,
+            ..self
+        }
+    }
+
     /// Creates new `Node`.
     ///
     /// It will automatically connect to the network in the same way a client does, but then
@@ -109,31 +136,37 @@ impl NodeBuilder {
                           min_section_size: usize,
                           outbox: &mut EventBox)
                           -> (RoutingActionSender, StateMachine) {
-        StateMachine::new(move |action_sender, crust_service, timer, outbox2| if self.first {
-                              if let Some(state) = states::Node::first(action_sender,
-                                                                       self.cache,
-                                                                       crust_service,
-                                                                       FullId::new(),
-                                                                       min_section_size,
-                                                                       timer) {
-                                  State::Node(state)
-                              } else {
-                                  State::Terminated
-                              }
-                          } else if
-            self.deny_other_local_nodes && crust_service.has_peers_on_lan() {
-            error!("More than one routing node found on LAN. Currently this is not supported.");
-            outbox2.send_event(Event::Terminate);
-            State::Terminated
-        } else {
-            Bootstrapping::new(action_sender,
-                               self.cache,
-                               BootstrappingTargetState::JoiningNode,
-                               crust_service,
-                               FullId::new(),
-                               min_section_size,
-                               timer)
-                    .map_or(State::Terminated, State::Bootstrapping)
+        StateMachine::new(move |action_sender, crust_service, timer, outbox2| {
+            let session_keys = SessionKeys::new(self.encrypt_links);
+            if self.first {
+                if let Some(state) = states::Node::first(action_sender,
+                                                         self.cache,
+                                                         self.relocation_strategy,
+                                                         crust_service,
+                                                         FullId::new(),
+                                                         session_keys,
+                                                         min_section_size,
+                                                         timer) {
+                    State::Node(state)
+                } else {
+                    State::Terminated
+                }
+            } else if self.deny_other_local_nodes && crust_service.has_peers_on_lan() {
+                error!("More than one routing node found on LAN. Currently this is not supported.");
+                outbox2.send_event(Event::Terminate);
+                State::Terminated
+            } else {
+                Bootstrapping::new(action_sender,
+                                   self.cache,
+                                   self.relocation_strategy,
+                                   BootstrappingTargetState::JoiningNode,
+                                   crust_service,
+                                   FullId::new(),
+                                   session_keys,
+                                   min_section_size,
+                                   timer)
+                        .map_or(State::Terminated, State::Bootstrapping)
+            }
         },
                           outbox)
     }
@@ -146,6 +179,10 @@ impl NodeBuilder {
 /// authority. Its methods can be used to send requests and responses as either an individual
 /// `ManagedNode` or as a part of a section or group authority. Their `src` argument indicates that
 /// role, and can be any [`Authority`](enum.Authority.html) other than `Client`.
+///
+/// A node which has survived many churn events in its section is eventually relocated: it leaves
+/// the network and rejoins it with a new name in a random section. It then receives a new
+/// `Event::Connected` once it has been approved there.
 pub struct Node {
     interface_result_tx: Sender<Result<(), InterfaceError>>,
     interface_result_rx: Receiver<Result<(), InterfaceError>>,
@@ -160,6 +197,8 @@ impl Node {
             cache: Box::new(NullCache),
             first: false,
             deny_other_local_nodes: false,
+            encrypt_links: false,
+            relocation_strategy: Box::new(HashingRelocation),
         }
     }
 
@@ -174,6 +213,17 @@ impl Node {
         self.send_action(src, dst, user_msg, RELOCATE_PRIORITY)
     }
 
+    /// Send a `GetBatch` request to `dst` to retrieve several data items at once.
+    pub fn send_get_batch_request(&mut self,
+                                  src: Authority<XorName>,
+                                  dst: Authority<XorName>,
+                                  data_ids: Vec<DataIdentifier>,
+                                  id: MessageId)
+                                  -> Result<(), InterfaceError> {
+        let user_msg = UserMessage::Request(Request::GetBatch(data_ids, id));
+        self.send_action(src, dst, user_msg, RELOCATE_PRIORITY)
+    }
+
     /// Send a `Put` request to `dst` to store data on the network.
     pub fn send_put_request(&mut self,
                             src: Authority<XorName>,
@@ -185,6 +235,17 @@ impl Node {
         self.send_action(src, dst, user_msg, DEFAULT_PRIORITY)
     }
 
+    /// Send a `PutBatch` request to `dst` to store several data items on the network at once.
+    pub fn send_put_batch_request(&mut self,
+                                  src: Authority<XorName>,
+                                  dst: Authority<XorName>,
+                                  data: Vec<Data>,
+                                  id: MessageId)
+                                  -> Result<(), InterfaceError> {
+        let user_msg = UserMessage::Request(Request::PutBatch(data, id));
+        self.send_action(src, dst, user_msg, DEFAULT_PRIORITY)
+    }
+
     /// Send a `Post` request to `dst` to modify data on the network.
     pub fn send_post_request(&mut self,
                              src: Authority<XorName>,
@@ -271,6 +332,39 @@ impl Node {
         self.send_action(src, dst, user_msg, DEFAULT_PRIORITY)
     }
 
+    /// Respond to a `GetBatch` request with the result for each item.
+    pub fn send_get_batch_response(&mut self,
+                                   src: Authority<XorName>,
+                                   dst: Authority<XorName>,
+                                   results: Vec<(DataIdentifier, Result<Data, Vec<u8>>)>,
+                                   id: MessageId)
+                                   -> Result<(), InterfaceError> {
+        let user_msg = UserMessage::Response(Response::GetBatch {
+                                                 id: id,
+                                                 results: results,
+                                             });
+        let priority = if dst.is_client() {
+            CLIENT_GET_PRIORITY
+        } else {
+            RELOCATE_PRIORITY
+        };
+        self.send_action(src, dst, user_msg, priority)
+    }
+
+    /// Respond to a `PutBatch` request with the result for each item.
+    pub fn send_put_batch_response(&mut self,
+                                   src: Authority<XorName>,
+                                   dst: Authority<XorName>,
+                                   results: Vec<(DataIdentifier, Result<(), Vec<u8>>)>,
+                                   id: MessageId)
+                                   -> Result<(), InterfaceError> {
+        let user_msg = UserMessage::Response(Response::PutBatch {
+                                                 id: id,
+                                                 results: results,
+                                             });
+        self.send_action(src, dst, user_msg, DEFAULT_PRIORITY)
+    }
+
     /// Respond to a `Post` request indicating success.
     pub fn send_post_success(&mut self,
                              src: Authority<XorName>,
@@ -358,12 +452,14 @@ impl Node {
                                          dst: Authority<XorName>,
                                          data_stored: u64,
                                          space_available: u64,
+                                         key_rotations: Vec<KeyRotation>,
                                          id: MessageId)
                                          -> Result<(), InterfaceError> {
         let user_msg = UserMessage::Response(Response::GetAccountInfoSuccess {
                                                  id: id,
                                                  data_stored: data_stored,
                                                  space_available: space_available,
+                                                 key_rotations: key_rotations,
                                              });
         self.send_action(src, dst, user_msg, CLIENT_GET_PRIORITY)
     }
@@ -382,6 +478,111 @@ impl Node {
         self.send_action(src, dst, user_msg, CLIENT_GET_PRIORITY)
     }
 
+    /// Send the account data of a client that rotated its key to the new `ClientManager`.
+    pub fn send_migrate_account_request(&mut self,
+                                        src: Authority<XorName>,
+                                        dst: Authority<XorName>,
+                                        rotation: KeyRotation,
+                                        account: Vec<u8>,
+                                        id: MessageId)
+                                        -> Result<(), InterfaceError> {
+        let user_msg = UserMessage::Request(Request::MigrateAccount(rotation, account, id));
+        self.send_action(src, dst, user_msg, DEFAULT_PRIORITY)
+    }
+
+    /// Respond to a `RotateKey` request indicating success.
+    pub fn send_rotate_key_success(&mut self,
+                                   src: Authority<XorName>,
+                                   dst: Authority<XorName>,
+                                   id: MessageId)
+                                   -> Result<(), InterfaceError> {
+        let user_msg = UserMessage::Response(Response::RotateKeySuccess(id));
+        self.send_action(src, dst, user_msg, CLIENT_GET_PRIORITY)
+    }
+
+    /// Respond to a `RotateKey` request indicating failure.
+    pub fn send_rotate_key_failure(&mut self,
+                                   src: Authority<XorName>,
+                                   dst: Authority<XorName>,
+                                   external_error_indicator: Vec<u8>,
+                                   id: MessageId)
+                                   -> Result<(), InterfaceError> {
+        let user_msg = UserMessage::Response(Response::RotateKeyFailure {
+                                                 id: id,
+                                                 external_error_indicator: external_error_indicator,
+                                             });
+        self.send_action(src, dst, user_msg, CLIENT_GET_PRIORITY)
+    }
+
+    /// Send an application-defined request. If `src` is a section authority, it is delivered once
+    /// a quorum of the section has sent it.
+    pub fn send_custom_request(&mut self,
+                               src: Authority<XorName>,
+                               dst: Authority<XorName>,
+                               tag: u64,
+                               payload: Vec<u8>,
+                               cacheable: bool,
+                               id: MessageId)
+                               -> Result<(), InterfaceError> {
+        let user_msg = UserMessage::Request(Request::Custom {
+                                                tag: tag,
+                                                payload: payload,
+                                                cacheable: cacheable,
+                                                id: id,
+                                            });
+        self.send_action(src, dst, user_msg, DEFAULT_PRIORITY)
+    }
+
+    /// Send an application-defined response. If `cacheable` is `true`, nodes relaying it pass it
+    /// to their `Cache`.
+    pub fn send_custom_response(&mut self,
+                                src: Authority<XorName>,
+                                dst: Authority<XorName>,
+                                tag: u64,
+                                payload: Vec<u8>,
+                                cacheable: bool,
+                                id: MessageId)
+                                -> Result<(), InterfaceError> {
+        let user_msg = UserMessage::Response(Response::Custom {
+                                                 tag: tag,
+                                                 payload: payload,
+                                                 cacheable: cacheable,
+                                                 id: id,
+                                             });
+        self.send_action(src, dst, user_msg, CLIENT_GET_PRIORITY)
+    }
+
+    /// Vote for `value` to be agreed on by our section. If no other member has proposed it yet,
+    /// they receive an `Event::SectionProposal` and can vote for it, too. Once a quorum has voted
+    /// for it, all members receive an `Event::SectionAgreement`; if that doesn't happen in time,
+    /// an `Event::SectionAgreementTimeout`.
+    pub fn vote(&mut self, value: Vec<u8>) -> Result<(), InterfaceError> {
+        self.poll();
+
+        let action = Action::SectionVote {
+            value: value,
+            result_tx: self.interface_result_tx.clone(),
+        };
+        self.perform_action(action)
+    }
+
+    /// Asks the section responsible for `name` for its prefix and members. Once a response signed
+    /// by a quorum of that section arrives, it is raised as an `Event::SectionInfo` with the same
+    /// `message_id`.
+    pub fn send_section_info_request(&mut self,
+                                     name: XorName,
+                                     message_id: MessageId)
+                                     -> Result<(), InterfaceError> {
+        self.poll();
+
+        let action = Action::GetSectionInfo {
+            name: name,
+            message_id: message_id,
+            result_tx: self.interface_result_tx.clone(),
+        };
+        self.perform_action(action)
+    }
+
     /// Send a `Refresh` request from `src` to `dst` to trigger churn.
     pub fn send_refresh_request(&mut self,
                                 src: Authority<XorName>,
@@ -411,6 +612,21 @@ impl Node {
             .ok_or(RoutingError::Terminated)
     }
 
+    /// Returns our section's data chain: the quorum-signed history of its membership.
+    pub fn data_chain(&self) -> Result<&DataChain, RoutingError> {
+        self.machine
+            .data_chain()
+            .ok_or(RoutingError::Terminated)
+    }
+
+    /// Returns the number of cacheable requests this node responded to from its response cache,
+    /// and the number it had to relay because there was no cached response.
+    pub fn cache_hits_and_misses(&self) -> Result<(usize, usize), RoutingError> {
+        self.machine
+            .cache_hits_and_misses()
+            .ok_or(RoutingError::Terminated)
+    }
+
     fn send_action(&mut self,
                    src: Authority<XorName>,
                    dst: Authority<XorName>,
@@ -428,6 +644,10 @@ impl Node {
             result_tx: self.interface_result_tx.clone(),
         };
 
+        self.perform_action(action)
+    }
+
+    fn perform_action(&mut self, action: Action) -> Result<(), InterfaceError> {
         let transition = self.machine
             .current_mut()
             .handle_action(action, &mut self.event_buffer);
@@ -523,6 +743,24 @@ impl Node {
     pub fn clear_next_relocation_dst(&mut self) {
         self.machine.current_mut().set_next_relocation_dst(None)
     }
+
+    /// Sets whether this node relocates itself once the data chain selects it for relocation.
+    /// This is enabled by default.
+    pub fn set_age_relocation(&mut self, enabled: bool) {
+        self.machine.current_mut().set_age_relocation(enabled)
+    }
+
+    /// Makes this node claim that its group only consists of the `size` members closest to the
+    /// group's name when sending as a group authority, so that fewer signatures suffice.
+    pub fn set_claimed_group_size(&mut self, size: usize) {
+        self.machine.current_mut().set_claimed_group_size(size)
+    }
+
+    /// Returns the elder of the section `dst` which this node would send a message to via `route`,
+    /// or `None` if `dst` isn't a neighbouring section whose elders it knows.
+    pub fn elder_target(&self, dst: Authority<XorName>, route: u8) -> Option<XorName> {
+        self.machine.current().get_elder_target(&dst, route)
+    }
 }
 
 #[cfg(feature = "use-mock-crust")]
@@ -58,7 +58,8 @@ pub type SectionMap = BTreeMap<VersionedPrefix<XorName>, BTreeSet<PublicId>>;
 pub struct PeerDetails {
     pub routing_peer_details: Vec<(PeerId, XorName, bool)>,
     pub out_of_sync_peers: Vec<PeerId>,
-    pub removal_details: Vec<RemovalDetails<XorName>>,
+    /// Names which are in the routing table without a valid peer entry, and need to be removed.
+    pub invalid_rt_names: Vec<XorName>,
 }
 
 #[derive(Debug)]
@@ -360,6 +361,7 @@ struct Candidate {
     target_interval: Option<(XorName, XorName)>,
     new_pub_id: Option<PublicId>,
     new_client_auth: Option<Authority<XorName>>,
+    age: u32,
     state: CandidateState,
     passed_our_challenge: bool,
 }
@@ -372,6 +374,7 @@ impl Candidate {
             target_interval: None,
             new_pub_id: None,
             new_client_auth: None,
+            age: 0,
             state: CandidateState::VotedFor,
             passed_our_challenge: false,
         }
@@ -499,10 +502,11 @@ impl PeerManager {
 
     /// Our section has agreed that the candidate should be accepted pending proof of resource.
     /// Replaces any other potential candidate we have previously voted for.  Sets the candidate
-    /// state to `AcceptedAsCandidate`.
+    /// state to `AcceptedAsCandidate` and records the age it carries over.
     pub fn accept_as_candidate(&mut self,
                                old_pub_id: PublicId,
-                               target_interval: (XorName, XorName))
+                               target_interval: (XorName, XorName),
+                               age: u32)
                                -> BTreeSet<PublicId> {
         // Remove all candidates except this one
         let mut old_candidates = mem::replace(&mut self.candidates, HashMap::new());
@@ -516,6 +520,7 @@ impl PeerManager {
                 .entry(old_pub_id)
                 .or_insert_with(Candidate::new);
             candidate.target_interval = Some(target_interval);
+            candidate.age = age;
             candidate.state = CandidateState::AcceptedAsCandidate;
         }
 
@@ -604,6 +609,7 @@ impl PeerManager {
                             old_public_id: *old_pub_id,
                             new_public_id: *new_pub_id,
                             new_client_auth: *new_client_auth,
+                            age: candidate.age,
                             sections: self.ideal_rt(),
                         },
                         *new_pub_id.name()))
@@ -1404,9 +1410,7 @@ impl PeerManager {
         }
         for name in dropped_routing_nodes {
             let _ = self.peer_map.remove_by_name(&name);
-            if let Ok(removal_detail) = self.routing_table.remove(&name) {
-                result.removal_details.push(removal_detail);
-            }
+            result.invalid_rt_names.push(name);
         }
         let mut nodes_missing_from_rt = Vec::new();
         for peer in self.peer_map.peers() {
@@ -1721,6 +1725,13 @@ impl PeerManager {
         self.routing_table.need_to_add(name)
     }
 
+    /// Removes `name` from the routing table, without touching its peer entry.
+    pub fn remove_from_routing_table(&mut self,
+                                     name: &XorName)
+                                     -> Result<RemovalDetails<XorName>, RoutingTableError> {
+        self.routing_table.remove(name)
+    }
+
     /// Removes the given entry, returns the removed peer and if it was a routing node,
     /// the removal details
     pub fn remove_peer(&mut self,
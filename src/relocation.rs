@@ -0,0 +1,144 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use routing_table::{Prefix, RoutingTable};
+use std::collections::{BTreeMap, BTreeSet};
+use utils;
+use xor_name::XorName;
+
+/// Decides where nodes joining the network are relocated to.
+///
+/// A joining node first asks the section closest to its initial name (section X) to relocate it.
+/// Section X picks a destination name using `relocation_dst`, and the section closest to that
+/// name (section Y) then picks an interval within its own range, using `relocation_interval`,
+/// in which the node has to generate its new key.
+///
+/// All members of a section need to arrive at the same result, so implementations must be
+/// deterministic and only depend on their arguments.
+pub trait RelocationStrategy: Send {
+    /// Returns the name whose section a node currently called `current_name` is relocated to.
+    /// `close_nodes` are the members of section X, which is handling the relocation request.
+    ///
+    /// `sections` contains the members of every section as agreed by section X: its own members
+    /// from its data chain, and the others from section lists signed by a quorum of section X.
+    /// It must not be derived from a node's local routing table, which can differ between the
+    /// members of section X.
+    fn relocation_dst(&self,
+                      sections: &BTreeMap<Prefix<XorName>, BTreeSet<XorName>>,
+                      close_nodes: Vec<XorName>,
+                      current_name: &XorName)
+                      -> XorName;
+
+    /// Returns the interval in which a node joining our section has to generate its new name.
+    ///
+    /// The default implementation picks the middle third of the largest gap between our
+    /// section's members.
+    fn relocation_interval(&self, routing_table: &RoutingTable<XorName>) -> (XorName, XorName) {
+        utils::calculate_relocation_interval(routing_table.our_prefix(),
+                                             routing_table.our_section())
+    }
+}
+
+/// The default strategy: relocates a node to the hash of its current name and the two members of
+/// section X closest to it. The destination is therefore unpredictable for the joining node, but
+/// uniformly distributed over the whole network.
+pub struct HashingRelocation;
+
+impl RelocationStrategy for HashingRelocation {
+    fn relocation_dst(&self,
+                      _sections: &BTreeMap<Prefix<XorName>, BTreeSet<XorName>>,
+                      close_nodes: Vec<XorName>,
+                      current_name: &XorName)
+                      -> XorName {
+        utils::calculate_relocation_dst(close_nodes, current_name)
+    }
+}
+
+/// A strategy that relocates nodes to the section with the shortest prefix agreed by section X,
+/// and among those to the one with the fewest members. Ties are broken by the prefix order. The
+/// name within that section is still derived from the hash used by `HashingRelocation`.
+///
+/// This makes the network grow more evenly: sections that lag behind receive new nodes until
+/// they are ready to split, instead of every section being equally likely to be chosen.
+pub struct BalancingRelocation;
+
+impl RelocationStrategy for BalancingRelocation {
+    fn relocation_dst(&self,
+                      sections: &BTreeMap<Prefix<XorName>, BTreeSet<XorName>>,
+                      close_nodes: Vec<XorName>,
+                      current_name: &XorName)
+                      -> XorName {
+        let hashed_name = utils::calculate_relocation_dst(close_nodes, current_name);
+        sections.iter()
+            .min_by_key(|&(prefix, members)| (prefix.bit_count(), members.len()))
+            .map_or(hashed_name,
+                    |(prefix, _)| prefix.substituted_in(hashed_name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BalancingRelocation, RelocationStrategy};
+    use routing_table::Prefix;
+    use std::collections::{BTreeMap, BTreeSet};
+    use xor_name::XorName;
+
+    // Returns the agreed sections. Each section is given as the leading byte of its members, its
+    // prefix length and its number of members.
+    fn sections(sections: &[(u8, usize, usize)]) -> BTreeMap<Prefix<XorName>, BTreeSet<XorName>> {
+        sections.iter()
+            .map(|&(byte, bit_count, count)| {
+                let members = (0..count)
+                    .map(|i| {
+                             let mut name = XorName([byte; 32]);
+                             name.0[31] = i as u8;
+                             name
+                         })
+                    .collect();
+                (Prefix::new(bit_count, XorName([byte; 32])), members)
+            })
+            .collect()
+    }
+
+    fn dst_prefix(sections: &BTreeMap<Prefix<XorName>, BTreeSet<XorName>>) -> Prefix<XorName> {
+        let current_name = XorName([0x55; 32]);
+        let close_nodes = unwrap!(sections.values().next()).iter().cloned().collect();
+        let dst = BalancingRelocation.relocation_dst(sections, close_nodes, &current_name);
+        *unwrap!(sections.keys().find(|prefix| prefix.matches(&dst)))
+    }
+
+    #[test]
+    fn balancing_prefers_shortest_prefix() {
+        // Sections `00`, `01` and `1`. Section `1` has the most members, but the shortest prefix.
+        let sections = sections(&[(0x00, 2, 2), (0x40, 2, 2), (0x80, 1, 3)]);
+        assert_eq!(dst_prefix(&sections), Prefix::new(1, XorName([0x80; 32])));
+    }
+
+    #[test]
+    fn balancing_prefers_least_populated() {
+        // Sections `00`, `01`, `10` and `11`, of which `10` has the fewest members.
+        let sections = sections(&[(0x00, 2, 3), (0x40, 2, 4), (0x80, 2, 2), (0xc0, 2, 3)]);
+        assert_eq!(dst_prefix(&sections), Prefix::new(2, XorName([0x80; 32])));
+    }
+
+    #[test]
+    fn balancing_breaks_ties_by_prefix() {
+        // Sections `00`, `01`, `10` and `11`, of which `01` and `11` have the fewest members.
+        let sections = sections(&[(0x00, 2, 3), (0x40, 2, 2), (0x80, 2, 3), (0xc0, 2, 2)]);
+        assert_eq!(dst_prefix(&sections), Prefix::new(2, XorName([0x40; 32])));
+    }
+}
@@ -42,6 +42,9 @@ use utils::DisplayDuration;
 /// our section) and sending a `CandidateApproval` for this candidate. If the candidate cannot
 /// satisfy the proof of resource challenge within this time, no `CandidateApproval` is sent.
 pub const RESOURCE_PROOF_DURATION_SECS: u64 = 300;
+/// The total size of the resource proof data, i.e. the amount of storage a new node has to prove
+/// it can provide.
+pub const RESOURCE_PROOF_TARGET_SIZE: usize = 250 * 1024 * 1024;
 /// Maximum time a new node will wait to receive `NodeApproval` after receiving a
 /// `RelocateResponse`. This covers the built-in delay of the process and also allows time for the
 /// message to accumulate and be sent via four different routes.
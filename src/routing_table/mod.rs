@@ -189,6 +189,75 @@ pub enum OwnMergeState<T: Binary + Clone + Copy + Default + Hash + Xorable> {
 
 
 
+/// The changes in our responsibility for a set of data names caused by a change in our routing
+/// table, as returned by `RoutingTable::responsibility_diff()`.
+///
+/// A node is considered responsible for a name if it is among the `min_section_size` nodes closest
+/// to it, i.e. if it is part of the name's `NaeManager` authority.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ResponsibilityDiff<T: Binary + Clone + Copy + Default + Hash + Xorable> {
+    /// The names we are responsible for after the change.
+    pub held: BTreeSet<T>,
+    /// The names we are responsible for after the change, but weren't before it.
+    pub gained: BTreeSet<T>,
+    /// The names we were responsible for before the change, but aren't anymore.
+    pub lost: BTreeSet<T>,
+    /// The names we were responsible for before the change, each mapped to the nodes which have
+    /// become responsible for it and hence need to be sent the data.
+    pub hand_over: BTreeMap<T, BTreeSet<T>>,
+}
+
+
+
+/// Our routing table before and after a churn event.
+///
+/// Passed to the user with the churn events so they can work out which of their data they have
+/// gained or lost responsibility for via `responsibility_diff()`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Churn<T: Binary + Clone + Copy + Debug + Default + Hash + Xorable> {
+    old_table: Option<RoutingTable<T>>,
+    new_table: RoutingTable<T>,
+}
+
+impl<T: Binary + Clone + Copy + Debug + Default + Hash + Xorable> Churn<T> {
+    /// Creates a new `Churn` from the routing tables before and after the change.
+    pub fn new(old_table: RoutingTable<T>, new_table: RoutingTable<T>) -> Self {
+        Churn {
+            old_table: Some(old_table),
+            new_table: new_table,
+        }
+    }
+
+    /// Creates a new `Churn` for a node which has just joined the network, and so wasn't
+    /// responsible for any data before.
+    pub fn joined(new_table: RoutingTable<T>) -> Self {
+        Churn {
+            old_table: None,
+            new_table: new_table,
+        }
+    }
+
+    /// Returns our routing table before the change, or `None` if we have just joined.
+    pub fn old_table(&self) -> Option<&RoutingTable<T>> {
+        self.old_table.as_ref()
+    }
+
+    /// Returns our routing table after the change.
+    pub fn new_table(&self) -> &RoutingTable<T> {
+        &self.new_table
+    }
+
+    /// Returns the changes in our responsibility for the given data `names`.
+    pub fn responsibility_diff<'a, I>(&self, names: I) -> ResponsibilityDiff<T>
+        where I: IntoIterator<Item = &'a T>,
+              T: 'a
+    {
+        self.new_table.diff_responsibility(self.old_table.as_ref(), names)
+    }
+}
+
+
+
 /// A routing table to manage contacts for a node.
 ///
 /// It maintains a list of sections (identified by a `Prefix<T>`), each with a
@@ -389,6 +458,15 @@ impl<T: Binary + Clone + Copy + Debug + Default + Hash + Xorable> RoutingTable<T
         self.sections.keys().cloned().collect()
     }
 
+    /// Collects prefixes of all sections known by the routing table other than ours, together with
+    /// their versions, into a `BTreeMap`.
+    pub fn other_section_versions(&self) -> BTreeMap<Prefix<T>, u64> {
+        self.sections
+            .iter()
+            .map(|(prefix, &(version, _))| (*prefix, version))
+            .collect()
+    }
+
     /// Collects prefixes of all sections known by the routing table into a `BTreeSet`.
     pub fn prefixes(&self) -> BTreeSet<Prefix<T>> {
         self.all_sections_iter()
@@ -445,6 +523,18 @@ impl<T: Binary + Clone + Copy + Debug + Default + Hash + Xorable> RoutingTable<T
                  })
     }
 
+    /// Returns the changes in our responsibility for the given data `names`, comparing `self` to
+    /// `old_table`, our routing table before the change.
+    pub fn responsibility_diff<'a, I>(&self,
+                                      old_table: &RoutingTable<T>,
+                                      names: I)
+                                      -> ResponsibilityDiff<T>
+        where I: IntoIterator<Item = &'a T>,
+              T: 'a
+    {
+        self.diff_responsibility(Some(old_table), names)
+    }
+
     /// Returns true if `name` is in our section (including if it is our own name).
     pub fn is_in_our_section(&self, name: &T) -> bool {
         self.our_section.contains(name)
@@ -510,6 +600,49 @@ impl<T: Binary + Clone + Copy + Debug + Default + Hash + Xorable> RoutingTable<T
         }
     }
 
+    // Compares our responsibilities under `self` with those under `old_table`. If `old_table` is
+    // `None`, we weren't responsible for anything before.
+    fn diff_responsibility<'a, I>(&self,
+                                  old_table: Option<&RoutingTable<T>>,
+                                  names: I)
+                                  -> ResponsibilityDiff<T>
+        where I: IntoIterator<Item = &'a T>,
+              T: 'a
+    {
+        let mut diff = ResponsibilityDiff::default();
+        for name in names {
+            let new_holders: BTreeSet<T> = self.closest_known_names(name, self.min_section_size)
+                .into_iter()
+                .cloned()
+                .collect();
+            let old_holders: BTreeSet<T> = old_table.map_or_else(BTreeSet::new, |table| {
+                table.closest_known_names(name, table.min_section_size)
+                    .into_iter()
+                    .cloned()
+                    .collect()
+            });
+            let held_before = old_holders.contains(&self.our_name);
+            if new_holders.contains(&self.our_name) {
+                let _ = diff.held.insert(*name);
+                if !held_before {
+                    let _ = diff.gained.insert(*name);
+                }
+            } else if held_before {
+                let _ = diff.lost.insert(*name);
+            }
+            if held_before {
+                let recipients: BTreeSet<T> = new_holders.difference(&old_holders)
+                    .filter(|holder| **holder != self.our_name)
+                    .cloned()
+                    .collect();
+                if !recipients.is_empty() {
+                    let _ = diff.hand_over.insert(*name, recipients);
+                }
+            }
+        }
+        diff
+    }
+
     /// Finds the `count` names closest to `name` in the whole routing table.
     fn closest_known_names(&self, name: &T, count: usize) -> Vec<&T> {
         self.all_sections_iter()
@@ -1222,7 +1355,7 @@ mod tests {
     use super::*;
     use super::SPLIT_BUFFER;
     use itertools::Itertools;
-    use std::collections::BTreeSet;
+    use std::collections::{BTreeMap, BTreeSet};
     use std::str::FromStr;
 
     #[test]
@@ -1545,6 +1678,63 @@ mod tests {
         assert_eq!(Some(2), table.section_version(&prefix_str("01")));
     }
 
+    #[test]
+    fn responsibility_diff() {
+        let mut old_table = RoutingTable::new(0x00u8, 2);
+        for name in &[0x10, 0x80] {
+            unwrap!(old_table.add(*name));
+        }
+        let names = vec![0x01, 0x03, 0x11, 0x90];
+
+        // Two new nodes take over some of the names close to us.
+        let mut new_table = old_table.clone();
+        for name in &[0x02, 0x12] {
+            unwrap!(new_table.add(*name));
+        }
+        let diff = new_table.responsibility_diff(&old_table, &names);
+        assert_eq!(diff.held, names_from(&[0x01, 0x03]));
+        assert!(diff.gained.is_empty());
+        assert_eq!(diff.lost, names_from(&[0x11]));
+        let expected_hand_over: BTreeMap<u8, BTreeSet<u8>> =
+            vec![(0x01, names_from(&[0x02])),
+                 (0x03, names_from(&[0x02])),
+                 (0x11, names_from(&[0x12]))]
+                    .into_iter()
+                    .collect();
+        assert_eq!(diff.hand_over, expected_hand_over);
+
+        // A node close to some of the names leaves, so we become responsible for all of them and
+        // the remaining node needs the ones it didn't hold before.
+        let mut new_table = old_table.clone();
+        let _ = unwrap!(new_table.remove(&0x10));
+        let diff = Churn::new(old_table, new_table).responsibility_diff(&names);
+        assert_eq!(diff.held, names_from(&names));
+        assert_eq!(diff.gained, names_from(&[0x90]));
+        assert!(diff.lost.is_empty());
+        let expected_hand_over: BTreeMap<u8, BTreeSet<u8>> = vec![0x01, 0x03, 0x11]
+            .into_iter()
+            .map(|name| (name, names_from(&[0x80])))
+            .collect();
+        assert_eq!(diff.hand_over, expected_hand_over);
+    }
+
+    #[test]
+    fn responsibility_diff_after_joining() {
+        let mut table = RoutingTable::new(0x00u8, 2);
+        for name in &[0x10, 0x80] {
+            unwrap!(table.add(*name));
+        }
+        let diff = Churn::joined(table).responsibility_diff(&[0x01, 0x90]);
+        assert_eq!(diff.held, names_from(&[0x01]));
+        assert_eq!(diff.gained, diff.held);
+        assert!(diff.lost.is_empty());
+        assert!(diff.hand_over.is_empty());
+    }
+
+    fn names_from(names: &[u8]) -> BTreeSet<u8> {
+        names.iter().cloned().collect()
+    }
+
     fn prefix_str(s: &str) -> Prefix<u8> {
         unwrap!(Prefix::from_str(s))
     }
@@ -0,0 +1,212 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use {QUORUM_DENOMINATOR, QUORUM_NUMERATOR};
+use lru_time_cache::LruCache;
+use sha3::Digest256;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+use timer::Timer;
+use tiny_keccak::sha3_256;
+use xor_name::XorName;
+
+/// Time (in seconds) after which a proposal which hasn't reached quorum is dropped.
+pub const AGREEMENT_TIMEOUT_SECS: u64 = 60;
+
+/// The outcome of counting a vote.
+#[derive(Debug, PartialEq, Eq)]
+pub enum VoteResult {
+    /// This is the first vote for the value: someone proposed it.
+    NewProposal,
+    /// The vote has been counted, but there is no quorum yet, or there was one already.
+    Pending,
+    /// The vote completed a quorum. Contains the names of all voters.
+    Agreed(BTreeSet<XorName>),
+}
+
+struct Proposal {
+    voters: BTreeSet<XorName>,
+    value: Vec<u8>,
+    timer_token: u64,
+}
+
+/// Tallies the votes of our section's members for opaque values, keyed by their hash.
+pub struct SectionAgreement {
+    proposals: BTreeMap<Digest256, Proposal>,
+    /// Hashes of the values agreed on recently, so late votes don't start a new proposal.
+    agreed: LruCache<Digest256, ()>,
+    timer: Timer,
+}
+
+impl SectionAgreement {
+    pub fn new(timer: Timer) -> Self {
+        let expiry_duration = Duration::from_secs(AGREEMENT_TIMEOUT_SECS);
+        SectionAgreement {
+            proposals: BTreeMap::new(),
+            agreed: LruCache::with_expiry_duration(expiry_duration),
+            timer: timer,
+        }
+    }
+
+    /// Counts `voter`'s vote for `value`. Each member's vote carries the weight given in
+    /// `weights`, and a quorum is more than `QUORUM_NUMERATOR / QUORUM_DENOMINATOR` of their total.
+    pub fn add_vote(&mut self,
+                    value: Vec<u8>,
+                    voter: XorName,
+                    weights: &BTreeMap<XorName, u64>)
+                    -> VoteResult {
+        let hash = sha3_256(&value);
+        if self.agreed.contains_key(&hash) {
+            return VoteResult::Pending;
+        }
+
+        let is_new = !self.proposals.contains_key(&hash);
+        let quorum = {
+            let timer = &self.timer;
+            let proposal = self.proposals
+                .entry(hash)
+                .or_insert_with(|| {
+                    Proposal {
+                        voters: BTreeSet::new(),
+                        value: value,
+                        timer_token: timer.schedule(Duration::from_secs(AGREEMENT_TIMEOUT_SECS)),
+                    }
+                });
+            let _ = proposal.voters.insert(voter);
+            let votes: u64 = proposal.voters.iter().filter_map(|name| weights.get(name)).sum();
+            let total: u64 = weights.values().sum();
+            votes * QUORUM_DENOMINATOR as u64 > total * QUORUM_NUMERATOR as u64
+        };
+
+        if quorum {
+            let _ = self.agreed.insert(hash, ());
+            // Safe to use `unwrap!()` here as we just inserted the entry if it didn't exist.
+            VoteResult::Agreed(unwrap!(self.proposals.remove(&hash)).voters)
+        } else if is_new {
+            VoteResult::NewProposal
+        } else {
+            VoteResult::Pending
+        }
+    }
+
+    /// If `token` belongs to a proposal which hasn't reached quorum, drops it and returns its
+    /// value.
+    pub fn handle_timeout(&mut self, token: u64) -> Option<Vec<u8>> {
+        let hash = match self.proposals
+                  .iter()
+                  .find(|&(_, proposal)| proposal.timer_token == token) {
+            Some((hash, _)) => *hash,
+            None => return None,
+        };
+        self.proposals
+            .remove(&hash)
+            .map(|proposal| proposal.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use maidsafe_utilities::event_sender::MaidSafeEventCategory;
+    use rand;
+    use std::sync::mpsc;
+    use types::RoutingActionSender;
+
+    fn new_agreement() -> SectionAgreement {
+        let (action_sender, _) = mpsc::channel();
+        let (category_sender, _) = mpsc::channel();
+        let sender = RoutingActionSender::new(action_sender,
+                                              MaidSafeEventCategory::Routing,
+                                              category_sender);
+        SectionAgreement::new(Timer::new(sender))
+    }
+
+    fn equal_weights(voters: &[XorName]) -> BTreeMap<XorName, u64> {
+        voters.iter().map(|voter| (*voter, 1)).collect()
+    }
+
+    #[test]
+    fn quorum() {
+        let mut agreement = new_agreement();
+        let voters = (0..8).map(|_| rand::random()).collect::<Vec<XorName>>();
+        let weights = equal_weights(&voters);
+        let value = b"value".to_vec();
+
+        assert_eq!(agreement.add_vote(value.clone(), voters[0], &weights),
+                   VoteResult::NewProposal);
+        // Repeated votes are only counted once.
+        assert_eq!(agreement.add_vote(value.clone(), voters[0], &weights),
+                   VoteResult::Pending);
+        for voter in &voters[1..4] {
+            assert_eq!(agreement.add_vote(value.clone(), *voter, &weights),
+                       VoteResult::Pending);
+        }
+        let expected_voters = voters[..5].iter().cloned().collect();
+        assert_eq!(agreement.add_vote(value.clone(), voters[4], &weights),
+                   VoteResult::Agreed(expected_voters));
+
+        // Late votes neither complete another quorum nor start a new proposal.
+        for voter in &voters[5..] {
+            assert_eq!(agreement.add_vote(value.clone(), *voter, &weights),
+                       VoteResult::Pending);
+        }
+    }
+
+    #[test]
+    fn weighted_quorum() {
+        let mut agreement = new_agreement();
+        let voters = (0..4).map(|_| rand::random()).collect::<Vec<XorName>>();
+        let mut weights = equal_weights(&voters);
+        let _ = weights.insert(voters[0], 3);
+        let value = b"value".to_vec();
+
+        // The oldest member alone doesn't have a quorum, but together with any other one it has.
+        assert_eq!(agreement.add_vote(value.clone(), voters[0], &weights),
+                   VoteResult::NewProposal);
+        let expected_voters = voters[..2].iter().cloned().collect();
+        assert_eq!(agreement.add_vote(value.clone(), voters[1], &weights),
+                   VoteResult::Agreed(expected_voters));
+
+        // The three younger members together don't have a quorum.
+        let value = b"other value".to_vec();
+        assert_eq!(agreement.add_vote(value.clone(), voters[1], &weights),
+                   VoteResult::NewProposal);
+        for voter in &voters[2..] {
+            assert_eq!(agreement.add_vote(value.clone(), *voter, &weights),
+                       VoteResult::Pending);
+        }
+    }
+
+    #[test]
+    fn timeout() {
+        let mut agreement = new_agreement();
+        let voters = (0..8).map(|_| rand::random()).collect::<Vec<XorName>>();
+        let weights = equal_weights(&voters);
+        let value = b"value".to_vec();
+        assert_eq!(agreement.add_vote(value.clone(), voters[0], &weights),
+                   VoteResult::NewProposal);
+        let token = unwrap!(agreement.proposals.values().next()).timer_token;
+
+        assert_eq!(agreement.handle_timeout(token + 1), None);
+        assert_eq!(agreement.handle_timeout(token), Some(value.clone()));
+        assert_eq!(agreement.handle_timeout(token), None);
+
+        // After a timeout, the value can be proposed again.
+        assert_eq!(agreement.add_vote(value, voters[1], &weights),
+                   VoteResult::NewProposal);
+    }
+}
@@ -101,12 +101,15 @@ impl SectionListCache {
     }
 
     /// Returns the currently signed section list for `prefix` along with a quorum of signatures.
-    // TODO: Remove this when the method is used in production
-    #[cfg(feature="use-mock-crust")]
     pub fn get_signatures(&self, prefix: Prefix<XorName>) -> Option<&(SectionList, Signatures)> {
         self.lists_cache.get(&prefix)
     }
 
+    /// Returns all section lists which are currently signed by a quorum of our section.
+    pub fn signed_lists(&self) -> Vec<&SectionList> {
+        self.lists_cache.values().map(|&(ref list, _)| list).collect()
+    }
+
     fn prune(&mut self) {
         let mut to_remove = vec![];
         for (prefix, map) in &mut self.signatures {
@@ -0,0 +1,308 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use client::Client;
+use data::{Data, DataIdentifier, ImmutableData};
+use error::{InterfaceError, RoutingError};
+use messages::Response;
+use routing_table::Authority;
+use rust_sodium::crypto::hash::sha256;
+use rust_sodium::crypto::secretbox;
+use std::cmp;
+use std::collections::BTreeMap;
+use types::MessageId;
+use xor_name::XorName;
+
+/// The maximum size of a chunk's content before encryption. Encryption only adds
+/// `secretbox::MACBYTES`, so every chunk fits into a single `ImmutableData`.
+pub const MAX_CHUNK_SIZE: usize = 1024 * 1024;
+/// The minimum size of a chunk's content. Content smaller than `3 * MIN_CHUNK_SIZE` is not split
+/// up but kept in the data map itself.
+pub const MIN_CHUNK_SIZE: usize = 1024;
+
+/// The details of one encrypted chunk.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ChunkDetails {
+    /// The name of the `ImmutableData` holding the encrypted chunk.
+    pub name: XorName,
+    /// The SHA-256 hash of the chunk's content before encryption.
+    pub source_hash: [u8; 32],
+    /// The size of the chunk's content before encryption.
+    pub source_size: u64,
+}
+
+/// Describes how to retrieve and decrypt self-encrypted content.
+///
+/// A data map can be serialised and stored as `ImmutableData` or in a `StructuredData`. If it is
+/// too large for that, its serialisation can itself be self-encrypted.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum DataMap {
+    /// Content too small to be split up, kept in the data map itself.
+    Content(Vec<u8>),
+    /// The chunks the content was split into, in order.
+    Chunks(Vec<ChunkDetails>),
+}
+
+impl DataMap {
+    /// Returns the size of the content, in bytes, as claimed by the data map.
+    pub fn content_size(&self) -> u64 {
+        match *self {
+            DataMap::Content(ref content) => content.len() as u64,
+            DataMap::Chunks(ref chunks) => {
+                chunks
+                    .iter()
+                    .fold(0, |size, chunk| size.saturating_add(chunk.source_size))
+            }
+        }
+    }
+}
+
+/// Splits `content` into encrypted chunks, and returns the data map and the chunks. The chunks
+/// still need to be stored on the network.
+///
+/// Each chunk is encrypted with a key derived from the hashes of the preceding chunks' content,
+/// wrapping around at the start. So the same content always yields the same chunks, but they can
+/// only be decrypted with the data map.
+pub fn encrypt(content: &[u8]) -> (DataMap, Vec<ImmutableData>) {
+    if content.len() < 3 * MIN_CHUNK_SIZE {
+        return (DataMap::Content(content.to_vec()), vec![]);
+    }
+    let count = cmp::max(3, (content.len() + MAX_CHUNK_SIZE - 1) / MAX_CHUNK_SIZE);
+    let sources: Vec<&[u8]> = (0..count)
+        .map(|index| &content[index * content.len() / count..(index + 1) * content.len() / count])
+        .collect();
+    let hashes: Vec<[u8; 32]> = sources.iter().map(|source| sha256::hash(source).0).collect();
+    let mut details = Vec::with_capacity(count);
+    let mut chunks = Vec::with_capacity(count);
+    for (index, source) in sources.iter().enumerate() {
+        let (key, nonce) = chunk_key(&hashes, index);
+        let chunk = ImmutableData::new(secretbox::seal(source, &nonce, &key));
+        details.push(ChunkDetails {
+                         name: *chunk.name(),
+                         source_hash: hashes[index],
+                         source_size: source.len() as u64,
+                     });
+        chunks.push(chunk);
+    }
+    (DataMap::Chunks(details), chunks)
+}
+
+/// Reassembles the content described by `data_map` from its chunks.
+pub fn decrypt(data_map: &DataMap,
+               chunks: &BTreeMap<XorName, ImmutableData>)
+               -> Result<Vec<u8>, RoutingError> {
+    let details = match *data_map {
+        DataMap::Content(ref content) => return Ok(content.clone()),
+        DataMap::Chunks(ref details) => details,
+    };
+    // The chunk keys are derived from the two preceding chunks, so there have to be at least three.
+    if details.len() < 3 {
+        return Err(RoutingError::InvalidDataMap);
+    }
+    let hashes: Vec<[u8; 32]> = details.iter().map(|detail| detail.source_hash).collect();
+    // The sizes in the data map are not trusted, so the content isn't pre-allocated.
+    let mut content = Vec::new();
+    for (index, detail) in details.iter().enumerate() {
+        let chunk = match chunks.get(&detail.name) {
+            Some(chunk) => chunk,
+            None => return Err(RoutingError::ChunkNotFound(detail.name)),
+        };
+        let (key, nonce) = chunk_key(&hashes, index);
+        let source = secretbox::open(chunk.value(), &nonce, &key)
+            .map_err(|()| RoutingError::InvalidChunk(detail.name))?;
+        if source.len() as u64 != detail.source_size ||
+           sha256::hash(&source).0 != detail.source_hash {
+            return Err(RoutingError::InvalidChunk(detail.name));
+        }
+        content.extend_from_slice(&source);
+    }
+    Ok(content)
+}
+
+/// Retrieves self-encrypted content from the network using `Get` requests sent by a `Client`.
+///
+/// Pass every response the client receives to `handle_response` until it returns the content.
+pub struct ContentReader {
+    data_map: DataMap,
+    /// The chunks requested but not received yet, by message ID.
+    pending: BTreeMap<MessageId, XorName>,
+    chunks: BTreeMap<XorName, ImmutableData>,
+}
+
+impl ContentReader {
+    /// Sends `Get` requests for all chunks in `data_map` via `client`.
+    pub fn new(client: &Client, data_map: DataMap) -> Result<ContentReader, InterfaceError> {
+        let mut pending = BTreeMap::new();
+        if let DataMap::Chunks(ref details) = data_map {
+            for detail in details {
+                let msg_id = MessageId::new();
+                client.send_get_request(Authority::NaeManager(detail.name),
+                                        DataIdentifier::Immutable(detail.name),
+                                        msg_id)?;
+                let _ = pending.insert(msg_id, detail.name);
+            }
+        }
+        Ok(ContentReader {
+               data_map: data_map,
+               pending: pending,
+               chunks: BTreeMap::new(),
+           })
+    }
+
+    /// Handles a response received by the client.
+    ///
+    /// Returns the content once all chunks have been received, or an error if a chunk couldn't be
+    /// retrieved or is invalid. Returns `None` while chunks are missing and for unrelated
+    /// responses.
+    pub fn handle_response(&mut self,
+                           response: &Response)
+                           -> Option<Result<Vec<u8>, RoutingError>> {
+        match *response {
+            Response::GetSuccess(Data::Immutable(ref chunk), ref id) => {
+                match self.pending.remove(id) {
+                    Some(name) if name == *chunk.name() => {
+                        let _ = self.chunks.insert(name, chunk.clone());
+                    }
+                    Some(name) => return Some(Err(RoutingError::InvalidChunk(name))),
+                    None => return None,
+                }
+            }
+            Response::GetFailure { ref id, .. } => {
+                return self.pending
+                           .remove(id)
+                           .map(|name| Err(RoutingError::ChunkNotFound(name)));
+            }
+            _ => return None,
+        }
+        self.content()
+    }
+
+    /// Returns the content if all chunks have been received. This is the case right away if the
+    /// data map contains the content itself.
+    pub fn content(&self) -> Option<Result<Vec<u8>, RoutingError>> {
+        if self.pending.is_empty() {
+            Some(decrypt(&self.data_map, &self.chunks))
+        } else {
+            None
+        }
+    }
+}
+
+// Returns the key and nonce for the chunk at `index`: they are derived from the content hashes of
+// the preceding two chunks.
+fn chunk_key(hashes: &[[u8; 32]], index: usize) -> (secretbox::Key, secretbox::Nonce) {
+    let count = hashes.len();
+    let key = secretbox::Key(hashes[(index + count - 1) % count]);
+    let mut nonce = secretbox::Nonce([0; secretbox::NONCEBYTES]);
+    nonce
+        .0
+        .copy_from_slice(&hashes[(index + count - 2) % count][..secretbox::NONCEBYTES]);
+    (key, nonce)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DataMap, MAX_CHUNK_SIZE, MIN_CHUNK_SIZE, decrypt, encrypt};
+    use data::ImmutableData;
+    use error::RoutingError;
+    use rand::{self, Rng};
+    use std::collections::BTreeMap;
+    use xor_name::XorName;
+
+    fn random_content(size: usize) -> Vec<u8> {
+        let mut content = vec![0; size];
+        rand::thread_rng().fill_bytes(&mut content);
+        content
+    }
+
+    fn by_name(chunks: Vec<ImmutableData>) -> BTreeMap<XorName, ImmutableData> {
+        chunks.into_iter().map(|chunk| (*chunk.name(), chunk)).collect()
+    }
+
+    #[test]
+    fn small_content() {
+        let content = random_content(3 * MIN_CHUNK_SIZE - 1);
+        let (data_map, chunks) = encrypt(&content);
+        assert_eq!(data_map, DataMap::Content(content.clone()));
+        assert!(chunks.is_empty());
+        assert_eq!(unwrap!(decrypt(&data_map, &BTreeMap::new())), content);
+    }
+
+    #[test]
+    fn round_trip() {
+        for &size in &[3 * MIN_CHUNK_SIZE, 3 * MAX_CHUNK_SIZE + 1] {
+            let content = random_content(size);
+            let (data_map, chunks) = encrypt(&content);
+            assert_eq!(data_map.content_size(), size as u64);
+            assert!(chunks.len() >= 3);
+            assert!(chunks.iter().all(ImmutableData::validate_size));
+            assert!(chunks.iter().all(|chunk| !content.starts_with(chunk.value())));
+            // Encryption is deterministic.
+            assert_eq!(encrypt(&content), (data_map.clone(), chunks.clone()));
+            assert_eq!(unwrap!(decrypt(&data_map, &by_name(chunks))), content);
+        }
+    }
+
+    #[test]
+    fn missing_or_invalid_chunk() {
+        let content = random_content(4 * MIN_CHUNK_SIZE);
+        let (data_map, mut chunks) = encrypt(&content);
+        let name = *chunks[1].name();
+
+        let _ = chunks.remove(1);
+        match decrypt(&data_map, &by_name(chunks.clone())) {
+            Err(RoutingError::ChunkNotFound(missing)) => assert_eq!(missing, name),
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        // A chunk which doesn't decrypt with its key.
+        let mut chunks = by_name(chunks);
+        let _ = chunks.insert(name, ImmutableData::new(random_content(MIN_CHUNK_SIZE)));
+        match decrypt(&data_map, &chunks) {
+            Err(RoutingError::InvalidChunk(invalid)) => assert_eq!(invalid, name),
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn hostile_data_map() {
+        let content = random_content(4 * MIN_CHUNK_SIZE);
+        let (data_map, chunks) = encrypt(&content);
+        let mut details = match data_map {
+            DataMap::Chunks(details) => details,
+            DataMap::Content(_) => panic!("Content should have been split into chunks."),
+        };
+        details[0].source_size = u64::max_value();
+        details[1].source_size = u64::max_value();
+        let name = details[0].name;
+        let data_map = DataMap::Chunks(details.clone());
+        assert_eq!(data_map.content_size(), u64::max_value());
+        match decrypt(&data_map, &by_name(chunks.clone())) {
+            Err(RoutingError::InvalidChunk(invalid)) => assert_eq!(invalid, name),
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        // Too few chunks to derive the keys from.
+        for count in 0..3 {
+            let data_map = DataMap::Chunks(details[..count].to_vec());
+            match decrypt(&data_map, &by_name(chunks.clone())) {
+                Err(RoutingError::InvalidDataMap) => (),
+                result => panic!("Unexpected result {:?}", result),
+            }
+        }
+    }
+}
@@ -0,0 +1,331 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use crust::PeerId;
+use error::RoutingError;
+use id::PublicId;
+use maidsafe_utilities::serialisation::{deserialise, serialise};
+use messages::Message;
+use rust_sodium::crypto::{box_, sign};
+use std::collections::HashMap;
+
+/// Returns the bytes signed in an identification message: `identity`, i.e. the sender's serialised
+/// public ID(s), followed by whether the sender wants the connection to be encrypted. So the flag
+/// can't be changed without invalidating the signature.
+pub fn identify_payload(identity: &[u8], session_encryption: bool) -> Vec<u8> {
+    let mut payload = identity.to_vec();
+    payload.push(session_encryption as u8);
+    payload
+}
+
+/// Verifies the signature of a `BootstrapIdentify` message.
+pub fn verify_bootstrap_identify(public_id: &PublicId,
+                                 session_encryption: bool,
+                                 signature: &sign::Signature)
+                                 -> Result<(), RoutingError> {
+    let payload = identify_payload(&serialise(public_id)?, session_encryption);
+    if sign::verify_detached(signature, &payload, public_id.signing_public_key()) {
+        Ok(())
+    } else {
+        Err(RoutingError::FailedSignature)
+    }
+}
+
+/// The encryption state of a single direct connection.
+#[derive(Default)]
+struct Session {
+    /// The shared key, once we know the peer's encryption key and both sides want encryption.
+    key: Option<box_::PrecomputedKey>,
+    /// Whether we have sent our own public ID to the peer, so that it can derive the key, too.
+    identified: bool,
+    /// Whether the peer has started encrypting. From then on, plaintext from it is rejected.
+    peer_encrypts: bool,
+}
+
+/// Session encryption of direct connections.
+///
+/// The keys are derived from both peers' `box_` keys, which are exchanged in the signed
+/// `ClientIdentify`/`BootstrapIdentify` and `NodeIdentify`/`CandidateIdentify` messages. Each of
+/// these carries a signed flag indicating whether the sender wants the connection to be encrypted.
+/// If both do, every subsequent message is sent as a `Message::Encrypted`. If we require it but the
+/// peer doesn't want it, the connection is refused rather than falling back to plaintext.
+///
+/// We start encrypting as soon as we have both sent our own and received the peer's
+/// identification. The peer may still send plaintext until it has received ours, so plaintext is
+/// only rejected after the first encrypted message has arrived.
+pub struct SessionKeys {
+    enabled: bool,
+    required: bool,
+    sessions: HashMap<PeerId, Session>,
+}
+
+impl SessionKeys {
+    /// Creates an empty collection. If `enabled` is `true`, every connection must be encrypted,
+    /// otherwise none will be.
+    pub fn new(enabled: bool) -> Self {
+        SessionKeys {
+            enabled: enabled,
+            required: enabled,
+            sessions: HashMap::new(),
+        }
+    }
+
+    /// Creates an empty collection which encrypts the connections to peers that want it, but
+    /// doesn't refuse the others. This is used by clients, which can't choose their proxies.
+    pub fn opportunistic() -> Self {
+        SessionKeys {
+            enabled: true,
+            required: false,
+            sessions: HashMap::new(),
+        }
+    }
+
+    /// Returns whether we want our connections to be encrypted.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Records that we have sent our public ID to the peer.
+    pub fn identified_to(&mut self, peer_id: PeerId) {
+        if self.enabled {
+            self.sessions.entry(peer_id).or_insert_with(Session::default).identified = true;
+        }
+    }
+
+    /// Records the peer's public encryption key, received in its identification message, and
+    /// whether the peer wants the connection to be encrypted. Returns an error if we require it to
+    /// be encrypted but the peer doesn't want it: the connection should then be dropped.
+    pub fn peer_identified(&mut self,
+                           peer_id: PeerId,
+                           their_key: &box_::PublicKey,
+                           our_key: &box_::SecretKey,
+                           peer_enabled: bool)
+                           -> Result<(), RoutingError> {
+        match (self.enabled, peer_enabled) {
+            (true, true) => {
+                let session = self.sessions.entry(peer_id).or_insert_with(Session::default);
+                session.key = Some(box_::precompute(their_key, our_key));
+                Ok(())
+            }
+            (true, false) if self.required => Err(RoutingError::EncryptionRefused),
+            _ => Ok(()),
+        }
+    }
+
+    /// Forgets the session with the given peer, e.g. because the connection was lost.
+    pub fn remove(&mut self, peer_id: &PeerId) {
+        let _ = self.sessions.remove(peer_id);
+    }
+
+    /// Returns the serialised message `bytes`, encrypted if the connection to `peer_id` is.
+    pub fn encrypt(&self, peer_id: &PeerId, bytes: Vec<u8>) -> Result<Vec<u8>, RoutingError> {
+        let key = match self.sessions.get(peer_id) {
+            Some(&Session {
+                     key: Some(ref key),
+                     identified: true,
+                     ..
+                 }) => key,
+            _ => return Ok(bytes),
+        };
+        let nonce = box_::gen_nonce();
+        let message = Message::Encrypted {
+            nonce: nonce.0,
+            content: box_::seal_precomputed(&bytes, &nonce, key),
+        };
+        Ok(serialise(&message)?)
+    }
+
+    /// Returns the serialised message received from `peer_id`, decrypted if necessary.
+    pub fn decrypt(&mut self, peer_id: &PeerId, bytes: Vec<u8>) -> Result<Vec<u8>, RoutingError> {
+        let session = match self.sessions.get_mut(peer_id) {
+            Some(session) => session,
+            None => return Ok(bytes),
+        };
+        match deserialise(&bytes)? {
+            Message::Encrypted { nonce, content } => {
+                let key = session
+                    .key
+                    .as_ref()
+                    .ok_or(RoutingError::AsymmetricDecryptionFailure)?;
+                let plaintext = box_::open_precomputed(&content, &box_::Nonce(nonce), key)
+                    .map_err(|()| RoutingError::AsymmetricDecryptionFailure)?;
+                session.peer_encrypts = true;
+                Ok(plaintext)
+            }
+            _ if session.peer_encrypts => Err(RoutingError::UnencryptedMessage),
+            _ => Ok(bytes),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use id::FullId;
+    use maidsafe_utilities::SeededRng;
+    use messages::DirectMessage;
+    use rust_sodium;
+
+    struct Peer {
+        full_id: FullId,
+        peer_id: PeerId,
+        keys: SessionKeys,
+    }
+
+    impl Peer {
+        #[cfg(not(feature = "use-mock-crust"))]
+        fn new(_index: usize, enabled: bool) -> Self {
+            let full_id = FullId::new();
+            Peer {
+                peer_id: PeerId(*full_id.public_id().encrypting_public_key()),
+                full_id: full_id,
+                keys: SessionKeys::new(enabled),
+            }
+        }
+
+        #[cfg(feature = "use-mock-crust")]
+        fn new(index: usize, enabled: bool) -> Self {
+            Peer {
+                full_id: FullId::new(),
+                peer_id: PeerId(index),
+                keys: SessionKeys::new(enabled),
+            }
+        }
+    }
+
+    // Updates both peers' sessions as if they had exchanged identification messages, and returns
+    // the results for A and B.
+    fn exchange_identify(peer_a: &mut Peer,
+                         peer_b: &mut Peer)
+                         -> (Result<(), RoutingError>, Result<(), RoutingError>) {
+        let enabled_a = peer_a.keys.is_enabled();
+        let enabled_b = peer_b.keys.is_enabled();
+        peer_a.keys.identified_to(peer_b.peer_id);
+        peer_b.keys.identified_to(peer_a.peer_id);
+        let result_a = peer_a
+            .keys
+            .peer_identified(peer_b.peer_id,
+                             peer_b.full_id.public_id().encrypting_public_key(),
+                             peer_a.full_id.encrypting_private_key(),
+                             enabled_b);
+        let result_b = peer_b
+            .keys
+            .peer_identified(peer_a.peer_id,
+                             peer_a.full_id.public_id().encrypting_public_key(),
+                             peer_b.full_id.encrypting_private_key(),
+                             enabled_a);
+        (result_a, result_b)
+    }
+
+    fn message_bytes() -> Vec<u8> {
+        unwrap!(serialise(&Message::Direct(DirectMessage::BootstrapDeny)))
+    }
+
+    #[test]
+    fn encrypted_round_trip() {
+        let mut rng = SeededRng::thread_rng();
+        unwrap!(rust_sodium::init_with_rng(&mut rng));
+
+        let mut peer_a = Peer::new(0, true);
+        let mut peer_b = Peer::new(1, true);
+
+        // Before the identification messages have been exchanged, messages are not encrypted.
+        let plaintext = message_bytes();
+        let sent = unwrap!(peer_a.keys.encrypt(&peer_b.peer_id, plaintext.clone()));
+        assert_eq!(sent, plaintext);
+
+        let (result_a, result_b) = exchange_identify(&mut peer_a, &mut peer_b);
+        unwrap!(result_a);
+        unwrap!(result_b);
+
+        // Plaintext is still accepted until the peer starts encrypting.
+        let received = unwrap!(peer_b.keys.decrypt(&peer_a.peer_id, plaintext.clone()));
+        assert_eq!(received, plaintext);
+
+        let encrypted = unwrap!(peer_a.keys.encrypt(&peer_b.peer_id, plaintext.clone()));
+        match unwrap!(deserialise(&encrypted)) {
+            Message::Encrypted { .. } => (),
+            message => panic!("Unexpected message {:?}", message),
+        }
+        let decrypted = unwrap!(peer_b.keys.decrypt(&peer_a.peer_id, encrypted));
+        assert_eq!(decrypted, plaintext);
+
+        match peer_b.keys.decrypt(&peer_a.peer_id, plaintext) {
+            Err(RoutingError::UnencryptedMessage) => (),
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn refused_unless_both_enabled() {
+        let mut rng = SeededRng::thread_rng();
+        unwrap!(rust_sodium::init_with_rng(&mut rng));
+
+        // A peer which wants encryption refuses one which doesn't.
+        let mut peer_a = Peer::new(0, true);
+        let mut peer_b = Peer::new(1, false);
+        match exchange_identify(&mut peer_a, &mut peer_b) {
+            (Err(RoutingError::EncryptionRefused), Ok(())) => (),
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        // An opportunistic peer accepts one which doesn't want encryption.
+        let mut peer_a = Peer::new(0, false);
+        let mut peer_b = Peer::new(1, false);
+        peer_a.keys = SessionKeys::opportunistic();
+        let (result_a, result_b) = exchange_identify(&mut peer_a, &mut peer_b);
+        unwrap!(result_a);
+        unwrap!(result_b);
+        let plaintext = message_bytes();
+        let sent = unwrap!(peer_a.keys.encrypt(&peer_b.peer_id, plaintext.clone()));
+        assert_eq!(sent, plaintext);
+
+        // If neither wants encryption, messages are sent in plaintext.
+        let mut peer_a = Peer::new(0, false);
+        let mut peer_b = Peer::new(1, false);
+        let (result_a, result_b) = exchange_identify(&mut peer_a, &mut peer_b);
+        unwrap!(result_a);
+        unwrap!(result_b);
+
+        let plaintext = message_bytes();
+        let sent = unwrap!(peer_a.keys.encrypt(&peer_b.peer_id, plaintext.clone()));
+        assert_eq!(sent, plaintext);
+        let sent = unwrap!(peer_b.keys.encrypt(&peer_a.peer_id, plaintext.clone()));
+        assert_eq!(sent, plaintext);
+        let received = unwrap!(peer_a.keys.decrypt(&peer_b.peer_id, plaintext.clone()));
+        assert_eq!(received, plaintext);
+    }
+
+    #[test]
+    fn wrong_key_is_rejected() {
+        let mut rng = SeededRng::thread_rng();
+        unwrap!(rust_sodium::init_with_rng(&mut rng));
+
+        let mut peer_a = Peer::new(0, true);
+        let mut peer_b = Peer::new(1, true);
+        let mut peer_c = Peer::new(2, true);
+        let _ = exchange_identify(&mut peer_a, &mut peer_b);
+        let _ = exchange_identify(&mut peer_a, &mut peer_c);
+
+        // A message encrypted for C can't be opened by B.
+        let encrypted = unwrap!(peer_a.keys.encrypt(&peer_c.peer_id, message_bytes()));
+        match peer_b.keys.decrypt(&peer_a.peer_id, encrypted) {
+            Err(RoutingError::AsymmetricDecryptionFailure) => (),
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+}
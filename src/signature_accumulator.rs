@@ -163,7 +163,7 @@ mod tests {
                                                       rand::random()),
             };
             let prefix = Prefix::new(0, *unwrap!(all_ids.iter().next()).name());
-            let lists = vec![SectionList::new(prefix, all_ids)];
+            let lists = vec![SectionList::new(prefix.with_version(0), all_ids)];
             let signed_msg = unwrap!(SignedMessage::new(routing_msg, msg_sender_id, lists));
             let signature_msgs = other_ids
                 .map(|id| {
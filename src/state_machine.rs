@@ -18,11 +18,12 @@
 use action::Action;
 use crust::{CrustEventSender, PeerId, Service};
 use crust::Event as CrustEvent;
+use data_chain::DataChain;
 use id::{FullId, PublicId};
 use maidsafe_utilities::event_sender::MaidSafeEventCategory;
 use outbox::EventBox;
 #[cfg(feature = "use-mock-crust")]
-use routing_table::Prefix;
+use routing_table::{Authority, Prefix};
 use routing_table::RoutingTable;
 #[cfg(feature = "use-mock-crust")]
 use rust_sodium::crypto::sign;
@@ -63,7 +64,7 @@ impl State {
     pub fn handle_action(&mut self, action: Action, outbox: &mut EventBox) -> Transition {
         match *self {
             State::Bootstrapping(ref mut state) => state.handle_action(action),
-            State::Client(ref mut state) => state.handle_action(action),
+            State::Client(ref mut state) => state.handle_action(action, outbox),
             State::JoiningNode(ref mut state) => state.handle_action(action, outbox),
             State::Node(ref mut state) => state.handle_action(action, outbox),
             State::Terminated => Transition::Terminate,
@@ -91,6 +92,20 @@ impl State {
         }
     }
 
+    fn data_chain(&self) -> Option<&DataChain> {
+        match *self {
+            State::Node(ref state) => Some(state.data_chain()),
+            _ => None,
+        }
+    }
+
+    fn cache_hits_and_misses(&self) -> Option<(usize, usize)> {
+        match *self {
+            State::Node(ref state) => Some(state.cache_hits_and_misses()),
+            _ => None,
+        }
+    }
+
     fn close_group(&self, name: XorName, count: usize) -> Option<Vec<XorName>> {
         self.base_state()
             .and_then(|state| state.close_group(name, count))
@@ -184,6 +199,25 @@ impl State {
             node.set_next_relocation_interval(interval);
         }
     }
+
+    pub fn set_age_relocation(&mut self, enabled: bool) {
+        if let State::Node(ref mut node) = *self {
+            node.set_age_relocation(enabled);
+        }
+    }
+
+    pub fn set_claimed_group_size(&mut self, size: usize) {
+        if let State::Node(ref mut node) = *self {
+            node.set_claimed_group_size(size);
+        }
+    }
+
+    pub fn get_elder_target(&self, dst: &Authority<XorName>, route: u8) -> Option<XorName> {
+        match *self {
+            State::Node(ref state) => state.get_elder_target(dst, route),
+            _ => None,
+        }
+    }
 }
 
 /// Enum returned from many message handlers
@@ -201,6 +235,8 @@ pub enum Transition {
         new_id: FullId,
         our_section: BTreeSet<PublicId>,
     },
+    // `Node` state leaving its section to rejoin the network as a `JoiningNode`.
+    Relocate,
     Terminate,
 }
 
@@ -312,6 +348,18 @@ impl StateMachine {
                 };
                 self.state = new_state;
             }
+            Relocate => {
+                let new_state = match mem::replace(&mut self.state, State::Terminated) {
+                    State::Node(node) => {
+                        let crust_sender = CrustEventSender::new(self.crust_tx.clone(),
+                                                                 MaidSafeEventCategory::Crust,
+                                                                 self.category_tx.clone());
+                        node.into_relocating(&mut self.crust_rx, crust_sender, outbox)
+                    }
+                    _ => unreachable!(),
+                };
+                self.state = new_state;
+            }
             Terminate => self.terminate(),
         }
     }
@@ -354,6 +402,14 @@ impl StateMachine {
         self.state.routing_table()
     }
 
+    pub fn data_chain(&self) -> Option<&DataChain> {
+        self.state.data_chain()
+    }
+
+    pub fn cache_hits_and_misses(&self) -> Option<(usize, usize)> {
+        self.state.cache_hits_and_misses()
+    }
+
     pub fn close_group(&self, name: XorName, count: usize) -> Option<Vec<XorName>> {
         self.state.close_group(name, count)
     }
@@ -21,14 +21,16 @@ use action::Action;
 use cache::Cache;
 use crust::{CrustUser, PeerId, Service};
 use crust::Event as CrustEvent;
-use error::RoutingError;
+use error::{InterfaceError, RoutingError};
 use event::Event;
 use id::{FullId, PublicId};
 use maidsafe_utilities::serialisation;
 use messages::{DirectMessage, Message};
 use outbox::EventBox;
+use relocation::RelocationStrategy;
 use routing_table::Authority;
 use rust_sodium::crypto::sign;
+use session_keys::{self, SessionKeys};
 use state_machine::{State, Transition};
 use stats::Stats;
 use std::collections::{BTreeSet, HashSet};
@@ -46,7 +48,7 @@ const BOOTSTRAP_TIMEOUT_SECS: u64 = 20;
 // FIXME - See https://maidsafe.atlassian.net/browse/MAID-2026 for info on removing this exclusion.
 #[cfg_attr(feature="cargo-clippy", allow(large_enum_variant))]
 pub enum TargetState {
-    Client,
+    Client { verify_responses: bool },
     JoiningNode,
     Node {
         old_full_id: FullId,
@@ -60,10 +62,12 @@ pub struct Bootstrapping {
     bootstrap_blacklist: HashSet<SocketAddr>,
     bootstrap_connection: Option<(PeerId, u64)>,
     cache: Box<Cache>,
+    relocation_strategy: Box<RelocationStrategy>,
     target_state: TargetState,
     crust_service: Service,
     full_id: FullId,
     min_section_size: usize,
+    session_keys: SessionKeys,
     stats: Stats,
     timer: Timer,
 }
@@ -71,14 +75,16 @@ pub struct Bootstrapping {
 impl Bootstrapping {
     pub fn new(action_sender: RoutingActionSender,
                cache: Box<Cache>,
+               relocation_strategy: Box<RelocationStrategy>,
                target_state: TargetState,
                mut crust_service: Service,
                full_id: FullId,
+               session_keys: SessionKeys,
                min_section_size: usize,
                timer: Timer)
                -> Option<Self> {
         match target_state {
-            TargetState::Client => {
+            TargetState::Client { .. } => {
                 let _ = crust_service.start_bootstrap(HashSet::new(), CrustUser::Client);
             }
             TargetState::JoiningNode => {
@@ -96,10 +102,12 @@ impl Bootstrapping {
                  bootstrap_blacklist: HashSet::new(),
                  bootstrap_connection: None,
                  cache: cache,
+                 relocation_strategy: relocation_strategy,
                  target_state: target_state,
                  crust_service: crust_service,
                  full_id: full_id,
                  min_section_size: min_section_size,
+                 session_keys: session_keys,
                  stats: Stats::new(),
                  timer: timer,
              })
@@ -114,6 +122,10 @@ impl Bootstrapping {
                 // preserve the pre-refactor behaviour.
                 let _ = result_tx.send(Ok(()));
             }
+            Action::SectionVote { result_tx, .. } |
+            Action::GetSectionInfo { result_tx, .. } => {
+                let _ = result_tx.send(Err(InterfaceError::InvalidState));
+            }
             Action::Name { result_tx } => {
                 let _ = result_tx.send(*self.name());
             }
@@ -180,22 +192,27 @@ impl Bootstrapping {
                              outbox: &mut EventBox)
                              -> State {
         match self.target_state {
-            TargetState::Client { .. } => {
+            TargetState::Client { verify_responses } => {
                 State::Client(Client::from_bootstrapping(self.crust_service,
                                                          self.full_id,
+                                                         self.session_keys,
                                                          self.min_section_size,
                                                          proxy_peer_id,
                                                          proxy_public_id,
+                                                         self.bootstrap_blacklist,
                                                          self.stats,
                                                          self.timer,
+                                                         verify_responses,
                                                          outbox))
             }
             TargetState::JoiningNode => {
                 if let Some(joining_node) =
                     JoiningNode::from_bootstrapping(self.action_sender,
                                                     self.cache,
+                                                    self.relocation_strategy,
                                                     self.crust_service,
                                                     self.full_id,
+                                                    self.session_keys,
                                                     self.min_section_size,
                                                     proxy_peer_id,
                                                     proxy_public_id,
@@ -215,9 +232,11 @@ impl Bootstrapping {
                 State::Node(Node::from_bootstrapping(our_section,
                                                      self.action_sender,
                                                      self.cache,
+                                                     self.relocation_strategy,
                                                      self.crust_service,
                                                      old_full_id,
                                                      self.full_id,
+                                                     self.session_keys,
                                                      self.min_section_size,
                                                      proxy_peer_id,
                                                      proxy_public_id,
@@ -278,6 +297,7 @@ impl Bootstrapping {
                           peer_id: PeerId,
                           bytes: Vec<u8>)
                           -> Result<Transition, RoutingError> {
+        let bytes = self.session_keys.decrypt(&peer_id, bytes)?;
         match serialisation::deserialise(&bytes) {
             Ok(Message::Direct(direct_msg)) => Ok(self.handle_direct_message(direct_msg, peer_id)),
             Ok(message) => {
@@ -293,9 +313,11 @@ impl Bootstrapping {
                              peer_id: PeerId)
                              -> Transition {
         match direct_message {
-            DirectMessage::BootstrapIdentify { public_id } => {
-                self.handle_bootstrap_identify(public_id, peer_id)
-            }
+            DirectMessage::BootstrapIdentify {
+                public_id,
+                session_encryption,
+                signature,
+            } => self.handle_bootstrap_identify(public_id, session_encryption, signature, peer_id),
             DirectMessage::BootstrapDeny => self.handle_bootstrap_deny(),
             _ => {
                 debug!("{:?} - Unhandled direct message: {:?}",
@@ -306,7 +328,30 @@ impl Bootstrapping {
         }
     }
 
-    fn handle_bootstrap_identify(&mut self, public_id: PublicId, peer_id: PeerId) -> Transition {
+    fn handle_bootstrap_identify(&mut self,
+                                 public_id: PublicId,
+                                 session_encryption: bool,
+                                 signature: sign::Signature,
+                                 peer_id: PeerId)
+                                 -> Transition {
+        let result = session_keys::verify_bootstrap_identify(&public_id,
+                                                             session_encryption,
+                                                             &signature)
+            .and_then(|()| {
+                self.session_keys
+                    .peer_identified(peer_id,
+                                     public_id.encrypting_public_key(),
+                                     self.full_id.encrypting_private_key(),
+                                     session_encryption)
+            });
+        if let Err(error) = result {
+            warn!("{:?} Rejecting BootstrapIdentify from {:?}: {:?}",
+                  self,
+                  peer_id,
+                  error);
+            self.rebootstrap();
+            return Transition::Stay;
+        }
         Transition::IntoBootstrapped {
             proxy_peer_id: peer_id,
             proxy_public_id: public_id,
@@ -334,17 +379,21 @@ impl Bootstrapping {
                 return;
             }
         };
-        let signature = sign::sign_detached(&serialised_public_id,
+        let session_encryption = self.session_keys.is_enabled();
+        let signature = sign::sign_detached(&session_keys::identify_payload(&serialised_public_id,
+                                                                            session_encryption),
                                             self.full_id.signing_private_key());
 
         let direct_message = DirectMessage::ClientIdentify {
             serialised_public_id: serialised_public_id,
             signature: signature,
             client_restriction: self.client_restriction(),
+            session_encryption: session_encryption,
         };
 
         self.stats().count_direct_message(&direct_message);
         self.send_message(&peer_id, Message::Direct(direct_message));
+        self.session_keys.identified_to(peer_id);
     }
 
     fn disconnect_peer(&mut self, peer_id: &PeerId) {
@@ -360,6 +409,7 @@ impl Bootstrapping {
                    self,
                    bootstrap_id);
             self.crust_service.disconnect(bootstrap_id);
+            self.session_keys.remove(&bootstrap_id);
             let crust_user = if self.client_restriction() {
                 CrustUser::Client
             } else {
@@ -380,6 +430,10 @@ impl Base for Bootstrapping {
         &self.full_id
     }
 
+    fn session_keys(&self) -> &SessionKeys {
+        &self.session_keys
+    }
+
     fn stats(&mut self) -> &mut Stats {
         &mut self.stats
     }
@@ -15,76 +15,138 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software.
 
-use super::common::{Base, Bootstrapped, USER_MSG_CACHE_EXPIRY_DURATION_SECS};
+use super::common::{self, Base, Bootstrapped, SECTION_INFO_TIMEOUT_SECS, SectionInfoRequest,
+                    USER_MSG_CACHE_EXPIRY_DURATION_SECS};
 use ack_manager::{Ack, AckManager};
 use action::Action;
-use crust::{PeerId, Service};
+use crust::{CrustUser, PeerId, Service};
 use crust::Event as CrustEvent;
 use error::{InterfaceError, RoutingError};
 use event::Event;
 use id::{FullId, PublicId};
+use itertools::Itertools;
+use lru_time_cache::LruCache;
 use maidsafe_utilities::serialisation;
-use messages::{HopMessage, Message, MessageContent, RoutingMessage, SignedMessage, UserMessage,
-               UserMessageCache};
+use messages::{DirectMessage, HopMessage, Message, MessageContent, RoutingMessage, SectionList,
+               SignedMessage, UserMessage, UserMessageCache};
 use outbox::EventBox;
 use routing_message_filter::{FilteringResult, RoutingMessageFilter};
-use routing_table::Authority;
+use routing_table::{Authority, Prefix, VersionedPrefix, Xorable};
+use rust_sodium::crypto::sign;
+use session_keys::{self, SessionKeys};
+use sha3::Digest256;
 use state_machine::Transition;
 use stats::Stats;
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::fmt::{self, Debug, Formatter};
+use std::net::SocketAddr;
 use std::time::Duration;
 use timer::Timer;
+use types::MessageId;
 use xor_name::XorName;
 
+type Signatures = BTreeMap<PublicId, sign::Signature>;
+
+/// Number of proxy nodes a client tries to stay connected to.
+const PROXY_COUNT: usize = 3;
+/// Number of proxies which must relay the same `SectionInfo` before we use it to verify messages.
+const MIN_SECTION_INFO_PROXIES: usize = 2;
+/// Maximum number of user messages we remember as having failed verification.
+const MAX_UNVERIFIED_MSGS: usize = 1000;
+
+/// A `GetSectionInfo` request sent via each of our proxies.
+///
+/// A proxy could forge all the responses it relays, so the responses relayed by each proxy are
+/// collected separately, and the section is only accepted once all of them agree on it.
+struct ProxiedSectionInfoRequest {
+    name: XorName,
+    /// Whether the user asked for it, as opposed to us needing it to verify a message.
+    requested: bool,
+    /// The responses relayed by each proxy, by its name, and the section list and signatures the
+    /// section agreed on via that proxy.
+    via_proxies: BTreeMap<XorName, (SectionInfoRequest, Option<(SectionList, Signatures)>)>,
+}
+
 /// A node connecting a user to the network, as opposed to a routing / data storage node.
 ///
-/// Each client has a _proxy_: a node through which all requests are routed.
+/// Each client has a _proxy_: a node through which all requests are routed. It also keeps
+/// connections to a few secondary proxies, and if the primary one is lost, the first secondary
+/// takes over and all unacknowledged messages are resent via the new primary.
 pub struct Client {
     ack_mgr: AckManager,
+    bootstrap_blacklist: HashSet<SocketAddr>,
     crust_service: Service,
     full_id: FullId,
     min_section_size: usize,
-    proxy_peer_id: PeerId,
-    proxy_public_id: PublicId,
+    /// Connected proxies, the primary one first.
+    proxies: Vec<(PeerId, PublicId)>,
+    /// Peers we sent a `ClientIdentify` to, but which have not yet accepted us as a client.
+    pending_proxies: HashSet<PeerId>,
     routing_msg_filter: RoutingMessageFilter,
+    /// The `GetSectionInfo` requests we sent, by message ID.
+    section_info_requests: LruCache<MessageId, ProxiedSectionInfoRequest>,
+    session_keys: SessionKeys,
     stats: Stats,
     timer: Timer,
     user_msg_cache: UserMessageCache,
+    /// Whether messages from group authorities are checked against the members we know their
+    /// section to have.
+    verify_responses: bool,
+    /// The sections about which enough of our proxies relayed the same valid `SectionInfo`, by
+    /// prefix.
+    known_sections: BTreeMap<Prefix<XorName>, BTreeSet<PublicId>>,
+    /// Messages held back until we receive the `SectionInfo` about their source name, together
+    /// with the timer token for giving up on it.
+    awaiting_section_info: BTreeMap<XorName, (u64, Vec<SignedMessage>)>,
+    /// The hashes of user messages of which some part failed verification.
+    unverified_msgs: LruCache<Digest256, ()>,
 }
 
 impl Client {
     #[cfg_attr(feature = "cargo-clippy", allow(too_many_arguments))]
     pub fn from_bootstrapping(crust_service: Service,
                               full_id: FullId,
+                              session_keys: SessionKeys,
                               min_section_size: usize,
                               proxy_peer_id: PeerId,
                               proxy_public_id: PublicId,
+                              bootstrap_blacklist: HashSet<SocketAddr>,
                               stats: Stats,
                               timer: Timer,
+                              verify_responses: bool,
                               outbox: &mut EventBox)
                               -> Self {
-        let client = Client {
+        let mut client = Client {
             ack_mgr: AckManager::new(),
+            bootstrap_blacklist: bootstrap_blacklist,
             crust_service: crust_service,
             full_id: full_id,
             min_section_size: min_section_size,
-            proxy_peer_id: proxy_peer_id,
-            proxy_public_id: proxy_public_id,
+            proxies: vec![(proxy_peer_id, proxy_public_id)],
+            pending_proxies: HashSet::new(),
             routing_msg_filter: RoutingMessageFilter::new(),
+            section_info_requests:
+                LruCache::with_expiry_duration(Duration::from_secs(SECTION_INFO_TIMEOUT_SECS)),
+            session_keys: session_keys,
             stats: stats,
             timer: timer,
             user_msg_cache: UserMessageCache::with_expiry_duration(
                 Duration::from_secs(USER_MSG_CACHE_EXPIRY_DURATION_SECS)),
+            verify_responses: verify_responses,
+            known_sections: BTreeMap::new(),
+            awaiting_section_info: BTreeMap::new(),
+            unverified_msgs: LruCache::with_expiry_duration_and_capacity(
+                Duration::from_secs(USER_MSG_CACHE_EXPIRY_DURATION_SECS), MAX_UNVERIFIED_MSGS),
         };
 
         debug!("{:?} - State changed to client.", client);
 
         outbox.send_event(Event::Connected);
+        client.find_more_proxies();
         client
     }
 
-    pub fn handle_action(&mut self, action: Action) -> Transition {
+    pub fn handle_action(&mut self, action: Action, outbox: &mut EventBox) -> Transition {
         match action {
             Action::ClientSendRequest {
                 content,
@@ -92,12 +154,7 @@ impl Client {
                 priority,
                 result_tx,
             } => {
-                let src = Authority::Client {
-                    client_key: *self.full_id.public_id().signing_public_key(),
-                    proxy_node_name: *self.proxy_public_id.name(),
-                    peer_id: self.crust_service.id(),
-                };
-
+                let src = self.client_auth();
                 let user_msg = UserMessage::Request(content);
                 let result = match self.send_user_message(src, dst, user_msg, priority) {
                     Err(RoutingError::Interface(err)) => Err(err),
@@ -106,13 +163,26 @@ impl Client {
 
                 let _ = result_tx.send(result);
             }
-            Action::NodeSendMessage { result_tx, .. } => {
+            Action::NodeSendMessage { result_tx, .. } |
+            Action::SectionVote { result_tx, .. } => {
                 let _ = result_tx.send(Err(InterfaceError::InvalidState));
             }
+            Action::GetSectionInfo {
+                name,
+                message_id,
+                result_tx,
+            } => {
+                let result = match self.send_section_info_request(name, message_id, true) {
+                    Err(RoutingError::Interface(err)) => Err(err),
+                    Err(_) | Ok(()) => Ok(()),
+                };
+
+                let _ = result_tx.send(result);
+            }
             Action::Name { result_tx } => {
                 let _ = result_tx.send(*self.name());
             }
-            Action::Timeout(token) => self.handle_timeout(token),
+            Action::Timeout(token) => self.handle_timeout(token, outbox),
             Action::ResourceProofResult(..) => {
                 error!("Action::ResourceProofResult received by Client state");
             }
@@ -129,6 +199,13 @@ impl Client {
                               outbox: &mut EventBox)
                               -> Transition {
         match crust_event {
+            CrustEvent::BootstrapConnect(peer_id, socket_addr) => {
+                self.handle_bootstrap_connect(peer_id, socket_addr)
+            }
+            CrustEvent::BootstrapFailed => {
+                debug!("{:?} Failed to connect to any further proxies.", self);
+                Transition::Stay
+            }
             CrustEvent::LostPeer(peer_id) => self.handle_lost_peer(peer_id, outbox),
             CrustEvent::NewMessage(peer_id, bytes) => {
                 self.handle_new_message(peer_id, bytes, outbox)
@@ -140,13 +217,171 @@ impl Client {
         }
     }
 
+    /// Our authority as the source of messages sent via our primary proxy.
+    fn client_auth(&self) -> Authority<XorName> {
+        Authority::Client {
+            client_key: *self.full_id.public_id().signing_public_key(),
+            proxy_node_name: *self.primary_proxy_name(),
+            peer_id: self.crust_service.id(),
+        }
+    }
+
+    fn primary_proxy_name(&self) -> &XorName {
+        // There is always at least one proxy: we terminate once the last one is lost.
+        self.proxies[0].1.name()
+    }
+
+    fn proxy_public_id(&self, peer_id: &PeerId) -> Option<&PublicId> {
+        self.proxies
+            .iter()
+            .find(|&&(ref proxy_peer_id, _)| proxy_peer_id == peer_id)
+            .map(|&(_, ref public_id)| public_id)
+    }
+
+    /// Starts bootstrapping against further nodes if we have fewer than `PROXY_COUNT` proxies.
+    fn find_more_proxies(&mut self) {
+        if self.proxies.len() + self.pending_proxies.len() < PROXY_COUNT {
+            let _ = self.crust_service
+                .start_bootstrap(self.bootstrap_blacklist.clone(), CrustUser::Client);
+        }
+    }
+
+    fn handle_bootstrap_connect(&mut self, peer_id: PeerId, socket_addr: SocketAddr) -> Transition {
+        if self.proxy_public_id(&peer_id).is_some() || self.pending_proxies.contains(&peer_id) {
+            warn!("{:?} Got more than one BootstrapConnect for peer {:?}.",
+                  self,
+                  peer_id);
+        } else if self.proxies.len() + self.pending_proxies.len() >= PROXY_COUNT {
+            debug!("{:?} Already have enough proxies - disconnecting {:?}.",
+                   self,
+                   peer_id);
+            let _ = self.crust_service.disconnect(peer_id);
+        } else {
+            debug!("{:?} Received BootstrapConnect from {:?}.", self, peer_id);
+            let _ = self.bootstrap_blacklist.insert(socket_addr);
+            self.send_client_identify(peer_id);
+        }
+        Transition::Stay
+    }
+
+    fn send_client_identify(&mut self, peer_id: PeerId) {
+        debug!("{:?} - Sending ClientIdentify to {:?}.", self, peer_id);
+
+        let serialised_public_id = match serialisation::serialise(self.full_id.public_id()) {
+            Ok(rslt) => rslt,
+            Err(e) => {
+                error!("Failed to serialise public ID: {:?}", e);
+                return;
+            }
+        };
+        let session_encryption = self.session_keys.is_enabled();
+        let signature = sign::sign_detached(&session_keys::identify_payload(&serialised_public_id,
+                                                                            session_encryption),
+                                            self.full_id.signing_private_key());
+
+        let direct_message = DirectMessage::ClientIdentify {
+            serialised_public_id: serialised_public_id,
+            signature: signature,
+            client_restriction: true,
+            session_encryption: session_encryption,
+        };
+
+        let _ = self.pending_proxies.insert(peer_id);
+        self.stats().count_direct_message(&direct_message);
+        self.send_message(&peer_id, Message::Direct(direct_message));
+        self.session_keys.identified_to(peer_id);
+    }
+
+    fn handle_direct_message(&mut self,
+                             direct_message: DirectMessage,
+                             peer_id: PeerId)
+                             -> Result<Transition, RoutingError> {
+        if !self.pending_proxies.contains(&peer_id) {
+            return Err(RoutingError::UnknownConnection(peer_id));
+        }
+
+        match direct_message {
+            DirectMessage::BootstrapIdentify {
+                public_id,
+                session_encryption,
+                signature,
+            } => {
+                let _ = self.pending_proxies.remove(&peer_id);
+                let result = session_keys::verify_bootstrap_identify(&public_id,
+                                                                     session_encryption,
+                                                                     &signature)
+                    .and_then(|()| {
+                        self.session_keys
+                            .peer_identified(peer_id,
+                                             public_id.encrypting_public_key(),
+                                             self.full_id.encrypting_private_key(),
+                                             session_encryption)
+                    });
+                if let Err(error) = result {
+                    warn!("{:?} Rejecting secondary proxy {:?}: {:?}",
+                          self,
+                          peer_id,
+                          error);
+                    self.session_keys.remove(&peer_id);
+                    let _ = self.crust_service.disconnect(peer_id);
+                    self.find_more_proxies();
+                    return Ok(Transition::Stay);
+                }
+                debug!("{:?} Added secondary proxy {:?} ({:?}).",
+                       self,
+                       public_id.name(),
+                       peer_id);
+                self.proxies.push((peer_id, public_id));
+            }
+            DirectMessage::BootstrapDeny => {
+                debug!("{:?} Secondary proxy {:?} denied the connection.",
+                       self,
+                       peer_id);
+                let _ = self.pending_proxies.remove(&peer_id);
+                self.session_keys.remove(&peer_id);
+                let _ = self.crust_service.disconnect(peer_id);
+                self.find_more_proxies();
+            }
+            _ => {
+                debug!("{:?} - Unhandled direct message: {:?}",
+                       self,
+                       direct_message);
+            }
+        }
+        Ok(Transition::Stay)
+    }
+
+    /// Makes the first secondary proxy our primary one and resends all unacknowledged messages
+    /// through it, so no in-flight request is lost.
+    fn fail_over(&mut self) {
+        info!("{:?} Switching primary proxy to {:?} ({:?}).",
+              self,
+              self.proxies[0].1.name(),
+              self.proxies[0].0);
+        for unacked_msg in self.ack_mgr.take_pending() {
+            if let Err(error) =
+                self.send_routing_message_via_route(unacked_msg.routing_msg, unacked_msg.route) {
+                debug!("{:?} Failed to resend message: {:?}", self, error);
+            }
+        }
+        self.find_more_proxies();
+    }
+
     fn handle_ack_response(&mut self, ack: Ack) -> Transition {
         self.ack_mgr.receive(ack);
         Transition::Stay
     }
 
-    fn handle_timeout(&mut self, token: u64) {
-        self.resend_unacknowledged_timed_out_msgs(token)
+    fn handle_timeout(&mut self, token: u64, outbox: &mut EventBox) {
+        let timed_out_name = self.awaiting_section_info
+            .iter()
+            .find(|&(_, &(msg_token, _))| msg_token == token)
+            .map(|(&name, _)| name);
+        if let Some(name) = timed_out_name {
+            self.verify_awaiting_messages(&name, outbox);
+        } else {
+            self.resend_unacknowledged_timed_out_msgs(token)
+        }
     }
 
     fn handle_new_message(&mut self,
@@ -154,13 +389,17 @@ impl Client {
                           bytes: Vec<u8>,
                           outbox: &mut EventBox)
                           -> Transition {
-        let transition = match serialisation::deserialise(&bytes) {
+        let message = self.session_keys
+            .decrypt(&peer_id, bytes)
+            .and_then(|bytes| Ok(serialisation::deserialise(&bytes)?));
+        let transition = match message {
             Ok(Message::Hop(hop_msg)) => self.handle_hop_message(hop_msg, peer_id, outbox),
+            Ok(Message::Direct(direct_msg)) => self.handle_direct_message(direct_msg, peer_id),
             Ok(message) => {
                 debug!("{:?} - Unhandled new message: {:?}", self, message);
                 Ok(Transition::Stay)
             }
-            Err(error) => Err(RoutingError::SerialisationError(error)),
+            Err(error) => Err(error),
         };
 
         match transition {
@@ -178,16 +417,24 @@ impl Client {
                           peer_id: PeerId,
                           outbox: &mut EventBox)
                           -> Result<Transition, RoutingError> {
-        if self.proxy_peer_id == peer_id {
-            hop_msg
-                .verify(self.proxy_public_id.signing_public_key())?;
+        let proxy_name = if let Some(public_id) = self.proxy_public_id(&peer_id) {
+            hop_msg.verify(public_id.signing_public_key())?;
+            *public_id.name()
         } else {
             return Err(RoutingError::UnknownConnection(peer_id));
-        }
+        };
 
         let signed_msg = hop_msg.content;
         signed_msg.check_integrity(self.min_section_size())?;
 
+        // A proxy must only relay messages addressed to us via itself, so that we can tell apart
+        // the responses relayed by different proxies.
+        if let Authority::Client { proxy_node_name, .. } = signed_msg.routing_message().dst {
+            if proxy_node_name != proxy_name {
+                return Err(RoutingError::InvalidDestination);
+            }
+        }
+
         let routing_msg = signed_msg.routing_message();
         let in_authority = self.in_authority(&routing_msg.dst);
         if in_authority {
@@ -206,7 +453,96 @@ impl Client {
             return Ok(Transition::Stay);
         }
 
-        Ok(self.dispatch_routing_message(routing_msg.clone(), outbox))
+        match routing_msg.src {
+            Authority::ClientManager(_) |
+            Authority::NaeManager(_) |
+            Authority::NodeManager(_) if self.verify_responses => {
+                Ok(self.verify_group_message(signed_msg.clone(), true, outbox))
+            }
+            _ => Ok(self.dispatch_routing_message(routing_msg.clone(), outbox)),
+        }
+    }
+
+    /// Checks that `signed_msg` is signed by a quorum of its source group, as far as we know the
+    /// members of its section, and dispatches it. If we don't know the section or the check fails,
+    /// and `fetch` is `true`, the message is held back until we have asked the section for its
+    /// current `SectionInfo`. Otherwise, the user message it is part of is marked as unverified.
+    fn verify_group_message(&mut self,
+                            signed_msg: SignedMessage,
+                            fetch: bool,
+                            outbox: &mut EventBox)
+                            -> Transition {
+        let src_name = signed_msg.routing_message().src.name();
+        let verified = self.known_group(&src_name)
+            .map_or(false, |group| signed_msg.signed_by_quorum_of(&group));
+        if !verified {
+            if fetch {
+                self.fetch_section_info(src_name, signed_msg);
+                return Transition::Stay;
+            }
+            debug!("{:?} - Failed to verify {:?}.", self, signed_msg);
+            if let MessageContent::UserMessagePart { hash, .. } =
+                signed_msg.routing_message().content {
+                let _ = self.unverified_msgs.insert(hash, ());
+            }
+        }
+        self.dispatch_routing_message(signed_msg.into_routing_message(), outbox)
+    }
+
+    /// Returns the `min_section_size` members of the known section of `name` which are closest to
+    /// it, i.e. the ones expected to sign on behalf of the group authority.
+    fn known_group(&self, name: &XorName) -> Option<BTreeSet<PublicId>> {
+        self.known_sections
+            .iter()
+            .find(|&(prefix, _)| prefix.matches(name))
+            .map(|(_, members)| {
+                     members
+                         .iter()
+                         .sorted_by(|lhs, rhs| name.cmp_distance(lhs.name(), rhs.name()))
+                         .into_iter()
+                         .take(self.min_section_size)
+                         .cloned()
+                         .collect()
+                 })
+    }
+
+    fn add_known_section(&mut self, prefix: Prefix<XorName>, members: BTreeSet<PublicId>) {
+        let outdated = self.known_sections
+            .keys()
+            .filter(|known_prefix| known_prefix.is_compatible(&prefix))
+            .cloned()
+            .collect_vec();
+        for known_prefix in outdated {
+            let _ = self.known_sections.remove(&known_prefix);
+        }
+        let _ = self.known_sections.insert(prefix, members);
+    }
+
+    fn fetch_section_info(&mut self, name: XorName, signed_msg: SignedMessage) {
+        if let Some(&mut (_, ref mut msgs)) = self.awaiting_section_info.get_mut(&name) {
+            msgs.push(signed_msg);
+            return;
+        }
+        if let Err(error) = self.send_section_info_request(name, MessageId::new(), false) {
+            debug!("{:?} - Failed to request SectionInfo about {:?}: {:?}",
+                   self,
+                   name,
+                   error);
+        }
+        let token = self.timer
+            .schedule(Duration::from_secs(SECTION_INFO_TIMEOUT_SECS));
+        let _ = self.awaiting_section_info
+            .insert(name, (token, vec![signed_msg]));
+    }
+
+    /// Verifies the messages held back for the `SectionInfo` about `name`, once it arrived or we
+    /// gave up waiting for it.
+    fn verify_awaiting_messages(&mut self, name: &XorName, outbox: &mut EventBox) {
+        if let Some((_, msgs)) = self.awaiting_section_info.remove(name) {
+            for signed_msg in msgs {
+                let _ = self.verify_group_message(signed_msg, false, outbox);
+            }
+        }
     }
 
     fn dispatch_routing_message(&mut self,
@@ -234,7 +570,39 @@ impl Client {
                 if let Some(msg) = self.user_msg_cache
                        .add(hash, part_count, part_index, payload) {
                     self.stats().count_user_message(&msg);
-                    outbox.send_event(msg.into_event(routing_msg.src, routing_msg.dst));
+                    if self.unverified_msgs.remove(&hash).is_none() {
+                        outbox.send_event(msg.into_event(routing_msg.src, routing_msg.dst));
+                    } else if let UserMessage::Response(response) = msg {
+                        outbox.send_event(Event::ResponseVerificationFailed {
+                                              response: response,
+                                              src: routing_msg.src,
+                                              dst: routing_msg.dst,
+                                          });
+                    } else {
+                        debug!("{:?} - Dropping unverified request from {:?}.",
+                               self,
+                               routing_msg.src);
+                    }
+                }
+                Transition::Stay
+            }
+            MessageContent::SectionInfo {
+                versioned_prefix,
+                members,
+                signatures,
+                message_id,
+            } => {
+                if let Err(error) = self.handle_section_info(routing_msg.src,
+                                                             routing_msg.dst,
+                                                             versioned_prefix,
+                                                             members,
+                                                             signatures,
+                                                             message_id,
+                                                             outbox) {
+                    debug!("{:?} - Invalid SectionInfo from {:?}: {:?}",
+                           self,
+                           routing_msg.src,
+                           error);
                 }
                 Transition::Stay
             }
@@ -249,6 +617,118 @@ impl Client {
         }
     }
 
+    /// Sends a `GetSectionInfo` request about `name` via each of our proxies.
+    fn send_section_info_request(&mut self,
+                                 name: XorName,
+                                 message_id: MessageId,
+                                 requested: bool)
+                                 -> Result<(), RoutingError> {
+        let proxy_names = self.proxies
+            .iter()
+            .map(|&(_, ref public_id)| *public_id.name())
+            .collect_vec();
+        let via_proxies = proxy_names
+            .iter()
+            .map(|&proxy_name| (proxy_name, (SectionInfoRequest::new(name), None)))
+            .collect();
+        let _ = self.section_info_requests
+            .insert(message_id,
+                    ProxiedSectionInfoRequest {
+                        name: name,
+                        requested: requested,
+                        via_proxies: via_proxies,
+                    });
+        let dst = Authority::Section(name);
+        for proxy_name in proxy_names {
+            let src = Authority::Client {
+                client_key: *self.full_id.public_id().signing_public_key(),
+                proxy_node_name: proxy_name,
+                peer_id: self.crust_service.id(),
+            };
+            self.send_routing_message(src, dst, MessageContent::GetSectionInfo(message_id))?;
+        }
+        Ok(())
+    }
+
+    /// Collects the responses to our request separately for each proxy that relayed them. Once
+    /// a quorum of the section agreed on its response via every proxy, and all proxies agree,
+    /// raises an `Event::SectionInfo` if the user asked for it. If there are enough proxies, the
+    /// section is also recorded and used to verify the messages that were waiting for it.
+    #[cfg_attr(feature = "cargo-clippy", allow(too_many_arguments))]
+    fn handle_section_info(&mut self,
+                           src: Authority<XorName>,
+                           dst: Authority<XorName>,
+                           versioned_prefix: VersionedPrefix<XorName>,
+                           members: BTreeSet<PublicId>,
+                           signatures: BTreeMap<PublicId, sign::Signature>,
+                           message_id: MessageId,
+                           outbox: &mut EventBox)
+                           -> Result<(), RoutingError> {
+        let (src_name, proxy_name) = match (src, dst) {
+            (Authority::ManagedNode(src_name), Authority::Client { proxy_node_name, .. }) => {
+                (src_name, proxy_node_name)
+            }
+            _ => return Err(RoutingError::BadAuthority),
+        };
+        let (name, requested, proxy_count, agreed) =
+            match self.section_info_requests.get_mut(&message_id) {
+                Some(request) => {
+                    {
+                        let &mut (ref mut proxy_request, ref mut agreed) =
+                            match request.via_proxies.get_mut(&proxy_name) {
+                                Some(entry) => entry,
+                                None => return Err(RoutingError::InvalidSource),
+                            };
+                        if agreed.is_some() {
+                            return Ok(()); // Already answered via this proxy.
+                        }
+                        *agreed = proxy_request.add_response(&src_name,
+                                                             versioned_prefix,
+                                                             members,
+                                                             signatures)?;
+                    }
+                    if request.via_proxies.values().any(|&(_, ref agreed)| agreed.is_none()) {
+                        return Ok(()); // The section hasn't agreed via every proxy yet.
+                    }
+                    let agreed_lists = request.via_proxies
+                        .values()
+                        .filter_map(|&(_, ref agreed)| agreed.as_ref())
+                        .collect_vec();
+                    let agreed = match agreed_lists.split_first() {
+                        Some((&first, others)) if others.iter().all(|other| other.0 == first.0) => {
+                            Some(first.clone())
+                        }
+                        _ => None,
+                    };
+                    (request.name, request.requested, agreed_lists.len(), agreed)
+                }
+                None => return Ok(()), // Already answered, or timed out.
+            };
+        let _ = self.section_info_requests.remove(&message_id);
+        let (section_list, signatures) = match agreed {
+            Some(agreed) => agreed,
+            None => {
+                warn!("{:?} - Our proxies relayed conflicting SectionInfo about {:?}.",
+                      self,
+                      name);
+                self.verify_awaiting_messages(&name, outbox);
+                return Err(RoutingError::InvalidSource);
+            }
+        };
+        if proxy_count >= MIN_SECTION_INFO_PROXIES {
+            self.add_known_section(section_list.prefix, section_list.pub_ids().clone());
+        } else {
+            debug!("{:?} - Not enough proxies to confirm SectionInfo about {:?}.",
+                   self,
+                   name);
+        }
+        if requested {
+            outbox.send_event(common::section_info_event(section_list, signatures, message_id));
+        }
+        self.verify_awaiting_messages(&name, outbox);
+        Ok(())
+    }
+
     /// Sends the given message, possibly splitting it up into smaller parts.
     fn send_user_message(&mut self,
                          src: Authority<XorName>,
@@ -273,6 +753,10 @@ impl Base for Client {
         &self.full_id
     }
 
+    fn session_keys(&self) -> &SessionKeys {
+        &self.session_keys
+    }
+
     /// Does the given authority represent us?
     fn in_authority(&self, auth: &Authority<XorName>) -> bool {
         if let Authority::Client { ref client_key, .. } = *auth {
@@ -289,15 +773,31 @@ impl Base for Client {
         }
 
         debug!("{:?} Received LostPeer - {:?}", self, peer_id);
+        self.session_keys.remove(&peer_id);
 
-        if self.proxy_peer_id == peer_id {
-            debug!("{:?} Lost bootstrap connection to {:?} ({:?}).",
-                   self,
-                   self.proxy_public_id.name(),
-                   peer_id);
+        let _ = self.pending_proxies.remove(&peer_id);
+
+        let index = match self.proxies
+                  .iter()
+                  .position(|&(ref proxy_peer_id, _)| *proxy_peer_id == peer_id) {
+            Some(index) => index,
+            None => return Transition::Stay,
+        };
+        let (_, public_id) = self.proxies.remove(index);
+        debug!("{:?} Lost proxy connection to {:?} ({:?}).",
+               self,
+               public_id.name(),
+               peer_id);
+
+        if self.proxies.is_empty() {
             outbox.send_event(Event::Terminate);
             Transition::Terminate
         } else {
+            if index == 0 {
+                self.fail_over();
+            } else {
+                self.find_more_proxies();
+            }
             Transition::Stay
         }
     }
@@ -349,15 +849,26 @@ impl Bootstrapped for Client {
             return Ok(()); // Message is for us.
         }
 
-        // Get PeerId of the proxy node
+        // Get PeerId of the proxy node. If the message was created for a proxy we are no longer
+        // connected to, it is redirected via the primary one.
+        let mut routing_msg = routing_msg;
         let (proxy_peer_id, sending_nodes) = match routing_msg.src {
-            Authority::Client { ref proxy_node_name, .. } => {
-                if *self.proxy_public_id.name() != *proxy_node_name {
-                    error!("{:?} Unable to find connection to proxy node in proxy map",
-                           self);
-                    return Err(RoutingError::ProxyConnectionNotFound);
-                }
-                (self.proxy_peer_id, vec![])
+            Authority::Client { ref mut proxy_node_name, .. } => {
+                let proxy = self.proxies
+                    .iter()
+                    .find(|&&(_, ref public_id)| public_id.name() == proxy_node_name)
+                    .or_else(|| self.proxies.first())
+                    .map(|&(peer_id, ref public_id)| (peer_id, *public_id.name()));
+                let (proxy_peer_id, proxy_name) = match proxy {
+                    Some(proxy) => proxy,
+                    None => {
+                        error!("{:?} Unable to find connection to proxy node in proxy map",
+                               self);
+                        return Err(RoutingError::ProxyConnectionNotFound);
+                    }
+                };
+                *proxy_node_name = proxy_name;
+                (proxy_peer_id, vec![])
             }
             _ => {
                 error!("{:?} Source should be client if our state is a Client",
@@ -21,6 +21,7 @@ use maidsafe_utilities::serialisation;
 use messages::Message;
 use outbox::EventBox;
 use routing_table::Authority;
+use session_keys::SessionKeys;
 use state_machine::Transition;
 use stats::Stats;
 use std::fmt::Debug;
@@ -30,6 +31,7 @@ use xor_name::XorName;
 pub trait Base: Debug {
     fn crust_service(&self) -> &Service;
     fn full_id(&self) -> &FullId;
+    fn session_keys(&self) -> &SessionKeys;
     fn stats(&mut self) -> &mut Stats;
     fn in_authority(&self, auth: &Authority<XorName>) -> bool;
 
@@ -64,9 +66,19 @@ pub trait Base: Debug {
         };
     }
 
-    // Sends the given `bytes` to the peer with the given Crust `PeerId`. If that results in an
-    // error, it disconnects from the peer.
+    // Sends the given `bytes` to the peer with the given Crust `PeerId`, encrypted if the
+    // connection is. If that results in an error, it disconnects from the peer.
     fn send_or_drop(&mut self, peer_id: &PeerId, bytes: Vec<u8>, priority: u8) {
+        let bytes = match self.session_keys().encrypt(peer_id, bytes) {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                error!("{:?} Failed to encrypt message to {:?}: {:?}",
+                       self,
+                       peer_id,
+                       error);
+                return;
+            }
+        };
         self.stats().count_bytes(bytes.len());
 
         if let Err(err) = self.crust_service().send(*peer_id, bytes, priority) {
@@ -20,5 +20,111 @@ mod bootstrapped;
 
 pub use self::base::Base;
 pub use self::bootstrapped::Bootstrapped;
+use {QUORUM_DENOMINATOR, QUORUM_NUMERATOR};
+use crust::{CrustEventSender, Service};
+use crust::Event as CrustEvent;
+use error::RoutingError;
+use event::Event;
+use id::PublicId;
+use messages::SectionList;
+use routing_table::VersionedPrefix;
+use rust_sodium::crypto::sign;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::mpsc::Receiver;
+use types::MessageId;
+use xor_name::XorName;
 
 pub const USER_MSG_CACHE_EXPIRY_DURATION_SECS: u64 = 60 * 20;
+/// Time (in seconds) for which we wait for the response to a `GetSectionInfo` request.
+pub const SECTION_INFO_TIMEOUT_SECS: u64 = 60;
+
+/// A `GetSectionInfo` request we sent about `name`, and the valid responses to it so far.
+///
+/// A single response only shows that the listed members signed their own list, so it could be
+/// forged by a node controlling all of them. The request is only answered once a quorum of the
+/// listed members independently sent us the same prefix, version and members.
+pub struct SectionInfoRequest {
+    name: XorName,
+    /// The senders of each distinct response, and the signatures they sent.
+    responses: BTreeMap<SectionList, (BTreeSet<XorName>, BTreeMap<PublicId, sign::Signature>)>,
+}
+
+impl SectionInfoRequest {
+    pub fn new(name: XorName) -> Self {
+        SectionInfoRequest {
+            name: name,
+            responses: BTreeMap::new(),
+        }
+    }
+
+    /// Checks that a `SectionInfo` response from `src_name` describes the section responsible for
+    /// our name, is signed by a quorum of its members and is sent by one of them. Returns the
+    /// section list and its signatures once a quorum of the members sent the same response.
+    pub fn add_response(&mut self,
+                        src_name: &XorName,
+                        versioned_prefix: VersionedPrefix<XorName>,
+                        members: BTreeSet<PublicId>,
+                        signatures: BTreeMap<PublicId, sign::Signature>)
+                        -> Result<Option<(SectionList, BTreeMap<PublicId, sign::Signature>)>,
+                                  RoutingError> {
+        if !versioned_prefix.prefix().matches(&self.name) ||
+           !members.iter().any(|pub_id| pub_id.name() == src_name) {
+            return Err(RoutingError::InvalidSource);
+        }
+        let section_list = SectionList::new(versioned_prefix, members);
+        section_list.verify_signatures(&signatures)?;
+
+        let member_count = section_list.pub_ids().len();
+        let &mut (ref mut responders, ref signatures) = self.responses
+            .entry(section_list.clone())
+            .or_insert_with(|| (BTreeSet::new(), signatures));
+        let _ = responders.insert(*src_name);
+        if responders.len() * QUORUM_DENOMINATOR <= member_count * QUORUM_NUMERATOR {
+            return Ok(None);
+        }
+        Ok(Some((section_list, signatures.clone())))
+    }
+}
+
+/// Returns the event reporting the section list agreed on in response to our `GetSectionInfo`
+/// request with the given `message_id`.
+pub fn section_info_event(section_list: SectionList,
+                          signatures: BTreeMap<PublicId, sign::Signature>,
+                          message_id: MessageId)
+                          -> Event {
+    Event::SectionInfo {
+        prefix: section_list.prefix,
+        version: section_list.version,
+        members: section_list.pub_ids().clone(),
+        signatures: signatures,
+        message_id: message_id,
+    }
+}
+
+/// Replaces `old_crust_service` with a new one, for rejoining the network under a new ID.
+#[cfg(not(feature = "use-mock-crust"))]
+pub fn start_new_crust_service(old_crust_service: Service,
+                               crust_rx: &mut Receiver<CrustEvent>,
+                               crust_sender: CrustEventSender)
+                               -> Service {
+    // Drop the current Crust service and flush the receiver
+    drop(old_crust_service);
+    while let Ok(_crust_event) = crust_rx.try_recv() {}
+
+    let mut crust_service = match Service::new(crust_sender) {
+        Ok(service) => service,
+        Err(error) => panic!("Unable to start crust::Service {:?}", error),
+    };
+    crust_service.start_service_discovery();
+    crust_service
+}
+
+/// Replaces `old_crust_service` with a new one, for rejoining the network under a new ID.
+#[cfg(feature = "use-mock-crust")]
+pub fn start_new_crust_service(old_crust_service: Service,
+                               _crust_rx: &mut Receiver<CrustEvent>,
+                               crust_sender: CrustEventSender)
+                               -> Service {
+    old_crust_service.restart(crust_sender);
+    old_crust_service
+}
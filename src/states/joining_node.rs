@@ -16,7 +16,7 @@
 // relating to use of the SAFE Network Software.
 
 use super::{Bootstrapping, BootstrappingTargetState};
-use super::common::{Base, Bootstrapped};
+use super::common::{self, Base, Bootstrapped};
 use ack_manager::{Ack, AckManager};
 use action::Action;
 use cache::Cache;
@@ -28,9 +28,11 @@ use id::{FullId, PublicId};
 use maidsafe_utilities::serialisation;
 use messages::{HopMessage, Message, MessageContent, RoutingMessage, SignedMessage};
 use outbox::EventBox;
+use relocation::RelocationStrategy;
 use resource_prover::RESOURCE_PROOF_DURATION_SECS;
 use routing_message_filter::{FilteringResult, RoutingMessageFilter};
 use routing_table::Authority;
+use session_keys::SessionKeys;
 use state_machine::{State, Transition};
 use stats::Stats;
 use std::collections::BTreeSet;
@@ -52,12 +54,15 @@ pub struct JoiningNode {
     full_id: FullId,
     /// Only held here to be passed eventually to the `Node` state.
     cache: Box<Cache>,
+    /// Only held here to be passed eventually to the `Node` state.
+    relocation_strategy: Box<RelocationStrategy>,
     min_section_size: usize,
     proxy_peer_id: PeerId,
     proxy_public_id: PublicId,
     /// The queue of routing messages addressed to us. These do not themselves need forwarding,
     /// although they may wrap a message which needs forwarding.
     routing_msg_filter: RoutingMessageFilter,
+    session_keys: SessionKeys,
     stats: Stats,
     relocation_timer_token: u64,
     timer: Timer,
@@ -67,8 +72,10 @@ impl JoiningNode {
     #[cfg_attr(feature = "cargo-clippy", allow(too_many_arguments))]
     pub fn from_bootstrapping(action_sender: RoutingActionSender,
                               cache: Box<Cache>,
+                              relocation_strategy: Box<RelocationStrategy>,
                               crust_service: Service,
                               full_id: FullId,
+                              session_keys: SessionKeys,
                               min_section_size: usize,
                               proxy_peer_id: PeerId,
                               proxy_public_id: PublicId,
@@ -83,10 +90,12 @@ impl JoiningNode {
             crust_service: crust_service,
             full_id: full_id,
             cache: cache,
+            relocation_strategy: relocation_strategy,
             min_section_size: min_section_size,
             proxy_peer_id: proxy_peer_id,
             proxy_public_id: proxy_public_id,
             routing_msg_filter: RoutingMessageFilter::new(),
+            session_keys: session_keys,
             stats: stats,
             relocation_timer_token: relocation_timer_token,
             timer: timer,
@@ -103,7 +112,9 @@ impl JoiningNode {
     pub fn handle_action(&mut self, action: Action, outbox: &mut EventBox) -> Transition {
         match action {
             Action::ClientSendRequest { ref result_tx, .. } |
-            Action::NodeSendMessage { ref result_tx, .. } => {
+            Action::NodeSendMessage { ref result_tx, .. } |
+            Action::SectionVote { ref result_tx, .. } |
+            Action::GetSectionInfo { ref result_tx, .. } => {
                 warn!("{:?} Cannot handle {:?} - not joined.", self, action);
                 let _ = result_tx.send(Err(InterfaceError::InvalidState));
             }
@@ -146,7 +157,7 @@ impl JoiningNode {
                               our_section: BTreeSet<PublicId>,
                               outbox: &mut EventBox)
                               -> State {
-        let service = Self::start_new_crust_service(self.crust_service, crust_rx, crust_sender);
+        let service = common::start_new_crust_service(self.crust_service, crust_rx, crust_sender);
         let target_state = BootstrappingTargetState::Node {
             old_full_id: self.full_id,
             our_section: our_section,
@@ -154,9 +165,11 @@ impl JoiningNode {
         if let Some(bootstrapping) =
             Bootstrapping::new(self.action_sender,
                                self.cache,
+                               self.relocation_strategy,
                                target_state,
                                service,
                                new_full_id,
+                               SessionKeys::new(self.session_keys.is_enabled()),
                                self.min_section_size,
                                self.timer) {
             State::Bootstrapping(bootstrapping)
@@ -166,40 +179,17 @@ impl JoiningNode {
         }
     }
 
-    #[cfg(not(feature = "use-mock-crust"))]
-    fn start_new_crust_service(old_crust_service: Service,
-                               crust_rx: &mut Receiver<CrustEvent>,
-                               crust_sender: CrustEventSender)
-                               -> Service {
-        // Drop the current Crust service and flush the receiver
-        drop(old_crust_service);
-        while let Ok(_crust_event) = crust_rx.try_recv() {}
-
-        let mut crust_service = match Service::new(crust_sender) {
-            Ok(service) => service,
-            Err(error) => panic!("Unable to start crust::Service {:?}", error),
-        };
-        crust_service.start_service_discovery();
-        crust_service
-    }
-
-    #[cfg(feature = "use-mock-crust")]
-    fn start_new_crust_service(old_crust_service: Service,
-                               _crust_rx: &mut Receiver<CrustEvent>,
-                               crust_sender: CrustEventSender)
-                               -> Service {
-        old_crust_service.restart(crust_sender);
-        old_crust_service
-    }
-
     fn handle_new_message(&mut self, peer_id: PeerId, bytes: Vec<u8>) -> Transition {
-        let transition = match serialisation::deserialise(&bytes) {
+        let message = self.session_keys
+            .decrypt(&peer_id, bytes)
+            .and_then(|bytes| Ok(serialisation::deserialise(&bytes)?));
+        let transition = match message {
             Ok(Message::Hop(hop_msg)) => self.handle_hop_message(hop_msg, peer_id),
             Ok(message) => {
                 debug!("{:?} - Unhandled new message: {:?}", self, message);
                 Ok(Transition::Stay)
             }
-            Err(error) => Err(RoutingError::SerialisationError(error)),
+            Err(error) => Err(error),
         };
 
         match transition {
@@ -261,7 +251,10 @@ impl JoiningNode {
             UserMessagePart { .. } |
             AcceptAsCandidate { .. } |
             CandidateApproval { .. } |
-            NodeApproval { .. } => {
+            NodeApproval { .. } |
+            SectionVote(..) |
+            GetSectionInfo(..) |
+            SectionInfo { .. } => {
                 warn!("{:?} Not joined yet. Not handling {:?} from {:?} to {:?}",
                       self,
                       routing_msg.content,
@@ -334,6 +327,10 @@ impl Base for JoiningNode {
         &self.full_id
     }
 
+    fn session_keys(&self) -> &SessionKeys {
+        &self.session_keys
+    }
+
     fn in_authority(&self, auth: &Authority<XorName>) -> bool {
         if let Authority::Client { ref client_key, .. } = *auth {
             client_key == self.full_id.public_id().signing_public_key()
@@ -349,6 +346,7 @@ impl Base for JoiningNode {
         }
 
         debug!("{:?} Received LostPeer - {:?}", self, peer_id);
+        self.session_keys.remove(&peer_id);
 
         if self.proxy_peer_id == peer_id {
             debug!("{:?} Lost bootstrap connection to {:?} ({:?}).",
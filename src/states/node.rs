@@ -15,14 +15,17 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software.
 
-use super::common::{Base, Bootstrapped, USER_MSG_CACHE_EXPIRY_DURATION_SECS};
+use super::{Bootstrapping, BootstrappingTargetState};
+use super::common::{self, Base, Bootstrapped, SECTION_INFO_TIMEOUT_SECS, SectionInfoRequest,
+                    USER_MSG_CACHE_EXPIRY_DURATION_SECS};
 use {QUORUM_DENOMINATOR, QUORUM_NUMERATOR};
 use ack_manager::{Ack, AckManager};
 use action::Action;
-use cache::Cache;
-use crust::{ConnectionInfoResult, CrustError, CrustUser, PeerId, PrivConnectionInfo,
-            PubConnectionInfo, Service};
+use cache::{self, Cache};
+use crust::{ConnectionInfoResult, CrustError, CrustEventSender, CrustUser, PeerId,
+            PrivConnectionInfo, PubConnectionInfo, Service};
 use crust::Event as CrustEvent;
+use data_chain::{BlockAccumulator, BlockPayload, DataChain, DataChainPage, RELOCATION_AGE};
 use error::{InterfaceError, RoutingError};
 use event::Event;
 use id::{FullId, PublicId};
@@ -30,28 +33,31 @@ use itertools::Itertools;
 use log::LogLevel;
 use lru_time_cache::LruCache;
 use maidsafe_utilities::serialisation;
-use messages::{DEFAULT_PRIORITY, DirectMessage, HopMessage, Message, MessageContent,
+use messages::{DEFAULT_PRIORITY, DirectMessage, HopMessage, Message, MessageContent, Request,
                RoutingMessage, SectionList, SignedMessage, UserMessage, UserMessageCache};
 use outbox::{EventBox, EventBuf};
 use peer_manager::{ConnectionInfoPreparedResult, Peer, PeerManager, PeerState, RoutingConnection,
                    SectionMap};
 use rand::{self, Rng};
-use resource_prover::{RESOURCE_PROOF_DURATION_SECS, ResourceProver};
+use relocation::RelocationStrategy;
+use resource_prover::{RESOURCE_PROOF_DURATION_SECS, RESOURCE_PROOF_TARGET_SIZE, ResourceProver};
 use routing_message_filter::{FilteringResult, RoutingMessageFilter};
-use routing_table::{Authority, OwnMergeState, Prefix, RemovalDetails, RoutingTable,
+use routing_table::{Authority, Churn, OwnMergeState, Prefix, RemovalDetails, RoutingTable,
                     VersionedPrefix, Xorable};
 use routing_table::Error as RoutingTableError;
 use rust_sodium::crypto::{box_, sign};
 use rust_sodium::crypto::hash::sha256;
+use section_agreement::{SectionAgreement, VoteResult};
 use section_list_cache::SectionListCache;
+use session_keys::{self, SessionKeys};
+use sha3::Digest256;
 use signature_accumulator::SignatureAccumulator;
-use state_machine::Transition;
+use state_machine::{State, Transition};
 use stats::Stats;
 use std::{cmp, fmt, iter, mem};
-use std::collections::{BTreeSet, VecDeque};
-#[cfg(feature = "use-mock-crust")]
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::fmt::{Debug, Formatter};
+use std::sync::mpsc::Receiver;
 use std::time::Duration;
 use timer::Timer;
 use tunnels::Tunnels;
@@ -63,8 +69,6 @@ use xor_name::XorName;
 const TICK_TIMEOUT_SECS: u64 = 60;
 /// The number of required leading zero bits for the resource proof
 const RESOURCE_PROOF_DIFFICULTY: u8 = 0;
-/// The total size of the resource proof data.
-const RESOURCE_PROOF_TARGET_SIZE: usize = 250 * 1024 * 1024;
 /// Initial delay between a routing table change and sending a `SectionUpdate`, in seconds.
 const SU_MIN_TIMEOUT_SECS: u64 = 30;
 /// Maximal delay between two subsequent `SectionUpdate`s, in seconds.
@@ -75,9 +79,12 @@ const CANDIDATE_STATUS_INTERVAL_SECS: u64 = 60;
 const MERGE_TIMEOUT_SECS: u64 = 300;
 /// Duration for which to hold the bootstrappers, in seconds.
 const BOOTSTRAPPER_HOLD_DUR_SECS: u64 = 300;
+/// Duration for which we remember the age of a node which is being relocated, in seconds.
+const RELOCATED_AGE_TIMEOUT_SECS: u64 = 300;
 
 pub struct Node {
     ack_mgr: AckManager,
+    action_sender: RoutingActionSender,
     cacheable_user_msg_cache: UserMessageCache,
     crust_service: Service,
     /// ID from before relocating.
@@ -92,17 +99,46 @@ pub struct Node {
     response_cache: Box<Cache>,
     routing_msg_filter: RoutingMessageFilter,
     sig_accumulator: SignatureAccumulator,
+    section_agreement: SectionAgreement,
+    /// Our section's membership history, and signatures for its next blocks.
+    data_chain: DataChain,
+    block_sigs: BlockAccumulator,
+    /// The members of our section as listed in our `NodeApproval`. A quorum of them has to vouch
+    /// for the genesis block of our data chain.
+    approved_section: BTreeSet<XorName>,
+    /// While we don't have a data chain: by genesis block hash, the first pages of the chains
+    /// starting with that block which members of our section sent us.
+    genesis_votes: BTreeMap<Digest256, BTreeMap<XorName, (PeerId, DataChainPage)>>,
+    /// Whether we leave our section once the data chain selects us for relocation.
+    age_relocation: bool,
+    /// Set once we have been selected for relocation.
+    relocating: bool,
+    /// The ages of the members our data chain selected for relocation, to be passed on to their
+    /// new section once they send their `Relocate` request.
+    relocated_ages: LruCache<PublicId, u32>,
+    /// The ages which approved candidates carry over from their previous section, by their new
+    /// name. Kept until our data chain records them as members, or until they are dropped.
+    candidate_ages: BTreeMap<XorName, u32>,
+    /// The elders of neighbouring sections, as announced in their `SectionUpdate`s.
+    neighbour_elders: BTreeMap<Prefix<XorName>, BTreeSet<XorName>>,
     section_list_sigs: SectionListCache,
+    session_keys: SessionKeys,
     stats: Stats,
     tick_timer_token: u64,
     timer: Timer,
     tunnels: Tunnels,
     user_msg_cache: UserMessageCache,
+    /// Decides where nodes joining the network via our section are relocated to.
+    relocation_strategy: Box<RelocationStrategy>,
     /// Value which can be set in mock-crust tests to be used as the calculated name for the next
     /// relocation request received by this node.
     next_relocation_dst: Option<XorName>,
     /// Interval used for relocation in mock crust tests.
     next_relocation_interval: Option<(XorName, XorName)>,
+    /// Number of members we claim our group has when sending as a group authority. Can be set in
+    /// mock crust tests to make the group send under-signed messages.
+    #[cfg(feature = "use-mock-crust")]
+    claimed_group_size: Option<usize>,
     /// The current duration between `RoutingTableRequest`s we send. Doubles with every message.
     su_timeout: Duration,
     /// The timer token for sending the next `RoutingTableRequest`.
@@ -117,27 +153,40 @@ pub struct Node {
     candidate_status_token: Option<u64>,
     /// Hold the kind of bootstrappers.
     bootstrappers: LruCache<PeerId, CrustUser>,
+    /// The names we sent a `GetSectionInfo` request about, by message ID.
+    section_info_requests: LruCache<MessageId, SectionInfoRequest>,
     resource_prover: ResourceProver,
 }
 
 impl Node {
     pub fn first(action_sender: RoutingActionSender,
                  cache: Box<Cache>,
+                 relocation_strategy: Box<RelocationStrategy>,
                  crust_service: Service,
                  full_id: FullId,
+                 session_keys: SessionKeys,
                  min_section_size: usize,
                  timer: Timer)
                  -> Option<Self> {
         let mut node = Self::new(action_sender,
                                  cache,
+                                 relocation_strategy,
                                  crust_service,
                                  true,
                                  FullId::new(),
                                  full_id,
+                                 session_keys,
                                  min_section_size,
                                  Stats::new(),
                                  timer,
                                  0);
+        node.data_chain = match DataChain::genesis(Default::default(), &node.full_id) {
+            Ok(data_chain) => data_chain,
+            Err(error) => {
+                error!("{:?} Failed to create the genesis block: {:?}", node, error);
+                return None;
+            }
+        };
         if let Err(error) = node.crust_service.start_listening_tcp() {
             error!("{:?} Failed to start listening: {:?}", node, error);
             None
@@ -152,9 +201,11 @@ impl Node {
     pub fn from_bootstrapping(our_section: BTreeSet<PublicId>,
                               action_sender: RoutingActionSender,
                               cache: Box<Cache>,
+                              relocation_strategy: Box<RelocationStrategy>,
                               crust_service: Service,
                               old_full_id: FullId,
                               new_full_id: FullId,
+                              session_keys: SessionKeys,
                               min_section_size: usize,
                               proxy_peer_id: PeerId,
                               proxy_public_id: PublicId,
@@ -163,10 +214,12 @@ impl Node {
                               -> Self {
         let mut node = Self::new(action_sender,
                                  cache,
+                                 relocation_strategy,
                                  crust_service,
                                  false,
                                  old_full_id,
                                  new_full_id,
+                                 session_keys,
                                  min_section_size,
                                  stats,
                                  timer,
@@ -179,10 +232,12 @@ impl Node {
     #[cfg_attr(feature = "cargo-clippy", allow(too_many_arguments))]
     fn new(action_sender: RoutingActionSender,
            cache: Box<Cache>,
+           relocation_strategy: Box<RelocationStrategy>,
            crust_service: Service,
            first_node: bool,
            old_full_id: FullId,
            new_full_id: FullId,
+           session_keys: SessionKeys,
            min_section_size: usize,
            stats: Stats,
            timer: Timer,
@@ -194,6 +249,7 @@ impl Node {
         let user_msg_cache_duration = Duration::from_secs(USER_MSG_CACHE_EXPIRY_DURATION_SECS);
         Node {
             ack_mgr: AckManager::new(),
+            action_sender: action_sender.clone(),
             cacheable_user_msg_cache:
                 UserMessageCache::with_expiry_duration(user_msg_cache_duration),
             crust_service: crust_service,
@@ -206,14 +262,29 @@ impl Node {
             response_cache: cache,
             routing_msg_filter: RoutingMessageFilter::new(),
             sig_accumulator: Default::default(),
+            section_agreement: SectionAgreement::new(timer.clone()),
+            data_chain: Default::default(),
+            block_sigs: Default::default(),
+            approved_section: BTreeSet::new(),
+            genesis_votes: BTreeMap::new(),
+            age_relocation: true,
+            relocating: false,
+            relocated_ages:
+                LruCache::with_expiry_duration(Duration::from_secs(RELOCATED_AGE_TIMEOUT_SECS)),
+            candidate_ages: BTreeMap::new(),
+            neighbour_elders: BTreeMap::new(),
             section_list_sigs: SectionListCache::new(),
+            session_keys: session_keys,
             stats: stats,
             tick_timer_token: tick_timer_token,
             timer: timer.clone(),
             tunnels: Default::default(),
             user_msg_cache: UserMessageCache::with_expiry_duration(user_msg_cache_duration),
+            relocation_strategy: relocation_strategy,
             next_relocation_dst: None,
             next_relocation_interval: None,
+            #[cfg(feature = "use-mock-crust")]
+            claimed_group_size: None,
             su_timeout: Duration::from_secs(SU_MIN_TIMEOUT_SECS),
             su_timer_token: None,
             routing_msg_backlog: vec![],
@@ -222,6 +293,8 @@ impl Node {
             candidate_status_token: None,
             bootstrappers:
                 LruCache::with_expiry_duration(Duration::from_secs(BOOTSTRAPPER_HOLD_DUR_SECS)),
+            section_info_requests:
+                LruCache::with_expiry_duration(Duration::from_secs(SECTION_INFO_TIMEOUT_SECS)),
             resource_prover: ResourceProver::new(action_sender, timer, challenger_count),
         }
     }
@@ -335,6 +408,26 @@ impl Node {
             Action::Name { result_tx } => {
                 let _ = result_tx.send(*self.name());
             }
+            Action::SectionVote { value, result_tx } => {
+                let result = match self.vote(value, outbox) {
+                    Err(RoutingError::Interface(err)) => Err(err),
+                    Err(_) | Ok(()) => Ok(()),
+                };
+
+                let _ = result_tx.send(result);
+            }
+            Action::GetSectionInfo {
+                name,
+                message_id,
+                result_tx,
+            } => {
+                let result = match self.send_section_info_request(name, message_id) {
+                    Err(RoutingError::Interface(err)) => Err(err),
+                    Err(_) | Ok(()) => Ok(()),
+                };
+
+                let _ = result_tx.send(result);
+            }
             Action::Timeout(token) => {
                 if let Transition::Terminate = self.handle_timeout(token, outbox) {
                     return Transition::Terminate;
@@ -352,7 +445,7 @@ impl Node {
 
         self.handle_routing_messages(outbox);
         self.update_stats();
-        Transition::Stay
+        self.relocation_transition()
     }
 
     pub fn handle_crust_event(&mut self,
@@ -409,7 +502,33 @@ impl Node {
 
         self.handle_routing_messages(outbox);
         self.update_stats();
-        Transition::Stay
+        self.relocation_transition()
+    }
+
+    /// Leaves the network and rejoins it as a `JoiningNode` under our current ID, so that our
+    /// section relocates us to a random one and passes on our age.
+    pub fn into_relocating(self,
+                           crust_rx: &mut Receiver<CrustEvent>,
+                           crust_sender: CrustEventSender,
+                           outbox: &mut EventBox)
+                           -> State {
+        let min_section_size = self.routing_table().min_section_size();
+        let service = common::start_new_crust_service(self.crust_service, crust_rx, crust_sender);
+        if let Some(bootstrapping) =
+            Bootstrapping::new(self.action_sender,
+                               self.response_cache,
+                               self.relocation_strategy,
+                               BootstrappingTargetState::JoiningNode,
+                               service,
+                               self.full_id,
+                               SessionKeys::new(self.session_keys.is_enabled()),
+                               min_section_size,
+                               self.timer) {
+            State::Bootstrapping(bootstrapping)
+        } else {
+            outbox.send_event(Event::RestartRequired);
+            State::Terminated
+        }
     }
 
     /// Routing table of this node.
@@ -417,6 +536,16 @@ impl Node {
         self.peer_mgr.routing_table()
     }
 
+    /// Our section's data chain.
+    pub fn data_chain(&self) -> &DataChain {
+        &self.data_chain
+    }
+
+    /// The number of cacheable requests we could and couldn't respond to from the cache.
+    pub fn cache_hits_and_misses(&self) -> (usize, usize) {
+        self.stats.cache_hits_and_misses()
+    }
+
     fn handle_routing_messages(&mut self, outbox: &mut EventBox) {
         while let Some(routing_msg) = self.msg_queue.pop_front() {
             if self.in_authority(&routing_msg.dst) {
@@ -532,6 +661,7 @@ impl Node {
                           bytes: Vec<u8>,
                           outbox: &mut EventBox)
                           -> Result<(), RoutingError> {
+        let bytes = self.session_keys.decrypt(&peer_id, bytes)?;
         match serialisation::deserialise(&bytes) {
             Ok(Message::Hop(hop_msg)) => self.handle_hop_message(hop_msg, peer_id),
             Ok(Message::Direct(direct_msg)) => {
@@ -598,6 +728,7 @@ impl Node {
                     Err(RoutingError::InvalidDestination)
                 }
             }
+            Ok(Message::Encrypted { .. }) => Err(RoutingError::InvalidMessage),
             Err(error) => Err(RoutingError::SerialisationError(error)),
         }
     }
@@ -614,10 +745,14 @@ impl Node {
             SectionListSignature(section_list, sig) => {
                 self.handle_section_list_signature(peer_id, section_list, sig)?
             }
+            BlockSignature(payload, sig) => self.handle_block_signature(peer_id, payload, sig)?,
+            DataChainPage(page) => self.handle_data_chain_page(peer_id, page)?,
+            DataChainRequest(start) => self.handle_data_chain_request(peer_id, start)?,
             ClientIdentify {
                 ref serialised_public_id,
                 ref signature,
                 client_restriction,
+                session_encryption,
             } => {
                 let drop = match self.bootstrappers.remove(&peer_id) {
                     Some(kind) => {
@@ -644,8 +779,12 @@ impl Node {
                     return Ok(self.disconnect_peer(&peer_id, Some(outbox)));
                 }
 
-                if let Ok(public_id) = verify_signed_public_id(serialised_public_id, signature) {
-                    self.handle_client_identify(public_id, peer_id, client_restriction, outbox)
+                if let Ok(public_id) = verify_signed_public_id(serialised_public_id,
+                                                               session_encryption,
+                                                               signature) {
+                    if self.set_session_key(peer_id, &public_id, session_encryption, outbox) {
+                        self.handle_client_identify(public_id, peer_id, client_restriction, outbox)
+                    }
                 } else {
                     warn!("{:?} Signature check failed in ClientIdentify, so dropping connection \
                            {:?}.",
@@ -658,8 +797,14 @@ impl Node {
                 ref serialised_public_id,
                 ref signature,
                 is_tunnel,
+                session_encryption,
             } => {
-                if let Ok(public_id) = verify_signed_public_id(serialised_public_id, signature) {
+                if let Ok(public_id) = verify_signed_public_id(serialised_public_id,
+                                                               session_encryption,
+                                                               signature) {
+                    if !self.set_session_key(peer_id, &public_id, session_encryption, outbox) {
+                        return Ok(());
+                    }
                     debug!("{:?} Handling NodeIdentify from {:?} with tunnel status: {:?}.",
                            self,
                            public_id.name(),
@@ -679,6 +824,7 @@ impl Node {
                 ref signature_using_new,
                 ref new_client_auth,
                 is_tunnel,
+                session_encryption,
             } => {
                 self.handle_candidate_identify(old_public_id,
                                                new_public_id,
@@ -687,6 +833,7 @@ impl Node {
                                                new_client_auth,
                                                &peer_id,
                                                is_tunnel,
+                                               session_encryption,
                                                outbox);
             }
             TunnelRequest(dst_id) => self.handle_tunnel_request(peer_id, dst_id),
@@ -761,7 +908,10 @@ impl Node {
     }
 
     fn get_section_list(&self, prefix: &Prefix<XorName>) -> Result<SectionList, RoutingError> {
-        Ok(SectionList::new(*prefix,
+        let version = self.routing_table()
+            .section_version(prefix)
+            .ok_or(RoutingError::InvalidSource)?;
+        Ok(SectionList::new(prefix.with_version(version),
                             self.peer_mgr.get_pub_ids(&self.get_section(prefix)?)))
     }
 
@@ -819,19 +969,33 @@ impl Node {
                 .cloned()
                 .collect_vec()
         } else {
-            self.routing_table()
-                .our_section()
-                .into_iter()
-                .filter(|&x| *x != *self.name())    // we don't want to send to ourselves
-                .filter_map(|x| self.peer_mgr.get_peer_id(x))   // map names to peer ids
-                .cloned()
-                .collect_vec()
+            self.our_section_peer_ids()
         };
 
         for peer_id in peers {
             let msg = DirectMessage::SectionListSignature(section.clone(), sig);
             self.send_direct_message(peer_id, msg);
         }
+
+        // Changes to our own section are also recorded in our data chain.
+        if prefix == *self.our_prefix() {
+            if let Some(dst) = dst {
+                self.send_data_chain(dst);
+            } else {
+                self.send_block_signature(&section);
+            }
+        }
+    }
+
+    /// Returns the peer IDs of the other members of our section.
+    fn our_section_peer_ids(&self) -> Vec<PeerId> {
+        self.routing_table()
+            .our_section()
+            .into_iter()
+            .filter(|&x| *x != *self.name())    // we don't want to send to ourselves
+            .filter_map(|x| self.peer_mgr.get_peer_id(x))   // map names to peer ids
+            .cloned()
+            .collect_vec()
     }
 
     fn handle_section_list_signature(&mut self,
@@ -857,6 +1021,234 @@ impl Node {
         }
     }
 
+    /// Signs the block recording that our section now consists of `section`, unless our data
+    /// chain already ends with it, and sends the signature to the rest of our section.
+    fn send_block_signature(&mut self, section: &SectionList) {
+        let prev_hash = match self.data_chain.last_hash() {
+            Ok(Some(prev_hash)) => prev_hash,
+            Ok(None) => return, // We haven't received the chain yet.
+            Err(err) => {
+                warn!("{:?} Error hashing the latest block: {:?}", self, err);
+                return;
+            }
+        };
+        // Members relocated to our section keep the age they had in their previous one.
+        let ages = match self.data_chain.last() {
+            Some(block) => {
+                section
+                    .pub_ids()
+                    .iter()
+                    .filter(|pub_id| !block.payload().members.contains(pub_id))
+                    .filter_map(|pub_id| {
+                                    self.candidate_ages
+                                        .get(pub_id.name())
+                                        .map(|age| (*pub_id, *age))
+                                })
+                    .collect()
+            }
+            None => return,
+        };
+        let payload = BlockPayload::new(prev_hash, section.prefix, section.pub_ids().clone(), ages);
+        if self.data_chain.is_current(&payload) {
+            return;
+        }
+        let sig = match payload.sign(&self.full_id) {
+            Ok(sig) => sig,
+            Err(err) => {
+                warn!("{:?} Error signing {:?}: {:?}", self, payload, err);
+                return;
+            }
+        };
+
+        for peer_id in self.our_section_peer_ids() {
+            let msg = DirectMessage::BlockSignature(payload.clone(), sig);
+            self.send_direct_message(peer_id, msg);
+        }
+        let our_id = *self.full_id.public_id();
+        self.add_block_signature(payload, our_id, sig);
+    }
+
+    fn handle_block_signature(&mut self,
+                              peer_id: PeerId,
+                              payload: BlockPayload,
+                              sig: sign::Signature)
+                              -> Result<(), RoutingError> {
+        let src_pub_id = *self.peer_mgr
+                              .get_routing_peer(&peer_id)
+                              .ok_or(RoutingError::InvalidSource)?;
+        if !payload.verify_signature(&src_pub_id, &sig) {
+            return Err(RoutingError::FailedSignature);
+        }
+        self.add_block_signature(payload, src_pub_id, sig);
+        Ok(())
+    }
+
+    fn add_block_signature(&mut self,
+                           payload: BlockPayload,
+                           pub_id: PublicId,
+                           sig: sign::Signature) {
+        self.block_sigs.add_signature(payload, pub_id, sig);
+        if self.block_sigs.extend_chain(&mut self.data_chain) > 0 {
+            debug!("{:?} Extended data chain to {} blocks: {:?}",
+                   self,
+                   self.data_chain.len(),
+                   self.data_chain.last());
+            self.remove_recorded_ages();
+            self.check_relocation();
+        }
+    }
+
+    /// Forgets the ages of the candidates which the latest block of our data chain lists as
+    /// members: the chain now records them.
+    fn remove_recorded_ages(&mut self) {
+        if let Some(block) = self.data_chain.last() {
+            for pub_id in &block.payload().members {
+                let _ = self.candidate_ages.remove(pub_id.name());
+            }
+        }
+    }
+
+    /// Handles the relocation selected by the latest change to our section, unless our section
+    /// would drop below the minimum size: remembers the member's age so that its new section can
+    /// take it over, and marks us for relocation if we were selected.
+    fn check_relocation(&mut self) {
+        let candidate = match self.data_chain.relocation_candidate() {
+            Some(candidate) => candidate,
+            None => return,
+        };
+        let section_len = self.data_chain
+            .last()
+            .map_or(0, |block| block.payload().members.len());
+        if section_len <= self.min_section_size() {
+            debug!("{:?} {} selected for relocation, but our section is too small.",
+                   self,
+                   candidate.name());
+            return;
+        }
+        let age = self.data_chain
+            .member_ages()
+            .get(&candidate)
+            .cloned()
+            .unwrap_or(0);
+        let _ = self.relocated_ages.insert(candidate, age);
+        if !self.age_relocation || self.relocating || candidate != *self.full_id.public_id() {
+            return;
+        }
+        info!("{:?} Older than {} churn events and the oldest member of our section. Relocating.",
+              self,
+              RELOCATION_AGE);
+        self.relocating = true;
+    }
+
+    fn relocation_transition(&self) -> Transition {
+        if self.relocating {
+            Transition::Relocate
+        } else {
+            Transition::Stay
+        }
+    }
+
+    /// Sends the first page of our data chain to `dst`, which just joined our section.
+    fn send_data_chain(&mut self, dst: XorName) {
+        if self.data_chain.is_empty() {
+            return;
+        }
+        if let Some(&peer_id) = self.peer_mgr.get_peer_id(&dst) {
+            let msg = DirectMessage::DataChainPage(self.data_chain.page(0));
+            self.send_direct_message(peer_id, msg);
+        }
+    }
+
+    /// Sends the page of our data chain beginning with block `start` to a member of our section.
+    fn handle_data_chain_request(&mut self,
+                                 peer_id: PeerId,
+                                 start: u64)
+                                 -> Result<(), RoutingError> {
+        if self.peer_mgr.get_routing_peer(&peer_id).is_none() {
+            return Err(RoutingError::InvalidSource);
+        }
+        if start < self.data_chain.len() as u64 {
+            let msg = DirectMessage::DataChainPage(self.data_chain.page(start));
+            self.send_direct_message(peer_id, msg);
+        }
+        Ok(())
+    }
+
+    /// Extends our data chain with the blocks in `page`. If we don't have a chain yet, the page
+    /// only counts as a vote for its genesis block, which we adopt once a quorum of our approved
+    /// section vouched for it.
+    fn handle_data_chain_page(&mut self,
+                              peer_id: PeerId,
+                              page: DataChainPage)
+                              -> Result<(), RoutingError> {
+        let name = match self.peer_mgr.get_routing_peer(&peer_id) {
+            Some(pub_id) => *pub_id.name(),
+            None => return Err(RoutingError::InvalidSource),
+        };
+        if let Some(genesis_hash) = self.data_chain.genesis_hash()? {
+            return self.extend_data_chain(peer_id, page, &genesis_hash);
+        }
+        let genesis_hash = page.genesis_hash()?.ok_or(RoutingError::InvalidMessage)?;
+        if self.genesis_votes
+               .values()
+               .any(|votes| votes.contains_key(&name)) {
+            return Ok(());
+        }
+        let _ = self.genesis_votes
+            .entry(genesis_hash)
+            .or_insert_with(BTreeMap::new)
+            .insert(name, (peer_id, page));
+        self.adopt_genesis();
+        Ok(())
+    }
+
+    /// Starts our data chain with the genesis block vouched for by a quorum of the other members
+    /// of our approved section, if there is one.
+    fn adopt_genesis(&mut self) {
+        let genesis_hash = {
+            let our_name = self.name();
+            let voters = self.approved_section
+                .iter()
+                .filter(|&name| name != our_name)
+                .collect::<BTreeSet<_>>();
+            let is_quorum = |votes: &BTreeMap<XorName, (PeerId, DataChainPage)>| {
+                let valid_votes = votes.keys().filter(|name| voters.contains(name)).count();
+                valid_votes * QUORUM_DENOMINATOR > voters.len() * QUORUM_NUMERATOR
+            };
+            match self.genesis_votes.iter().find(|&(_, votes)| is_quorum(votes)) {
+                Some((genesis_hash, _)) => *genesis_hash,
+                None => return,
+            }
+        };
+        let votes = mem::replace(&mut self.genesis_votes, BTreeMap::new())
+            .remove(&genesis_hash)
+            .unwrap_or_default();
+        for (name, (peer_id, page)) in votes {
+            if let Err(error) = self.extend_data_chain(peer_id, page, &genesis_hash) {
+                debug!("{:?} Invalid data chain page from {:?}: {:?}", self, name, error);
+            }
+        }
+    }
+
+    /// Appends the new blocks in `page` to our data chain and requests the next page from the
+    /// sender if its chain is longer still.
+    fn extend_data_chain(&mut self,
+                         peer_id: PeerId,
+                         page: DataChainPage,
+                         genesis_hash: &Digest256)
+                         -> Result<(), RoutingError> {
+        let chain_len = page.chain_len;
+        let result = self.data_chain.extend_from_page(page, genesis_hash);
+        if self.block_sigs.extend_chain(&mut self.data_chain) > 0 {
+            self.remove_recorded_ages();
+        }
+        if result? > 0 && chain_len > self.data_chain.len() as u64 {
+            let msg = DirectMessage::DataChainRequest(self.data_chain.len() as u64);
+            self.send_direct_message(peer_id, msg);
+        }
+        Ok(())
+    }
+
     fn handle_hop_message(&mut self,
                           hop_msg: HopMessage,
                           peer_id: PeerId)
@@ -927,6 +1319,7 @@ impl Node {
             warn!("{:?} Not enough signatures in {:?}.", self, signed_msg);
             return Err(RoutingError::NotEnoughSignatures);
         }
+        self.check_src_elders(&signed_msg)?;
 
         match self.routing_msg_filter
                   .filter_incoming(signed_msg.routing_message(), route) {
@@ -975,6 +1368,8 @@ impl Node {
                 CandidateApproval { .. } |
                 ConnectionInfoRequest { .. } |
                 SectionUpdate { .. } |
+                SectionVote(..) |
+                GetSectionInfo(..) |
                 UserMessagePart { .. } => {
                     // These messages should not be handled before node approval
                     trace!("{:?} Not approved yet. Delaying message handling: {:?}",
@@ -987,7 +1382,8 @@ impl Node {
                 ConnectionInfoResponse { .. } |
                 RelocateResponse { .. } |
                 Ack(..) |
-                NodeApproval { .. } => {
+                NodeApproval { .. } |
+                SectionInfo { .. } => {
                     // Handle like normal
                 }
             }
@@ -1020,12 +1416,14 @@ impl Node {
             (ExpectCandidate {
                  old_public_id,
                  old_client_auth,
+                 age,
                  message_id,
              },
              Section(_),
              relocation_dst @ Section(_)) => {
                 self.handle_expect_candidate(old_public_id,
                                              old_client_auth,
+                                             age,
                                              relocation_dst,
                                              message_id,
                                              outbox)
@@ -1033,6 +1431,7 @@ impl Node {
             (AcceptAsCandidate {
                  old_public_id,
                  old_client_auth,
+                 age,
                  target_interval,
                  message_id,
              },
@@ -1040,6 +1439,7 @@ impl Node {
              dst @ Section(_)) => {
                 self.handle_accept_as_candidate(old_public_id,
                                                 old_client_auth,
+                                                age,
                                                 dst,
                                                 target_interval,
                                                 message_id,
@@ -1096,6 +1496,7 @@ impl Node {
                  old_public_id,
                  new_public_id,
                  new_client_auth,
+                 age,
                  ..
              },
              Section(_),
@@ -1103,6 +1504,7 @@ impl Node {
                 self.handle_candidate_approval(old_public_id,
                                                new_public_id,
                                                new_client_auth,
+                                               age,
                                                outbox)
             }
             (NodeApproval { sections }, Section(_), Client { .. }) => {
@@ -1111,9 +1513,12 @@ impl Node {
             (SectionUpdate {
                  versioned_prefix,
                  members,
+                 elders,
              },
              Section(_),
-             PrefixSection(_)) => self.handle_section_update(versioned_prefix, members, outbox),
+             PrefixSection(_)) => {
+                self.handle_section_update(versioned_prefix, members, elders, outbox)
+            }
             (SectionSplit(ver_pfx, joining_node), PrefixSection(_), PrefixSection(_)) => {
                 self.handle_section_split(ver_pfx, joining_node, outbox)
             }
@@ -1127,6 +1532,28 @@ impl Node {
              PrefixSection(_)) => {
                 self.handle_other_section_merge(merge_prefix.with_version(version), section, outbox)
             }
+            (SectionVote(value), ManagedNode(voter), Section(_)) => {
+                self.handle_section_vote(value, voter, outbox)
+            }
+            (GetSectionInfo(message_id), src @ Client { .. }, Section(_)) |
+            (GetSectionInfo(message_id), src @ ManagedNode(_), Section(_)) => {
+                self.handle_get_section_info(src, message_id)
+            }
+            (SectionInfo {
+                 versioned_prefix,
+                 members,
+                 signatures,
+                 message_id,
+             },
+             ManagedNode(src_name),
+             ManagedNode(_)) => {
+                self.handle_section_info(src_name,
+                                         versioned_prefix,
+                                         members,
+                                         signatures,
+                                         message_id,
+                                         outbox)
+            }
             (Ack(ack, _), _, _) => self.handle_ack_response(ack),
             (UserMessagePart {
                  hash,
@@ -1139,6 +1566,7 @@ impl Node {
              dst) => {
                 if let Some(msg) = self.user_msg_cache
                        .add(hash, part_count, part_index, payload) {
+                    msg.check_key_rotation(&src, &dst)?;
                     self.stats().count_user_message(&msg);
                     outbox.send_event(msg.into_event(src, dst));
                 }
@@ -1155,10 +1583,240 @@ impl Node {
         }
     }
 
+    fn send_section_info_request(&mut self,
+                                 name: XorName,
+                                 message_id: MessageId)
+                                 -> Result<(), RoutingError> {
+        if !self.is_approved {
+            return Err(RoutingError::Interface(InterfaceError::InvalidState));
+        }
+
+        let _ = self.section_info_requests
+            .insert(message_id, SectionInfoRequest::new(name));
+        let src = Authority::ManagedNode(*self.name());
+        let dst = Authority::Section(name);
+        self.send_routing_message(src, dst, MessageContent::GetSectionInfo(message_id))
+    }
+
+    /// Replies to a `GetSectionInfo` request with our section's current signed section list.
+    fn handle_get_section_info(&mut self,
+                               src: Authority<XorName>,
+                               message_id: MessageId)
+                               -> Result<(), RoutingError> {
+        let our_prefix = *self.our_prefix();
+        let (versioned_prefix, members, signatures) =
+            match self.section_list_sigs.get_signatures(our_prefix) {
+            Some(&(ref section_list, ref signatures)) => {
+                (section_list.versioned_prefix(),
+                 section_list.pub_ids().clone(),
+                 signatures
+                     .iter()
+                     .map(|(&pub_id, &sig)| (pub_id, sig))
+                     .collect())
+            }
+            None => {
+                debug!("{:?} Not replying to GetSectionInfo from {:?}: our section list is not \
+                        signed by a quorum yet.",
+                       self,
+                       src);
+                return Ok(());
+            }
+        };
+        let content = MessageContent::SectionInfo {
+            versioned_prefix: versioned_prefix,
+            members: members,
+            signatures: signatures,
+            message_id: message_id,
+        };
+        self.send_routing_message(Authority::ManagedNode(*self.name()), src, content)
+    }
+
+    /// Raises an `Event::SectionInfo` once a quorum of the section agreed on its response to our
+    /// request, and ignores any further responses.
+    fn handle_section_info(&mut self,
+                           src_name: XorName,
+                           versioned_prefix: VersionedPrefix<XorName>,
+                           members: BTreeSet<PublicId>,
+                           signatures: BTreeMap<PublicId, sign::Signature>,
+                           message_id: MessageId,
+                           outbox: &mut EventBox)
+                           -> Result<(), RoutingError> {
+        let agreed = match self.section_info_requests.get_mut(&message_id) {
+            Some(request) => {
+                request.add_response(&src_name, versioned_prefix, members, signatures)?
+            }
+            None => return Ok(()), // Already answered, or timed out.
+        };
+        if let Some((section_list, signatures)) = agreed {
+            let _ = self.section_info_requests.remove(&message_id);
+            outbox.send_event(common::section_info_event(section_list, signatures, message_id));
+        }
+        Ok(())
+    }
+
+    /// Votes for `value` in our section, and sends the vote to the other members.
+    fn vote(&mut self, value: Vec<u8>, outbox: &mut EventBox) -> Result<(), RoutingError> {
+        if !self.is_approved {
+            return Err(RoutingError::Interface(InterfaceError::InvalidState));
+        }
+
+        let our_name = *self.name();
+        self.count_section_vote(value.clone(), our_name, outbox);
+
+        let src = Authority::ManagedNode(our_name);
+        let dst = Authority::Section(our_name);
+        self.send_routing_message(src, dst, MessageContent::SectionVote(value))
+    }
+
+    fn handle_section_vote(&mut self,
+                           value: Vec<u8>,
+                           voter: XorName,
+                           outbox: &mut EventBox)
+                           -> Result<(), RoutingError> {
+        if voter == *self.name() {
+            return Ok(()); // Already counted when we voted.
+        }
+        if !self.routing_table().our_section().contains(&voter) {
+            debug!("{:?} Received SectionVote from {:?}, which is not in our section.",
+                   self,
+                   voter);
+            return Err(RoutingError::InvalidSource);
+        }
+
+        self.count_section_vote(value, voter, outbox);
+        Ok(())
+    }
+
+    fn count_section_vote(&mut self, value: Vec<u8>, voter: XorName, outbox: &mut EventBox) {
+        let weights = self.vote_weights();
+        match self.section_agreement
+                  .add_vote(value.clone(), voter, &weights) {
+            VoteResult::NewProposal if voter != *self.name() => {
+                outbox.send_event(Event::SectionProposal {
+                                      value: value,
+                                      proposer: voter,
+                                  });
+            }
+            VoteResult::Agreed(voters) => {
+                outbox.send_event(Event::SectionAgreement {
+                                      value: value,
+                                      voters: voters,
+                                  });
+            }
+            VoteResult::NewProposal | VoteResult::Pending => (),
+        }
+    }
+
+    /// Returns the weight of each member's vote in our section: one more than its age, so that
+    /// older members have a greater say.
+    fn vote_weights(&self) -> BTreeMap<XorName, u64> {
+        let ages = self.member_ages();
+        self.routing_table()
+            .our_section()
+            .iter()
+            .map(|name| (*name, 1 + ages.get(name).cloned().unwrap_or(0) as u64))
+            .collect()
+    }
+
+    /// Returns the ages of our section's members according to our data chain, by name.
+    fn member_ages(&self) -> BTreeMap<XorName, u32> {
+        self.data_chain
+            .member_ages()
+            .into_iter()
+            .map(|(pub_id, age)| (*pub_id.name(), age))
+            .collect()
+    }
+
+    /// Returns our section's elders: the `min_section_size` oldest members listed in the latest
+    /// block of our data chain. Since the section agrees on the chain, this doesn't depend on our
+    /// routing table. Until we have received the chain, we don't consider anyone an elder.
+    fn our_elders(&self) -> BTreeSet<XorName> {
+        self.data_chain
+            .elders(self.min_section_size())
+            .iter()
+            .map(|pub_id| *pub_id.name())
+            .collect()
+    }
+
+    /// Checks the elders a message from a section claims to be signed by against the source
+    /// section as we know it: they must all be its members and, unless it is smaller, there must
+    /// be `min_section_size` of them. Lists of sections we don't know are not checked, and neither
+    /// is anything before we are approved and have populated our routing table.
+    fn check_src_elders(&self, signed_msg: &SignedMessage) -> Result<(), RoutingError> {
+        if !self.is_approved {
+            return Ok(());
+        }
+        if let Authority::Section(_) = signed_msg.routing_message().src {
+            for list in signed_msg.src_sections() {
+                let section = match self.routing_table().section_with_prefix(&list.prefix) {
+                    Some(section) => section,
+                    None => continue,
+                };
+                if list.pub_ids().len() < cmp::min(self.min_section_size(), section.len()) ||
+                   list.pub_ids().iter().any(|pub_id| !section.contains(pub_id.name())) {
+                    debug!("{:?} {:?} lists the wrong elders: {:?}",
+                           self,
+                           signed_msg,
+                           list);
+                    return Err(RoutingError::InvalidSource);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn update_neighbour_elders(&mut self, prefix: Prefix<XorName>, elders: BTreeSet<XorName>) {
+        if prefix == *self.our_prefix() {
+            return;
+        }
+        let _ = self.neighbour_elders.insert(prefix, elders);
+        let stale_prefixes = self.neighbour_elders
+            .keys()
+            .filter(|prefix| self.routing_table().section_with_prefix(prefix).is_none())
+            .cloned()
+            .collect_vec();
+        for prefix in stale_prefixes {
+            let _ = self.neighbour_elders.remove(&prefix);
+        }
+    }
+
+    /// If `dst` is a neighbouring section whose elders we know, returns the `route`-th closest of
+    /// those which we are connected to and haven't sent the message to yet.
+    fn elder_target(&self,
+                    dst: &Authority<XorName>,
+                    route: u8,
+                    exclude: &XorName,
+                    sent_to: &BTreeSet<XorName>)
+                    -> Option<XorName> {
+        let dst_name = match *dst {
+            Authority::Section(ref name) if !self.in_authority(dst) => name,
+            _ => return None,
+        };
+        let elders = match self.neighbour_elders
+                  .iter()
+                  .find(|&(prefix, _)| prefix.matches(dst_name)) {
+            Some((_, elders)) => elders,
+            None => return None,
+        };
+        let targets = elders
+            .iter()
+            .filter(|name| {
+                        self.routing_table().has(name) && *name != exclude &&
+                        !sent_to.contains(*name)
+                    })
+            .sorted_by(|&lhs, &rhs| dst_name.cmp_distance(lhs, rhs));
+        if targets.is_empty() {
+            None
+        } else {
+            Some(*targets[route as usize % targets.len()])
+        }
+    }
+
     fn handle_candidate_approval(&mut self,
                                  old_pub_id: PublicId,
                                  new_pub_id: PublicId,
                                  new_client_auth: Authority<XorName>,
+                                 age: u32,
                                  outbox: &mut EventBox)
                                  -> Result<(), RoutingError> {
         for peer_id in self.peer_mgr.remove_expired_candidates() {
@@ -1189,6 +1847,11 @@ impl Node {
             }
         };
 
+        // The age is part of the accumulated vote, so all of us sign the same block for it.
+        if age > 0 {
+            let _ = self.candidate_ages.insert(*new_pub_id.name(), age);
+        }
+
         info!("{:?} Our section with {:?} has approved candidate {}->{}.",
               self,
               self.our_prefix(),
@@ -1250,6 +1913,12 @@ impl Node {
         }
 
         self.resource_prover.handle_approval();
+        self.approved_section = sections
+            .iter()
+            .find(|&(ver_pfx, _)| ver_pfx.prefix().matches(self.name()))
+            .map_or_else(BTreeSet::new,
+                         |(_, section)| section.iter().map(|pub_id| *pub_id.name()).collect());
+        self.adopt_genesis();
 
         if let Err(error) = self.peer_mgr
                .add_prefixes(sections.keys().cloned().collect()) {
@@ -1264,7 +1933,7 @@ impl Node {
         outbox.send_event(Event::Connected);
         for name in self.routing_table().iter() {
             // TODO: try to remove this as safe_core/safe_vault may not require this notification
-            outbox.send_event(Event::NodeAdded(*name, self.routing_table().clone()));
+            outbox.send_event(Event::NodeAdded(*name, Churn::joined(self.routing_table().clone())));
         }
 
         let our_prefix = *self.our_prefix();
@@ -1378,7 +2047,8 @@ impl Node {
     /// Returns `Ok` if a client is allowed to send the given message.
     fn check_valid_client_message(&self, msg: &RoutingMessage) -> Result<(), RoutingError> {
         match msg.content {
-            MessageContent::Ack(..) => Ok(()),
+            MessageContent::Ack(..) |
+            MessageContent::GetSectionInfo(..) => Ok(()),
             MessageContent::UserMessagePart { priority, .. } if priority >= DEFAULT_PRIORITY => {
                 Ok(())
             }
@@ -1410,7 +2080,12 @@ impl Node {
             match self.cacheable_user_msg_cache
                       .add(hash, part_count, part_index, payload.clone()) {
                 Some(UserMessage::Request(request)) => {
-                    if let Some(response) = self.response_cache.get(&request) {
+                    if !self.is_cacheable_request(&request) {
+                        return Ok(false);
+                    }
+                    let cached_response = self.response_cache.get(&request);
+                    self.stats.count_cache_lookup(cached_response.is_some());
+                    if let Some(response) = cached_response {
                         debug!("{:?} Found cached response to {:?}", self, request);
 
                         let priority = response.priority();
@@ -1426,8 +2101,17 @@ impl Node {
                 }
 
                 Some(UserMessage::Response(response)) => {
-                    debug!("{:?} Putting {:?} in cache", self, response);
-                    self.response_cache.put(response);
+                    if let Some(data_id) = response.invalidated_data() {
+                        if self.response_cache.caches_structured_data() {
+                            debug!("{:?} Invalidating {:?} in cache", self, data_id);
+                            self.response_cache.invalidate(&data_id);
+                        }
+                    } else if response.is_cacheable() ||
+                              (self.response_cache.caches_structured_data() &&
+                               cache::is_structured_data_response(&response)) {
+                        debug!("{:?} Putting {:?} in cache", self, response);
+                        self.response_cache.put(response);
+                    }
                 }
 
                 None => (),
@@ -1437,10 +2121,57 @@ impl Node {
         Ok(false)
     }
 
+    // Returns whether our cache handles the response to `request`: structured data is only cached
+    // if the cache opts in to it.
+    fn is_cacheable_request(&self, request: &Request) -> bool {
+        request.is_cacheable() ||
+        (self.response_cache.caches_structured_data() && cache::is_structured_data_get(request))
+    }
+
     fn send_bootstrap_identify(&mut self, peer_id: PeerId) {
-        let direct_message =
-            DirectMessage::BootstrapIdentify { public_id: *self.full_id.public_id() };
+        let session_encryption = self.session_keys.is_enabled();
+        let serialised_public_id = match serialisation::serialise(self.full_id.public_id()) {
+            Ok(rslt) => rslt,
+            Err(e) => {
+                error!("Failed to serialise public ID: {:?}", e);
+                return;
+            }
+        };
+        let signature = sign::sign_detached(&session_keys::identify_payload(&serialised_public_id,
+                                                                            session_encryption),
+                                            self.full_id.signing_private_key());
+        let direct_message = DirectMessage::BootstrapIdentify {
+            public_id: *self.full_id.public_id(),
+            session_encryption: session_encryption,
+            signature: signature,
+        };
         self.send_direct_message(peer_id, direct_message);
+        self.session_keys.identified_to(peer_id);
+    }
+
+    // Derives the session key for the direct connection to `peer_id`, using the encryption key in
+    // the public ID it identified itself with. Tunnelled peers are skipped: messages to them are
+    // encrypted on each of the tunnel node's direct connections instead. If the peer refuses to
+    // encrypt although we require it, it is disconnected and `false` is returned.
+    fn set_session_key(&mut self,
+                       peer_id: PeerId,
+                       public_id: &PublicId,
+                       session_encryption: bool,
+                       outbox: &mut EventBox)
+                       -> bool {
+        if self.tunnels.tunnel_for(&peer_id).is_some() {
+            return true;
+        }
+        if let Err(error) = self.session_keys
+               .peer_identified(peer_id,
+                                public_id.encrypting_public_key(),
+                                self.full_id.encrypting_private_key(),
+                                session_encryption) {
+            warn!("{:?} Dropping peer {:?}: {:?}", self, peer_id, error);
+            self.disconnect_peer(&peer_id, Some(outbox));
+            return false;
+        }
+        true
     }
 
     fn handle_client_identify(&mut self,
@@ -1507,6 +2238,7 @@ impl Node {
                                  new_client_auth: &Authority<XorName>,
                                  peer_id: &PeerId,
                                  is_tunnel: bool,
+                                 session_encryption: bool,
                                  outbox: &mut EventBox) {
         debug!("{:?} Handling CandidateIdentify from {}->{}.",
                self,
@@ -1515,11 +2247,14 @@ impl Node {
         if !self.is_candidate_identify_valid(old_pub_id,
                                              new_pub_id,
                                              signature_using_old,
-                                             signature_using_new) {
+                                             signature_using_new,
+                                             session_encryption) {
             warn!("{:?} Signature check failed in CandidateIdentify, so dropping peer {:?}.",
                   self,
                   peer_id);
             self.disconnect_peer(peer_id, Some(outbox));
+        } else if !self.set_session_key(*peer_id, new_pub_id, session_encryption, outbox) {
+            return;
         }
 
         // If this is a valid node in peer_mgr but the Candidate has sent us a CandidateIdentify,
@@ -1597,11 +2332,12 @@ impl Node {
                                    old_pub_id: &PublicId,
                                    new_pub_id: &PublicId,
                                    signature_using_old: &sign::Signature,
-                                   signature_using_new: &sign::Signature)
+                                   signature_using_new: &sign::Signature,
+                                   session_encryption: bool)
                                    -> bool {
         let old_and_new_pub_ids = (old_pub_id, new_pub_id);
         let mut signed_data = match serialisation::serialise(&old_and_new_pub_ids) {
-            Ok(result) => result,
+            Ok(result) => session_keys::identify_payload(&result, session_encryption),
             Err(error) => {
                 error!("Failed to serialise public IDs: {:?}", error);
                 return false;
@@ -1667,6 +2403,7 @@ impl Node {
                             peer_id: &PeerId,
                             is_tunnel: bool,
                             outbox: &mut EventBox) {
+        let old_table = self.churn_snapshot();
         match self.peer_mgr
                   .add_to_routing_table(public_id, peer_id, is_tunnel) {
             Err(RoutingTableError::AlreadyExists) => return,  // already in RT
@@ -1702,9 +2439,12 @@ impl Node {
             outbox.send_event(Event::Connected);
         }
 
-        if self.is_approved {
-            outbox.send_event(Event::NodeAdded(*public_id.name(), self.routing_table().clone()));
+        if let Some(old_table) = old_table {
+            let churn = Churn::new(old_table, self.routing_table().clone());
+            outbox.send_event(Event::NodeAdded(*public_id.name(), churn));
+        }
 
+        if self.is_approved {
             if let Some(prefix) = self.routing_table()
                    .find_section_prefix(public_id.name()) {
                 self.send_section_list_signature(prefix, None);
@@ -1753,6 +2493,7 @@ impl Node {
         let content = MessageContent::SectionUpdate {
             versioned_prefix: self.routing_table().our_versioned_prefix(),
             members: members,
+            elders: self.our_elders(),
         };
 
         let prefixes = match dst_prefix {
@@ -2107,6 +2848,7 @@ impl Node {
                    peer_id);
             let _ = self.crust_service.disconnect(*peer_id);
             let _ = self.peer_mgr.remove_peer(peer_id);
+            self.session_keys.remove(peer_id);
             self.dropped_tunnel_client(peer_id);
             // FIXME: `outbox` is optional here primarily to avoid passing an `EventBox` through
             //        many of the `send_xxx` functions. We're relying on `purge_invalid_rt_entries`
@@ -2141,13 +2883,23 @@ impl Node {
             return Err(RoutingError::InvalidDestination);
         }
 
-        let close_section = match self.routing_table().close_names(&dst_name) {
-            Some(close_section) => close_section.into_iter().collect(),
+        // Only use the sections agreed by our section, so that all of us pick the same
+        // destination and the `ExpectCandidate` message accumulates.
+        let sections = self.agreed_sections();
+        let close_section = match sections.iter().find(|&(prefix, _)| prefix.matches(&dst_name)) {
+            Some((_, members)) => members.iter().cloned().collect(),
             None => return Err(RoutingError::InvalidDestination),
         };
-        let relocation_dst =
-            self.next_relocation_dst
-                .unwrap_or_else(|| utils::calculate_relocation_dst(close_section, &dst_name));
+        let relocation_dst = match self.next_relocation_dst {
+            Some(relocation_dst) => relocation_dst,
+            None => {
+                self.relocation_strategy
+                    .relocation_dst(&sections, close_section, &dst_name)
+            }
+        };
+
+        // If our data chain selected the node for relocation, it keeps its age.
+        let age = self.relocated_ages.remove(&relocating_node_id).unwrap_or(0);
 
         // From X -> Y; Send to close section of the relocated name
         let request_content = MessageContent::ExpectCandidate {
@@ -2157,6 +2909,7 @@ impl Node {
                 proxy_node_name: proxy_name,
                 peer_id: peer_id,
             },
+            age: age,
             message_id: message_id,
         };
 
@@ -2165,12 +2918,37 @@ impl Node {
         self.send_routing_message(src, dst, request_content)
     }
 
+    // Returns the members of all sections as agreed by our section: our own from our data chain,
+    // and the others from the section lists signed by a quorum of us. Lists of prefixes which have
+    // since been split are omitted.
+    fn agreed_sections(&self) -> BTreeMap<Prefix<XorName>, BTreeSet<XorName>> {
+        let lists = self.section_list_sigs.signed_lists();
+        let our_block = self.data_chain.last().map(|block| block.payload());
+        let is_outdated = |prefix: &Prefix<XorName>| {
+            our_block.map_or(false, |payload| payload.prefix.is_compatible(prefix)) ||
+            lists.iter().any(|list| {
+                                 list.prefix.bit_count() > prefix.bit_count() &&
+                                 list.prefix.is_compatible(prefix)
+                             })
+        };
+        let mut sections: BTreeMap<_, _> = lists.iter()
+            .filter(|list| !is_outdated(&list.prefix))
+            .map(|list| (list.prefix, list.pub_ids().iter().map(|id| *id.name()).collect()))
+            .collect();
+        if let Some(payload) = our_block {
+            let members = payload.members.iter().map(|id| *id.name()).collect();
+            let _ = sections.insert(payload.prefix, members);
+        }
+        sections
+    }
+
     // Received by Y; From X -> Y
     // Context: a node is joining our section. Sends `AcceptAsCandidate` to our section. If the
     // network is unbalanced, sends `ExpectCandidate` on to a section with a shorter prefix.
     fn handle_expect_candidate(&mut self,
                                old_pub_id: PublicId,
                                old_client_auth: Authority<XorName>,
+                               age: u32,
                                relocation_dst: Authority<XorName>,
                                message_id: MessageId,
                                outbox: &mut EventBox)
@@ -2196,6 +2974,7 @@ impl Node {
             let request_content = MessageContent::ExpectCandidate {
                 old_public_id: old_pub_id,
                 old_client_auth: old_client_auth,
+                age: age,
                 message_id: message_id,
             };
             let src = relocation_dst;
@@ -2203,18 +2982,16 @@ impl Node {
             return self.send_routing_message(src, dst, request_content);
         }
 
-        let target_interval = self.next_relocation_interval
-            .take()
-            .unwrap_or_else(|| {
-                                utils::calculate_relocation_interval(self.our_prefix(),
-                                                                     self.routing_table()
-                                                                         .our_section())
-                            });
+        let target_interval = match self.next_relocation_interval.take() {
+            Some(target_interval) => target_interval,
+            None => self.relocation_strategy.relocation_interval(self.routing_table()),
+        };
 
         self.peer_mgr.expect_candidate(old_pub_id)?;
         let response_content = MessageContent::AcceptAsCandidate {
             old_public_id: old_pub_id,
             old_client_auth: old_client_auth,
+            age: age,
             target_interval: target_interval,
             message_id: message_id,
         };
@@ -2227,9 +3004,11 @@ impl Node {
 
     // Received by Y; From Y -> Y
     // Context: a node is joining our section. Sends the node our section.
+    #[cfg_attr(feature = "cargo-clippy", allow(too_many_arguments))]
     fn handle_accept_as_candidate(&mut self,
                                   old_pub_id: PublicId,
                                   old_client_auth: Authority<XorName>,
+                                  age: u32,
                                   relocation_dst: Authority<XorName>,
                                   target_interval: (XorName, XorName),
                                   message_id: MessageId,
@@ -2249,7 +3028,7 @@ impl Node {
                      .schedule(Duration::from_secs(RESOURCE_PROOF_DURATION_SECS)));
 
         let own_section = self.peer_mgr
-            .accept_as_candidate(old_pub_id, target_interval);
+            .accept_as_candidate(old_pub_id, target_interval, age);
         let response_content = MessageContent::RelocateResponse {
             target_interval: target_interval,
             section: own_section,
@@ -2270,18 +3049,28 @@ impl Node {
     fn handle_section_update(&mut self,
                              ver_pfx: VersionedPrefix<XorName>,
                              members: BTreeSet<PublicId>,
+                             elders: BTreeSet<XorName>,
                              outbox: &mut EventBox)
                              -> Result<(), RoutingError> {
         trace!("{:?} Got section update for {:?}", self, ver_pfx);
 
         // Perform splits and merges that we missed, according to the section update.
+        let old_versions = self.routing_table().other_section_versions();
         for (name, peer_id) in self.peer_mgr.add_prefix(ver_pfx) {
             self.disconnect_peer(&peer_id, Some(outbox));
             info!("{:?} Dropped {:?} from the routing table.", self, name);
         }
+        self.send_remote_sections_event(old_versions, outbox);
         info!("{:?} SectionUpdate handled. Prefixes: {:?}",
               self,
               self.routing_table().prefixes());
+        if elders.iter().all(|name| members.iter().any(|pub_id| pub_id.name() == name)) {
+            self.update_neighbour_elders(*ver_pfx.prefix(), elders);
+        } else {
+            debug!("{:?} Section update for {:?} lists elders which aren't members.",
+                   self,
+                   ver_pfx);
+        }
         // Filter list of members to just those we don't know about:
         let members = if let Some(section) = self.routing_table()
                .section_with_prefix(ver_pfx.prefix()) {
@@ -2327,9 +3116,12 @@ impl Node {
         }
         // None of the `peers_to_drop` will have been in our section, so no need to notify Routing
         // user about them.
+        let old_table = self.routing_table().clone();
         let (peers_to_drop, our_new_prefix) = self.peer_mgr.split_section(ver_pfx);
+        self.send_remote_sections_event(old_table.other_section_versions(), outbox);
         if let Some(new_prefix) = our_new_prefix {
-            outbox.send_event(Event::SectionSplit(new_prefix));
+            let churn = Churn::new(old_table, self.routing_table().clone());
+            outbox.send_event(Event::SectionSplit(new_prefix, churn));
         }
 
         for (_name, peer_id) in peers_to_drop {
@@ -2407,6 +3199,39 @@ impl Node {
         Ok(())
     }
 
+    // Raises `Event::RemoteSectionsChanged` if the sections other than our own differ from
+    // `old_versions`, the prefixes and versions we knew of before the change.
+    fn send_remote_sections_event(&self,
+                                  old_versions: BTreeMap<Prefix<XorName>, u64>,
+                                  outbox: &mut EventBox) {
+        let new_versions = self.routing_table().other_section_versions();
+        let mut added = BTreeMap::new();
+        let mut updated = BTreeMap::new();
+        for (prefix, &version) in &new_versions {
+            match old_versions.get(prefix) {
+                None => {
+                    let _ = added.insert(*prefix, version);
+                }
+                Some(&old_version) if old_version != version => {
+                    let _ = updated.insert(*prefix, version);
+                }
+                Some(_) => (),
+            }
+        }
+        let removed: BTreeMap<_, _> = old_versions
+            .into_iter()
+            .filter(|&(prefix, _)| !new_versions.contains_key(&prefix))
+            .collect();
+        if added.is_empty() && removed.is_empty() && updated.is_empty() {
+            return;
+        }
+        outbox.send_event(Event::RemoteSectionsChanged {
+                              added: added,
+                              removed: removed,
+                              updated: updated,
+                          });
+    }
+
     fn we_want_to_merge(&self) -> bool {
         self.merge_cache.contains_key(self.our_prefix())
     }
@@ -2422,6 +3247,7 @@ impl Node {
                                  sections: SectionMap,
                                  our_merged_section: &BTreeSet<XorName>,
                                  outbox: &mut EventBox) {
+        let old_table = self.routing_table().clone();
         match self.peer_mgr
                   .merge_own_section(sender_prefix, merge_version, sections) {
             (OwnMergeState::AlreadyMerged, _dropped_peers, _needed_peers) => (),
@@ -2436,7 +3262,9 @@ impl Node {
                     self.disconnect_peer(&peer_id, Some(outbox));
                 }
                 // TODO - the event should maybe only fire once all new connections have been made?
-                outbox.send_event(Event::SectionMerge(*versioned_prefix.prefix()));
+                self.send_remote_sections_event(old_table.other_section_versions(), outbox);
+                let churn = Churn::new(old_table, self.routing_table().clone());
+                outbox.send_event(Event::SectionMerge(*versioned_prefix.prefix(), churn));
                 info!("{:?} Own section merge completed. Prefixes: {:?}",
                       self,
                       self.routing_table().prefixes());
@@ -2477,7 +3305,9 @@ impl Node {
                                   section: BTreeSet<PublicId>,
                                   outbox: &mut EventBox)
                                   -> Result<(), RoutingError> {
+        let old_versions = self.routing_table().other_section_versions();
         let needed_peers = self.peer_mgr.merge_other_section(merge_ver_pfx, section);
+        self.send_remote_sections_event(old_versions, outbox);
         let own_name = *self.name();
 
         for needed in needed_peers {
@@ -2550,6 +3380,11 @@ impl Node {
                 Some(self.timer
                          .schedule(Duration::from_secs(CANDIDATE_STATUS_INTERVAL_SECS)));
             self.peer_mgr.show_candidate_status();
+        } else if let Some(value) = self.section_agreement.handle_timeout(token) {
+            debug!("{:?} Section agreement timed out: {}",
+                   self,
+                   utils::format_binary_array(&value));
+            outbox.send_event(Event::SectionAgreementTimeout(value));
         } else {
             // Each token has only one purpose, so we only need to call this if none of the above
             // matched:
@@ -2567,9 +3402,13 @@ impl Node {
             self.crust_service.disconnect(peer_id);
             self.dropped_peer(&peer_id, outbox, true);
         }
-        for removal_detail in peer_details.removal_details {
-            let name = removal_detail.name;
-            self.dropped_routing_node(&name, removal_detail, outbox);
+        for name in peer_details.invalid_rt_names {
+            // Each removal gets its own snapshot, so that every `NodeLost` event only covers the
+            // loss of that one node.
+            let old_table = self.churn_snapshot();
+            if let Ok(removal_details) = self.peer_mgr.remove_from_routing_table(&name) {
+                self.dropped_routing_node(&name, removal_details, old_table, outbox);
+            }
         }
         let mut peer_ids_to_drop = vec![];
         for (peer_id, name, is_tunnel) in peer_details.routing_peer_details {
@@ -2798,6 +3637,7 @@ impl Node {
     /// may be us or another node. If our signature is not required, this returns `None`.
     fn get_signature_target(&self, src: &Authority<XorName>, route: u8) -> Option<XorName> {
         use Authority::*;
+        let elders;
         let list: Vec<&XorName> = match *src {
             ClientManager(_) | NaeManager(_) | NodeManager(_) => {
                 let mut v = self.routing_table()
@@ -2808,8 +3648,9 @@ impl Node {
                 v
             }
             Section(_) => {
-                self.routing_table()
-                    .our_section()
+                // Only elders accumulate messages from our section.
+                elders = self.our_elders();
+                elders
                     .iter()
                     .sorted_by(|&lhs, &rhs| src.name().cmp_distance(lhs, rhs))
             }
@@ -2847,11 +3688,16 @@ impl Node {
         };
 
         if self.is_proper() && !force_via_proxy {
-            let targets: BTreeSet<_> = self.routing_table()
-                .targets(&routing_msg.dst, *exclude, route as usize)?
-                .into_iter()
-                .filter(|target| !sent_to.contains(target))
-                .collect();
+            let targets: BTreeSet<_> =
+                if let Some(elder) = self.elder_target(&routing_msg.dst, route, exclude, sent_to) {
+                    iter::once(elder).collect()
+                } else {
+                    self.routing_table()
+                        .targets(&routing_msg.dst, *exclude, route as usize)?
+                        .into_iter()
+                        .filter(|target| !sent_to.contains(target))
+                        .collect()
+                };
             let new_sent_to =
                 if self.in_authority(&routing_msg.dst) {
                     sent_to.iter()
@@ -2906,6 +3752,7 @@ impl Node {
     }
 
     fn send_node_identify(&mut self, peer_id: PeerId, is_tunnel: bool) {
+        let session_encryption = self.session_keys.is_enabled();
         let direct_message = if self.is_approved {
             let serialised_public_id = match serialisation::serialise(self.full_id.public_id()) {
                 Ok(rslt) => rslt,
@@ -2914,18 +3761,21 @@ impl Node {
                     return;
                 }
             };
-            let signature = sign::sign_detached(&serialised_public_id,
-                                                self.full_id.signing_private_key());
+            let to_sign = session_keys::identify_payload(&serialised_public_id,
+                                                         session_encryption);
+            let signature = sign::sign_detached(&to_sign, self.full_id.signing_private_key());
             DirectMessage::NodeIdentify {
                 serialised_public_id: serialised_public_id,
                 signature: signature,
                 is_tunnel: is_tunnel,
+                session_encryption: session_encryption,
             }
         } else {
-            // Serialise the old and new `PublicId`s and sign this using the old key.
+            // Serialise the old and new `PublicId`s, append the encryption flag and sign this using
+            // the old key.
             let old_and_new_pub_ids = (self.old_full_id.public_id(), self.full_id.public_id());
             let mut to_sign = match serialisation::serialise(&old_and_new_pub_ids) {
-                Ok(result) => result,
+                Ok(result) => session_keys::identify_payload(&result, session_encryption),
                 Err(error) => {
                     error!("Failed to serialise public IDs: {:?}", error);
                     return;
@@ -2957,10 +3807,14 @@ impl Node {
                 signature_using_new: signature_using_new,
                 new_client_auth: new_client_auth,
                 is_tunnel: is_tunnel,
+                session_encryption: session_encryption,
             }
         };
 
         self.send_direct_message(peer_id, direct_message);
+        if self.tunnels.tunnel_for(&peer_id).is_none() {
+            self.session_keys.identified_to(peer_id);
+        }
     }
 
     // Note: This fn assumes `their_public_id` is a valid node in the network
@@ -3037,6 +3891,17 @@ impl Node {
         Ok(())
     }
 
+    // Returns a copy of our routing table, to be passed as the old table with the churn event
+    // following the next change. Returns `None` if we aren't approved yet, as we don't raise churn
+    // events then and so don't need the copy.
+    fn churn_snapshot(&self) -> Option<RoutingTable<XorName>> {
+        if self.is_approved {
+            Some(self.routing_table().clone())
+        } else {
+            None
+        }
+    }
+
     // Handle dropped peer with the given peer id. Returns true if we should keep running, false if
     // we should terminate.
     fn dropped_peer(&mut self,
@@ -3044,13 +3909,17 @@ impl Node {
                     outbox: &mut EventBox,
                     mut try_reconnect: bool)
                     -> bool {
+        let old_table = match self.peer_mgr.get_peer(peer_id) {
+            Some(peer) if self.routing_table().has(peer.name()) => self.churn_snapshot(),
+            _ => None,
+        };
         let (peer, removal_result) = match self.peer_mgr.remove_peer(peer_id) {
             Some(result) => result,
             None => return true,
         };
 
         if let Ok(removal_details) = removal_result {
-            if !self.dropped_routing_node(peer.name(), removal_details, outbox) {
+            if !self.dropped_routing_node(peer.name(), removal_details, old_table, outbox) {
                 return false;
             }
         }
@@ -3102,19 +3971,22 @@ impl Node {
         true
     }
 
-    // Handle dropped routing peer with the given name and removal details. Returns true if we
+    // Handle dropped routing peer with the given name and removal details, where `old_table` is
+    // our routing table before the removal, as returned by `churn_snapshot`. Returns true if we
     // should keep running, false if we should terminate.
     fn dropped_routing_node(&mut self,
                             name: &XorName,
                             details: RemovalDetails<XorName>,
+                            old_table: Option<RoutingTable<XorName>>,
                             outbox: &mut EventBox)
                             -> bool {
         info!("{:?} Dropped {:?} from the routing table.",
               self,
               details.name);
 
-        if self.is_approved {
-            outbox.send_event(Event::NodeLost(details.name, self.routing_table().clone()));
+        if let Some(old_table) = old_table {
+            let churn = Churn::new(old_table, self.routing_table().clone());
+            outbox.send_event(Event::NodeLost(details.name, churn));
         }
 
         self.merge_if_necessary();
@@ -3123,6 +3995,7 @@ impl Node {
             .map_or((),
                     |prefix| { self.send_section_list_signature(prefix, None); });
         if details.was_in_our_section {
+            let _ = self.candidate_ages.remove(name);
             self.reset_su_timer();
             let section_len = self.routing_table().our_section().len();
             self.section_list_sigs
@@ -3260,6 +4133,10 @@ impl Base for Node {
         &self.full_id
     }
 
+    fn session_keys(&self) -> &SessionKeys {
+        &self.session_keys
+    }
+
     fn in_authority(&self, auth: &Authority<XorName>) -> bool {
         if let Authority::Client { ref client_key, .. } = *auth {
             client_key == self.full_id.public_id().signing_public_key()
@@ -3282,6 +4159,7 @@ impl Base for Node {
 
         debug!("{:?} Received LostPeer - {:?}", self, peer_id);
 
+        self.session_keys.remove(&peer_id);
         self.dropped_tunnel_client(&peer_id);
         self.dropped_tunnel_node(&peer_id, outbox);
 
@@ -3323,6 +4201,11 @@ impl Node {
         let _ = self.purge_invalid_rt_entries(&mut EventBuf::new());
     }
 
+    /// Returns the elder we would send a message for `dst` to via `route`, if any.
+    pub fn get_elder_target(&self, dst: &Authority<XorName>, route: u8) -> Option<XorName> {
+        self.elder_target(dst, route, self.name(), &BTreeSet::new())
+    }
+
     pub fn clear_state(&mut self) {
         self.ack_mgr.clear();
         self.routing_msg_filter.clear();
@@ -3352,6 +4235,33 @@ impl Node {
     pub fn set_next_relocation_interval(&mut self, interval: (XorName, XorName)) {
         self.next_relocation_interval = Some(interval);
     }
+
+    pub fn set_age_relocation(&mut self, enabled: bool) {
+        self.age_relocation = enabled;
+    }
+
+    pub fn set_claimed_group_size(&mut self, size: usize) {
+        self.claimed_group_size = Some(size);
+    }
+
+    // Returns the members of our group we claim to send as `src` with: all of `pub_ids`, or only
+    // the closest ones if `set_claimed_group_size` was called.
+    fn claimed_group(&self,
+                     src: &Authority<XorName>,
+                     pub_ids: BTreeSet<PublicId>)
+                     -> BTreeSet<PublicId> {
+        match self.claimed_group_size {
+            Some(size) => {
+                let src_name = src.name();
+                pub_ids.into_iter()
+                    .sorted_by(|lhs, rhs| src_name.cmp_distance(lhs.name(), rhs.name()))
+                    .into_iter()
+                    .take(size)
+                    .collect()
+            }
+            None => pub_ids,
+        }
+    }
 }
 
 impl Bootstrapped for Node {
@@ -3381,6 +4291,14 @@ impl Bootstrapped for Node {
                    routing_msg);
             return Ok(());
         }
+        if let Authority::Section(_) = routing_msg.src {
+            if !self.our_elders().contains(self.name()) {
+                trace!("{:?} Not an elder. Not signing message {:?}.",
+                       self,
+                       routing_msg);
+                return Ok(());
+            }
+        }
         if !self.add_to_pending_acks(&routing_msg, route) {
             debug!("{:?} already received an ack for {:?} - so not resending it.",
                    self,
@@ -3395,19 +4313,21 @@ impl Bootstrapped for Node {
                         .get_section(self.name())
                         .ok_or(RoutingError::RoutingTable(RoutingTableError::NoSuchPeer))?;
                 let pub_ids = self.peer_mgr.get_pub_ids(section);
-                vec![SectionList::new(*self.our_prefix(), pub_ids)]
+                #[cfg(feature = "use-mock-crust")]
+                let pub_ids = self.claimed_group(&routing_msg.src, pub_ids);
+                vec![SectionList::new(self.routing_table().our_versioned_prefix(), pub_ids)]
             }
             Section(_) => {
-                vec![SectionList::new(*self.our_prefix(),
-                                      self.peer_mgr
-                                          .get_pub_ids(self.routing_table().our_section()))]
+                vec![SectionList::new(self.routing_table().our_versioned_prefix(),
+                                      self.data_chain.elders(self.min_section_size()))]
             }
             PrefixSection(ref prefix) => {
                 self.routing_table()
                     .all_sections()
                     .into_iter()
-                    .filter_map(|(p, (_, members))| if prefix.is_compatible(&p) {
-                                    Some(SectionList::new(p, self.peer_mgr.get_pub_ids(&members)))
+                    .filter_map(|(p, (v, members))| if prefix.is_compatible(&p) {
+                                    Some(SectionList::new(p.with_version(v),
+                                                          self.peer_mgr.get_pub_ids(&members)))
                                 } else {
                                     None
                                 })
@@ -3462,13 +4382,15 @@ impl Debug for Node {
     }
 }
 
-// Verify the serialised public id against the signature.
+// Verify the serialised public id and the session encryption flag against the signature.
 fn verify_signed_public_id(serialised_public_id: &[u8],
+                           session_encryption: bool,
                            signature: &sign::Signature)
                            -> Result<PublicId, RoutingError> {
     let public_id: PublicId = serialisation::deserialise(serialised_public_id)?;
     let public_key = public_id.signing_public_key();
-    if sign::verify_detached(signature, serialised_public_id, public_key) {
+    let signed_data = session_keys::identify_payload(serialised_public_id, session_encryption);
+    if sign::verify_detached(signature, &signed_data, public_key) {
         Ok(public_id)
     } else {
         Err(RoutingError::FailedSignature)
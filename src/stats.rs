@@ -34,6 +34,10 @@ pub struct Stats {
     routes: Vec<usize>,
     /// Messages we sent unsuccessfully: unacknowledged on all routes.
     unacked_msgs: usize,
+    /// Cacheable requests we could respond to from the response cache.
+    cache_hits: usize,
+    /// Cacheable requests we had to relay because there was no cached response.
+    cache_misses: usize,
 
     msg_direct_node_identify: usize,
     msg_direct_candidate_identify: usize,
@@ -42,13 +46,19 @@ pub struct Stats {
     msg_direct_resource_proof_rsp: usize,
     msg_direct_resource_proof_rsp_receipt: usize,
     msg_direct_sls: usize,
+    msg_direct_block_sig: usize,
 
     msg_get: usize,
     msg_put: usize,
+    msg_get_batch: usize,
+    msg_put_batch: usize,
     msg_post: usize,
     msg_delete: usize,
     msg_append: usize,
     msg_get_account_info: usize,
+    msg_rotate_key: usize,
+    msg_migrate_account: usize,
+    msg_custom: usize,
     msg_relocate: usize,
     msg_expect_candidate: usize,
     msg_accept_as_candidate: usize,
@@ -67,6 +77,11 @@ pub struct Stats {
     msg_append_failure: usize,
     msg_get_account_info_success: usize,
     msg_get_account_info_failure: usize,
+    msg_rotate_key_success: usize,
+    msg_rotate_key_failure: usize,
+    msg_custom_response: usize,
+    msg_get_batch_response: usize,
+    msg_put_batch_response: usize,
     msg_section_update: usize,
     msg_section_split: usize,
     msg_own_section_merge: usize,
@@ -74,6 +89,9 @@ pub struct Stats {
     msg_relocate_rsp: usize,
     msg_candidate_approval: usize,
     msg_node_approval: usize,
+    msg_section_vote: usize,
+    msg_get_section_info: usize,
+    msg_section_info: usize,
     msg_ack: usize,
 
     msg_other: usize,
@@ -94,6 +112,19 @@ impl Stats {
         self.unacked_msgs += 1;
     }
 
+    pub fn count_cache_lookup(&mut self, hit: bool) {
+        if hit {
+            self.cache_hits += 1;
+        } else {
+            self.cache_misses += 1;
+        }
+    }
+
+    /// Returns the number of cacheable requests we could and couldn't respond to from the cache.
+    pub fn cache_hits_and_misses(&self) -> (usize, usize) {
+        (self.cache_hits, self.cache_misses)
+    }
+
     pub fn count_route(&mut self, route: u8) {
         let route = route as usize;
         if route >= self.routes.len() {
@@ -110,10 +141,15 @@ impl Stats {
                     Request::Refresh(..) => self.msg_refresh += 1,
                     Request::Get(..) => self.msg_get += 1,
                     Request::Put(..) => self.msg_put += 1,
+                    Request::GetBatch(..) => self.msg_get_batch += 1,
+                    Request::PutBatch(..) => self.msg_put_batch += 1,
                     Request::Post(..) => self.msg_post += 1,
                     Request::Delete(..) => self.msg_delete += 1,
                     Request::Append(..) => self.msg_append += 1,
                     Request::GetAccountInfo(..) => self.msg_get_account_info += 1,
+                    Request::RotateKey(..) => self.msg_rotate_key += 1,
+                    Request::MigrateAccount(..) => self.msg_migrate_account += 1,
+                    Request::Custom { .. } => self.msg_custom += 1,
                 }
             }
             UserMessage::Response(ref response) => {
@@ -134,6 +170,11 @@ impl Stats {
                     Response::GetAccountInfoFailure { .. } => {
                         self.msg_get_account_info_failure += 1
                     }
+                    Response::RotateKeySuccess(..) => self.msg_rotate_key_success += 1,
+                    Response::RotateKeyFailure { .. } => self.msg_rotate_key_failure += 1,
+                    Response::Custom { .. } => self.msg_custom_response += 1,
+                    Response::GetBatch { .. } => self.msg_get_batch_response += 1,
+                    Response::PutBatch { .. } => self.msg_put_batch_response += 1,
                 }
             }
         }
@@ -156,6 +197,9 @@ impl Stats {
             MessageContent::Ack(..) => self.msg_ack += 1,
             MessageContent::CandidateApproval { .. } => self.msg_candidate_approval += 1,
             MessageContent::NodeApproval { .. } => self.msg_node_approval += 1,
+            MessageContent::SectionVote(..) => self.msg_section_vote += 1,
+            MessageContent::GetSectionInfo(..) => self.msg_get_section_info += 1,
+            MessageContent::SectionInfo { .. } => self.msg_section_info += 1,
             MessageContent::UserMessagePart { .. } => return, // Counted as request/response.
         }
         self.increment_msg_total();
@@ -169,12 +213,15 @@ impl Stats {
             CandidateIdentify { .. } => self.msg_direct_candidate_identify += 1,
             MessageSignature(..) => self.msg_direct_sig += 1,
             SectionListSignature(..) => self.msg_direct_sls += 1,
+            BlockSignature(..) => self.msg_direct_block_sig += 1,
             ResourceProof { .. } => self.msg_direct_resource_proof += 1,
             ResourceProofResponse { .. } => self.msg_direct_resource_proof_rsp += 1,
             ResourceProofResponseReceipt => self.msg_direct_resource_proof_rsp_receipt += 1,
             BootstrapIdentify { .. } |
             BootstrapDeny |
             ClientIdentify { .. } |
+            DataChainPage(_) |
+            DataChainRequest(_) |
             TunnelRequest(_) |
             TunnelSuccess(_) |
             TunnelClosed(_) |
@@ -198,27 +245,32 @@ impl Stats {
         if self.should_log && self.msg_total % MSG_LOG_COUNT == 0 {
             info!(target: "routing_stats",
                   "Stats - Sent {} messages in total, comprising {} bytes, {} uncategorised, \
-                   routes/failed: {:?}/{}",
+                   routes/failed: {:?}/{}, cache hits/misses: {}/{}",
                   self.msg_total,
                   self.msg_total_bytes,
                   self.msg_other,
                   self.routes,
-                  self.unacked_msgs);
+                  self.unacked_msgs,
+                  self.cache_hits,
+                  self.cache_misses);
             info!(target: "routing_stats",
                   "Stats - Direct - NodeIdentify: {}, CandidateIdentify: {}, \
-                   MessageSignature: {}, ResourceProof: {}/{}/{}, SectionListSignature: {}",
+                   MessageSignature: {}, ResourceProof: {}/{}/{}, SectionListSignature: {}, \
+                   BlockSignature: {}",
                   self.msg_direct_node_identify,
                   self.msg_direct_candidate_identify,
                   self.msg_direct_sig,
                   self.msg_direct_resource_proof,
                   self.msg_direct_resource_proof_rsp,
                   self.msg_direct_resource_proof_rsp_receipt,
-                  self.msg_direct_sls);
+                  self.msg_direct_sls,
+                  self.msg_direct_block_sig);
             info!(target: "routing_stats",
                   "Stats - Hops (Request/Response) - Relocate: {}/{}, ExpectCandidate: {}, \
                    AcceptAsCandidate: {}, SectionUpdate: {}, SectionSplit: {}, \
                    OwnSectionMerge: {}, OtherSectionMerge: {}, ConnectionInfo: {}/{}, \
-                   CandidateApproval: {}, NodeApproval: {}, Ack: {}",
+                   CandidateApproval: {}, NodeApproval: {}, SectionVote: {}, SectionInfo: {}/{}, \
+                   Ack: {}",
                   self.msg_relocate,
                   self.msg_relocate_rsp,
                   self.msg_expect_candidate,
@@ -231,11 +283,15 @@ impl Stats {
                   self.msg_connection_info_rsp,
                   self.msg_candidate_approval,
                   self.msg_node_approval,
+                  self.msg_section_vote,
+                  self.msg_get_section_info,
+                  self.msg_section_info,
                   self.msg_ack);
             info!(target: "routing_stats",
                   "Stats - User (Request/Success/Failure) - Get: {}/{}/{}, Put: {}/{}/{}, \
                    Post: {}/{}/{}, Delete: {}/{}/{}, Append: {}/{}/{}, GetAccountInfo: {}/{}/{}, \
-                   Refresh: {}",
+                   RotateKey: {}/{}/{}, MigrateAccount: {}, Refresh: {}, Custom: {}/{}, \
+                   GetBatch: {}/{}, PutBatch: {}/{}",
                   self.msg_get,
                   self.msg_get_success,
                   self.msg_get_failure,
@@ -254,7 +310,17 @@ impl Stats {
                   self.msg_get_account_info,
                   self.msg_get_account_info_success,
                   self.msg_get_account_info_failure,
-                  self.msg_refresh);
+                  self.msg_rotate_key,
+                  self.msg_rotate_key_success,
+                  self.msg_rotate_key_failure,
+                  self.msg_migrate_account,
+                  self.msg_refresh,
+                  self.msg_custom,
+                  self.msg_custom_response,
+                  self.msg_get_batch,
+                  self.msg_get_batch_response,
+                  self.msg_put_batch,
+                  self.msg_put_batch_response);
         }
     }
 }
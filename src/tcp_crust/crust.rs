@@ -0,0 +1,549 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::support::{self, Config, Handshake};
+use maidsafe_utilities::event_sender;
+use maidsafe_utilities::serialisation::{deserialise, serialise};
+use rust_sodium::crypto::box_;
+use std::{fmt, io, thread};
+use std::collections::{HashMap, HashSet};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// How long to wait for the other side's handshake on a new connection.
+const HANDSHAKE_TIMEOUT_SECS: u64 = 10;
+/// How often the (non-blocking) listener checks for new connections and for shutdown.
+const ACCEPT_POLL_INTERVAL_MS: u64 = 20;
+
+/// Loopback version of `crust::Service`.
+pub struct Service {
+    inner: Arc<Mutex<Inner>>,
+    listener: Option<JoinHandle<()>>,
+}
+
+struct Inner {
+    our_id: PeerId,
+    event_tx: CrustEventSender,
+    config: Config,
+    listener_addr: Option<SocketAddr>,
+    connections: HashMap<PeerId, Connection>,
+    next_connection_id: u64,
+    running: bool,
+}
+
+struct Connection {
+    id: u64,
+    stream: TcpStream,
+}
+
+impl Service {
+    /// Create a new `Service` using the config from the environment (see `Config::read`).
+    pub fn new(event_sender: CrustEventSender) -> Result<Self, CrustError> {
+        Self::with_config(event_sender, Config::read())
+    }
+
+    /// Create a new `Service` with the given config.
+    pub fn with_config(event_sender: CrustEventSender, config: Config) -> Result<Self, CrustError> {
+        let our_id = PeerId(box_::gen_keypair().0);
+        Ok(Service {
+               inner: Arc::new(Mutex::new(Inner {
+                                              our_id: our_id,
+                                              event_tx: event_sender,
+                                              config: config,
+                                              listener_addr: None,
+                                              connections: HashMap::new(),
+                                              next_connection_id: 0,
+                                              running: true,
+                                          })),
+               listener: None,
+           })
+    }
+
+    /// Start the bootstrapping procedure: try each contact in turn and raise `BootstrapConnect`
+    /// for the first one that accepts us, or `BootstrapFailed` if none does.
+    pub fn start_bootstrap(&mut self,
+                           blacklist: HashSet<SocketAddr>,
+                           user: CrustUser)
+                           -> Result<(), CrustError> {
+        let (our_id, contacts) = {
+            let inner = lock(&self.inner);
+            let contacts: Vec<SocketAddr> = inner
+                .config
+                .hard_coded_contacts
+                .iter()
+                .map(|port| support::loopback(*port))
+                .filter(|addr| !blacklist.contains(addr) && Some(*addr) != inner.listener_addr)
+                .collect();
+            (inner.our_id, contacts)
+        };
+        let inner = self.inner.clone();
+        let _ = spawn("TCP bootstrap", move || {
+            for addr in contacts {
+                let (peer_id, stream) =
+                    match handshake_out(addr, Handshake::Bootstrap(our_id, user)) {
+                        Ok(result) => result,
+                        Err(error) => {
+                            debug!(target: "crust",
+                                   "Failed to bootstrap off {}: {:?}",
+                                   addr,
+                                   error);
+                            continue;
+                        }
+                    };
+                if register(&inner, peer_id, stream) {
+                    send_event(&inner, Event::BootstrapConnect(peer_id, addr));
+                    return;
+                }
+            }
+            send_event(&inner, Event::BootstrapFailed);
+        });
+        Ok(())
+    }
+
+    /// Stops the ongoing bootstrap. Bootstrapping over loopback is not interruptible, so this
+    /// does nothing.
+    pub fn stop_bootstrap(&mut self) -> Result<(), CrustError> {
+        Ok(())
+    }
+
+    /// Start service discovery (beacon). Not supported: there is no discovery on loopback.
+    pub fn start_service_discovery(&mut self) {
+        trace!(target: "crust", "[TCP] start_service_discovery not supported");
+    }
+
+    /// Enable listening and responding to peers searching for us. Not supported.
+    pub fn set_service_discovery_listen(&self, _listen: bool) {
+        trace!(target: "crust", "[TCP] set_service_discovery_listen not supported");
+    }
+
+    /// Check if we have peers on LAN. Always `false`, so that several nodes can run on the same
+    /// machine.
+    pub fn has_peers_on_lan(&self) -> bool {
+        false
+    }
+
+    /// Start the TCP acceptor on `127.0.0.1`, raising `ListenerStarted` or `ListenerFailed`.
+    pub fn start_listening_tcp(&mut self) -> Result<(), CrustError> {
+        if self.listener.is_some() {
+            return Ok(());
+        }
+        let port = lock(&self.inner).config.listen_port.unwrap_or(0);
+        let listener = match TcpListener::bind(support::loopback(port))
+                  .and_then(|listener| listener.set_nonblocking(true).map(|()| listener))
+                  .and_then(|listener| listener.local_addr().map(|addr| (listener, addr))) {
+            Ok((listener, addr)) => {
+                lock(&self.inner).listener_addr = Some(addr);
+                send_event(&self.inner, Event::ListenerStarted(addr.port()));
+                listener
+            }
+            Err(error) => {
+                warn!(target: "crust", "Failed to listen on port {}: {:?}", port, error);
+                send_event(&self.inner, Event::ListenerFailed);
+                return Ok(());
+            }
+        };
+        let inner = self.inner.clone();
+        self.listener = Some(spawn("TCP listener", move || run_listener(&inner, &listener)));
+        Ok(())
+    }
+
+    /// Request connection info structure used for establishing peer-to-peer connections. This
+    /// fails unless we are listening.
+    pub fn prepare_connection_info(&self, result_token: u32) {
+        let result = {
+            let inner = lock(&self.inner);
+            inner
+                .listener_addr
+                .map(|addr| PrivConnectionInfo(inner.our_id, addr))
+                .ok_or(CrustError::NotListening)
+        };
+        send_event(&self.inner,
+                   Event::ConnectionInfoPrepared(ConnectionInfoResult {
+                                                     result_token: result_token,
+                                                     result: result,
+                                                 }));
+    }
+
+    /// Connect to a peer using our and their connection infos. Raises `ConnectSuccess` or
+    /// `ConnectFailure` once done.
+    ///
+    /// Both sides are expected to call this. To avoid two competing connections, only the peer
+    /// with the lower ID dials; the other one waits for the incoming connection.
+    pub fn connect(&self,
+                   our_info: PrivConnectionInfo,
+                   their_info: PubConnectionInfo)
+                   -> Result<(), CrustError> {
+        let PubConnectionInfo(their_id, addr) = their_info;
+        if self.is_connected(&their_id) {
+            return Ok(());
+        }
+        let inner = self.inner.clone();
+        if our_info.0 > their_id {
+            let _ = spawn("TCP await connect", move || {
+                let deadline = Instant::now() + Duration::from_secs(HANDSHAKE_TIMEOUT_SECS);
+                while Instant::now() < deadline {
+                    if lock(&inner).connections.contains_key(&their_id) {
+                        return;
+                    }
+                    thread::sleep(Duration::from_millis(ACCEPT_POLL_INTERVAL_MS));
+                }
+                send_event(&inner, Event::ConnectFailure(their_id));
+            });
+            return Ok(());
+        }
+        let _ = spawn("TCP connect", move || {
+            match handshake_out(addr, Handshake::Connect(our_info.0)) {
+                Ok((peer_id, stream)) if peer_id == their_id => {
+                    if register(&inner, peer_id, stream) {
+                        send_event(&inner, Event::ConnectSuccess(peer_id));
+                    }
+                }
+                Ok((peer_id, _)) => {
+                    debug!(target: "crust",
+                           "Expected {:?} at {}, found {:?}",
+                           their_id,
+                           addr,
+                           peer_id);
+                    send_event(&inner, Event::ConnectFailure(their_id));
+                }
+                Err(error) => {
+                    debug!(target: "crust", "Failed to connect to {}: {:?}", addr, error);
+                    send_event(&inner, Event::ConnectFailure(their_id));
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Disconnect from the given peer. Does not raise `LostPeer`.
+    pub fn disconnect(&self, peer_id: PeerId) -> bool {
+        if let Some(connection) = lock(&self.inner).connections.remove(&peer_id) {
+            let _ = connection.stream.shutdown(Shutdown::Both);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Send message to the given peer.
+    pub fn send(&self, id: PeerId, data: Vec<u8>, _priority: u8) -> io::Result<()> {
+        if data.len() > support::MAX_PAYLOAD_SIZE {
+            send_event(&self.inner, Event::WriteMsgSizeProhibitive(id, data));
+            return Ok(());
+        }
+        let stream = match lock(&self.inner).connections.get(&id) {
+            Some(connection) => connection.stream.try_clone(),
+            None => {
+                let msg = format!("No connection to peer {:?}", id);
+                return Err(io::Error::new(io::ErrorKind::Other, msg));
+            }
+        };
+        support::write_frame(&mut stream?, &data)
+    }
+
+    /// Returns `true` if we are currently connected to the given `peer_id`
+    pub fn is_connected(&self, peer_id: &PeerId) -> bool {
+        lock(&self.inner).connections.contains_key(peer_id)
+    }
+
+    /// Adds the peer to the whitelist. All loopback peers are whitelisted, so this does nothing.
+    pub fn whitelist_peer(&self, _peer_id: PeerId) {}
+
+    /// Returns `true` if the specified peer is allowed to connect to us. (Always `true`.)
+    pub fn is_peer_whitelisted(&self, _peer_id: &PeerId) -> bool {
+        true
+    }
+
+    /// Returns `true` if the specified peer's IP is hard-coded. (Always `true` on loopback.)
+    pub fn is_peer_hard_coded(&self, _peer_id: &PeerId) -> bool {
+        true
+    }
+
+    /// Our `PeerId`.
+    pub fn id(&self) -> PeerId {
+        lock(&self.inner).our_id
+    }
+
+    /// The address we are listening on, if any.
+    pub fn listener_addr(&self) -> Option<SocketAddr> {
+        lock(&self.inner).listener_addr
+    }
+}
+
+impl Drop for Service {
+    fn drop(&mut self) {
+        {
+            let mut inner = lock(&self.inner);
+            inner.running = false;
+            for (_, connection) in inner.connections.drain() {
+                let _ = connection.stream.shutdown(Shutdown::Both);
+            }
+        }
+        if let Some(listener) = self.listener.take() {
+            let _ = listener.join();
+        }
+    }
+}
+
+/// Loopback version of `crust::PeerId`.
+#[derive(Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct PeerId(pub box_::PublicKey);
+
+impl fmt::Debug for PeerId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "PeerId({:02x}{:02x}{:02x}..)",
+               (self.0).0[0],
+               (self.0).0[1],
+               (self.0).0[2])
+    }
+}
+
+/// Loopback version of `crust::Event`.
+#[derive(Debug)]
+pub enum Event {
+    /// Invoked when a bootstrap peer connects to us
+    BootstrapAccept(PeerId, CrustUser),
+    /// Invoked when we get a bootstrap connection to a new peer.
+    BootstrapConnect(PeerId, SocketAddr),
+    /// Invoked when we failed to connect to all bootstrap contacts.
+    BootstrapFailed,
+    /// Invoked when we are ready to listen for incomming connection. Contains
+    /// the listening port.
+    ListenerStarted(u16),
+    /// Invoked when listener failed to start.
+    ListenerFailed,
+    /// Invoked as a result to the call of `Service::prepare_contact_info`.
+    ConnectionInfoPrepared(ConnectionInfoResult),
+    /// Invoked when connection to a new peer has been established.
+    ConnectSuccess(PeerId),
+    /// Invoked when connection to a new peer has failed.
+    ConnectFailure(PeerId),
+    /// Invoked when a peer is lost or having read/write error.
+    LostPeer(PeerId),
+    /// Invoked when a new message is received.  Passes the message.
+    NewMessage(PeerId, Vec<u8>),
+    /// Invoked when trying to sending a too large data.
+    WriteMsgSizeProhibitive(PeerId, Vec<u8>),
+}
+
+/// Loopback version of `CrustEventSender`.
+pub type CrustEventSender = event_sender::MaidSafeObserver<Event>;
+
+/// Loopback version of `PrivConnectionInfo`: our ID and listening address.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PrivConnectionInfo(pub PeerId, pub SocketAddr);
+
+impl PrivConnectionInfo {
+    /// Convert our connection info to theirs so that we can give it to them.
+    pub fn to_pub_connection_info(&self) -> PubConnectionInfo {
+        PubConnectionInfo(self.0, self.1)
+    }
+}
+
+/// Loopback version of `PubConnectionInfo`, used to connect to another peer.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PubConnectionInfo(pub PeerId, pub SocketAddr);
+
+impl PubConnectionInfo {
+    /// The peer's Crust ID.
+    pub fn id(&self) -> PeerId {
+        self.0
+    }
+}
+
+/// The result of a `Service::prepare_contact_info` call.
+#[derive(Debug)]
+pub struct ConnectionInfoResult {
+    /// The token that was passed to `prepare_connection_info`.
+    pub result_token: u32,
+    /// The new contact info, if successful.
+    pub result: Result<PrivConnectionInfo, CrustError>,
+}
+
+/// Loopback version of `crust::CrustError`.
+#[derive(Debug)]
+pub enum CrustError {
+    /// We are not listening, so we have no connection info.
+    NotListening,
+    /// I/O error.
+    Io(io::Error),
+}
+
+impl From<io::Error> for CrustError {
+    fn from(error: io::Error) -> CrustError {
+        CrustError::Io(error)
+    }
+}
+
+/// Specify crust user. Behaviour (for example in bootstrap phase) will be different for different
+/// variants.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum CrustUser {
+    /// Crust user is a Node.
+    Node,
+    /// Crust user is a Client.
+    Client,
+}
+
+fn lock(inner: &Arc<Mutex<Inner>>) -> MutexGuard<Inner> {
+    // A poisoned mutex means one of our threads panicked while holding it; the data is still
+    // usable for shutting down.
+    inner.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn spawn<F: FnOnce() + Send + 'static>(name: &str, f: F) -> JoinHandle<()> {
+    unwrap!(thread::Builder::new().name(name.to_string()).spawn(f))
+}
+
+fn send_event(inner: &Arc<Mutex<Inner>>, event: Event) {
+    let event_tx = {
+        let inner = lock(inner);
+        if !inner.running {
+            return;
+        }
+        inner.event_tx.clone()
+    };
+    if let Err(error) = event_tx.send(event) {
+        debug!(target: "crust", "Failed to send event: {:?}", error);
+    }
+}
+
+// Connects to `addr`, sends `hello` and waits for the listener's `Accept`.
+fn handshake_out(addr: SocketAddr, hello: Handshake) -> io::Result<(PeerId, TcpStream)> {
+    let mut stream = TcpStream::connect(addr)?;
+    stream.set_read_timeout(Some(Duration::from_secs(HANDSHAKE_TIMEOUT_SECS)))?;
+    support::write_frame(&mut stream, &serialise(&hello).map_err(to_io_error)?)?;
+    let reply = deserialise(&support::read_frame(&mut stream)?).map_err(to_io_error)?;
+    stream.set_read_timeout(None)?;
+    match reply {
+        Handshake::Accept(peer_id) => Ok((peer_id, stream)),
+        handshake => {
+            let msg = format!("Unexpected handshake {:?}", handshake);
+            Err(io::Error::new(io::ErrorKind::InvalidData, msg))
+        }
+    }
+}
+
+// Reads the peer's handshake on an accepted connection, replies with `Accept` and registers it.
+fn handshake_in(inner: &Arc<Mutex<Inner>>, mut stream: TcpStream) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(HANDSHAKE_TIMEOUT_SECS)))?;
+    let hello = deserialise(&support::read_frame(&mut stream)?).map_err(to_io_error)?;
+    stream.set_read_timeout(None)?;
+    let our_id = lock(inner).our_id;
+    support::write_frame(&mut stream,
+                         &serialise(&Handshake::Accept(our_id)).map_err(to_io_error)?)?;
+    match hello {
+        Handshake::Bootstrap(peer_id, user) => {
+            if register(inner, peer_id, stream) {
+                send_event(inner, Event::BootstrapAccept(peer_id, user));
+            }
+        }
+        Handshake::Connect(peer_id) => {
+            if register(inner, peer_id, stream) {
+                send_event(inner, Event::ConnectSuccess(peer_id));
+            }
+        }
+        Handshake::Accept(peer_id) => {
+            debug!(target: "crust", "Unexpected Accept handshake from {:?}", peer_id);
+        }
+    }
+    Ok(())
+}
+
+// Adds the connection to `peer_id` and starts reading from it. Returns `false`, and closes the
+// stream, if we are already connected to that peer.
+fn register(inner_arc: &Arc<Mutex<Inner>>, peer_id: PeerId, stream: TcpStream) -> bool {
+    let reader = match stream.try_clone() {
+        Ok(reader) => reader,
+        Err(error) => {
+            debug!(target: "crust", "Failed to clone stream to {:?}: {:?}", peer_id, error);
+            return false;
+        }
+    };
+    let mut inner = lock(inner_arc);
+    if !inner.running || inner.connections.contains_key(&peer_id) {
+        let _ = stream.shutdown(Shutdown::Both);
+        return false;
+    }
+    let connection_id = inner.next_connection_id;
+    inner.next_connection_id += 1;
+    let _ = inner
+        .connections
+        .insert(peer_id,
+                Connection {
+                    id: connection_id,
+                    stream: stream,
+                });
+    let inner_clone = inner_arc.clone();
+    let _ = spawn("TCP reader",
+                  move || run_reader(&inner_clone, peer_id, connection_id, reader));
+    true
+}
+
+fn run_listener(inner: &Arc<Mutex<Inner>>, listener: &TcpListener) {
+    while lock(inner).running {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let inner = inner.clone();
+                let _ = spawn("TCP handshake", move || if let Err(error) =
+                    handshake_in(&inner, stream) {
+                    debug!(target: "crust", "Failed to accept connection: {:?}", error);
+                });
+            }
+            Err(ref error) if error.kind() == io::ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(ACCEPT_POLL_INTERVAL_MS));
+            }
+            Err(error) => {
+                warn!(target: "crust", "TCP listener failed: {:?}", error);
+                break;
+            }
+        }
+    }
+}
+
+fn run_reader(inner: &Arc<Mutex<Inner>>,
+              peer_id: PeerId,
+              connection_id: u64,
+              mut stream: TcpStream) {
+    while let Ok(payload) = support::read_frame(&mut stream) {
+        send_event(inner, Event::NewMessage(peer_id, payload));
+    }
+    // Only report the loss if this connection hasn't been replaced or explicitly disconnected.
+    let lost = {
+        let mut inner = lock(inner);
+        if inner
+               .connections
+               .get(&peer_id)
+               .map_or(false, |connection| connection.id == connection_id) {
+            let _ = inner.connections.remove(&peer_id);
+            true
+        } else {
+            false
+        }
+    };
+    if lost {
+        send_event(inner, Event::LostPeer(peer_id));
+    }
+}
+
+fn to_io_error<E: fmt::Debug>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", error))
+}
@@ -0,0 +1,33 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Plain TCP transport restricted to the loopback interface.
+//!
+//! This is a drop-in replacement for the subset of the crust API used by routing. Peers talk to
+//! each other over `std::net::TcpStream`s carrying length-prefixed frames. There is no service
+//! discovery, no bootstrap cache and no NAT traversal: every process reads a static list of
+//! contact ports (see `Config`) and only ever binds to and connects to `127.0.0.1`. It is intended
+//! for running a real multi-process network on a single machine, e.g. on CI.
+
+/// Loopback version of the crust API.
+pub mod crust;
+mod support;
+
+#[cfg(test)]
+mod tests;
+
+pub use self::support::Config;
@@ -0,0 +1,157 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::crust::{CrustUser, PeerId};
+use std::env;
+use std::io::{self, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+
+/// Environment variable holding the port to listen on.
+pub const LISTEN_PORT_ENV_VAR: &'static str = "ROUTING_TCP_LISTEN_PORT";
+/// Environment variable holding a comma-separated list of contact ports.
+pub const CONTACTS_ENV_VAR: &'static str = "ROUTING_TCP_CONTACTS";
+
+/// The maximum size of a single frame's payload, in bytes.
+pub const MAX_PAYLOAD_SIZE: usize = 2 * 1024 * 1024;
+
+/// Static configuration of the loopback transport.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Config {
+    /// The port to listen on. If `None`, the OS picks a free port.
+    ///
+    /// Only processes which will never be relocated (i.e. the first node) should set this, as a
+    /// relocating node restarts its service and would try to bind the same port again.
+    pub listen_port: Option<u16>,
+    /// The ports on `127.0.0.1` to bootstrap off, tried in order.
+    pub hard_coded_contacts: Vec<u16>,
+}
+
+impl Config {
+    /// Creates a config listening on any port, with no contacts.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Creates a config with the given contact ports.
+    pub fn with_contacts(contacts: &[u16]) -> Self {
+        Config {
+            listen_port: None,
+            hard_coded_contacts: contacts.to_vec(),
+        }
+    }
+
+    /// Reads the config from the `ROUTING_TCP_LISTEN_PORT` and `ROUTING_TCP_CONTACTS` environment
+    /// variables. Unset or malformed values are ignored.
+    pub fn read() -> Self {
+        let listen_port = env::var(LISTEN_PORT_ENV_VAR)
+            .ok()
+            .and_then(|port| port.trim().parse().ok());
+        let hard_coded_contacts = env::var(CONTACTS_ENV_VAR)
+            .ok()
+            .map_or_else(Vec::new, |contacts| parse_ports(&contacts));
+        Config {
+            listen_port: listen_port,
+            hard_coded_contacts: hard_coded_contacts,
+        }
+    }
+}
+
+/// Returns the loopback address with the given port.
+pub fn loopback(port: u16) -> SocketAddr {
+    SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), port))
+}
+
+/// The first frame sent in each direction on a new connection.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Handshake {
+    /// Sent by a peer bootstrapping off the listener.
+    Bootstrap(PeerId, CrustUser),
+    /// Sent by a peer connecting after exchanging connection infos.
+    Connect(PeerId),
+    /// The listener's reply, accepting the connection.
+    Accept(PeerId),
+}
+
+/// Writes `payload` to `stream`, prefixed with its length as a big-endian `u32`.
+pub fn write_frame<W: Write>(stream: &mut W, payload: &[u8]) -> io::Result<()> {
+    if payload.len() > MAX_PAYLOAD_SIZE {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Frame payload too large"));
+    }
+    let len = payload.len() as u32;
+    let header = [(len >> 24) as u8, (len >> 16) as u8, (len >> 8) as u8, len as u8];
+    stream.write_all(&header)?;
+    stream.write_all(payload)?;
+    stream.flush()
+}
+
+/// Reads a single length-prefixed frame from `stream` and returns its payload.
+pub fn read_frame<R: Read>(stream: &mut R) -> io::Result<Vec<u8>> {
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header)?;
+    let len = header
+        .iter()
+        .fold(0usize, |len, byte| (len << 8) | *byte as usize);
+    if len > MAX_PAYLOAD_SIZE {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Frame payload too large"));
+    }
+    let mut payload = vec![0u8; len];
+    stream.read_exact(&mut payload)?;
+    Ok(payload)
+}
+
+fn parse_ports(ports: &str) -> Vec<u16> {
+    ports
+        .split(',')
+        .filter_map(|port| port.trim().parse().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn frame_round_trip() {
+        let mut buffer = Vec::new();
+        unwrap!(write_frame(&mut buffer, b"hello"));
+        unwrap!(write_frame(&mut buffer, b""));
+        assert_eq!(buffer.len(), 4 + 5 + 4);
+
+        let mut cursor = Cursor::new(buffer);
+        assert_eq!(unwrap!(read_frame(&mut cursor)), b"hello".to_vec());
+        assert!(unwrap!(read_frame(&mut cursor)).is_empty());
+        assert!(read_frame(&mut cursor).is_err());
+    }
+
+    #[test]
+    fn oversized_frames_are_rejected() {
+        let mut buffer = Vec::new();
+        assert!(write_frame(&mut buffer, &vec![0; MAX_PAYLOAD_SIZE + 1]).is_err());
+        assert!(buffer.is_empty());
+
+        let len = (MAX_PAYLOAD_SIZE + 1) as u32;
+        let header = vec![(len >> 24) as u8, (len >> 16) as u8, (len >> 8) as u8, len as u8];
+        assert!(read_frame(&mut Cursor::new(header)).is_err());
+    }
+
+    #[test]
+    fn parse_contacts() {
+        assert_eq!(parse_ports("5483, 5484,x,,70000"), vec![5483, 5484]);
+        assert!(parse_ports("").is_empty());
+    }
+}
@@ -0,0 +1,132 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::crust::{CrustEventSender, CrustUser, Event, PeerId, Service};
+use super::support::Config;
+use maidsafe_utilities::event_sender::{MaidSafeEventCategory, MaidSafeObserver};
+use std::collections::HashSet;
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+fn get_event_sender() -> (CrustEventSender, Receiver<MaidSafeEventCategory>, Receiver<Event>) {
+    let (category_tx, category_rx) = mpsc::channel();
+    let (event_tx, event_rx) = mpsc::channel();
+
+    (MaidSafeObserver::new(event_tx, MaidSafeEventCategory::Crust, category_tx),
+     category_rx,
+     event_rx)
+}
+
+// Waits for an event from the given receiver and asserts that it matches the given pattern.
+macro_rules! expect_event {
+    ($rx:expr, $pattern:pat) => {
+        match unwrap!($rx.recv_timeout(Duration::from_secs(10))) {
+            $pattern => (),
+            e => panic!("unexpected event {:?}", e),
+        }
+    };
+
+    ($rx:expr, $pattern:pat => $arm:expr) => {
+        match unwrap!($rx.recv_timeout(Duration::from_secs(10))) {
+            $pattern => $arm,
+            e => panic!("unexpected event {:?}", e),
+        }
+    }
+}
+
+fn start_listening(service: &mut Service, event_rx: &Receiver<Event>) -> u16 {
+    unwrap!(service.start_listening_tcp());
+    expect_event!(event_rx, Event::ListenerStarted(port) => port)
+}
+
+#[test]
+fn start_two_services_bootstrap_communicate_exit() {
+    let (event_sender_0, _category_rx_0, event_rx_0) = get_event_sender();
+    let (event_sender_1, _category_rx_1, event_rx_1) = get_event_sender();
+
+    let mut service_0 = unwrap!(Service::with_config(event_sender_0, Config::new()));
+    let port_0 = start_listening(&mut service_0, &event_rx_0);
+
+    let mut service_1 = unwrap!(Service::with_config(event_sender_1,
+                                                     Config::with_contacts(&[port_0])));
+    unwrap!(service_1.start_bootstrap(HashSet::new(), CrustUser::Client));
+
+    let id_0 = expect_event!(event_rx_1, Event::BootstrapConnect(id, _) => id);
+    assert_eq!(id_0, service_0.id());
+    let id_1 = expect_event!(event_rx_0, Event::BootstrapAccept(id, CrustUser::Client) => id);
+    assert_eq!(id_1, service_1.id());
+
+    unwrap!(service_1.send(id_0, b"hello".to_vec(), 0));
+    let (id, data) = expect_event!(event_rx_0, Event::NewMessage(id, data) => (id, data));
+    assert_eq!((id, &data[..]), (id_1, &b"hello"[..]));
+    unwrap!(service_0.send(id_1, b"world".to_vec(), 0));
+    let (id, data) = expect_event!(event_rx_1, Event::NewMessage(id, data) => (id, data));
+    assert_eq!((id, &data[..]), (id_0, &b"world"[..]));
+
+    drop(service_1);
+    let lost_id = expect_event!(event_rx_0, Event::LostPeer(id) => id);
+    assert_eq!(lost_id, id_1);
+    assert!(!service_0.is_connected(&id_1));
+}
+
+#[test]
+fn bootstrap_fails_without_contacts() {
+    let (event_sender, _category_rx, event_rx) = get_event_sender();
+    let mut service = unwrap!(Service::with_config(event_sender, Config::new()));
+    unwrap!(service.start_bootstrap(HashSet::new(), CrustUser::Node));
+    expect_event!(event_rx, Event::BootstrapFailed);
+}
+
+#[test]
+fn connect_two_services_via_connection_info() {
+    let (event_sender_0, _category_rx_0, event_rx_0) = get_event_sender();
+    let (event_sender_1, _category_rx_1, event_rx_1) = get_event_sender();
+
+    let mut service_0 = unwrap!(Service::with_config(event_sender_0, Config::new()));
+    let mut service_1 = unwrap!(Service::with_config(event_sender_1, Config::new()));
+    let _ = start_listening(&mut service_0, &event_rx_0);
+    let _ = start_listening(&mut service_1, &event_rx_1);
+
+    service_0.prepare_connection_info(0);
+    let our_info_0 = expect_event!(event_rx_0, Event::ConnectionInfoPrepared(result) => {
+        assert_eq!(result.result_token, 0);
+        unwrap!(result.result)
+    });
+    service_1.prepare_connection_info(1);
+    let our_info_1 = expect_event!(event_rx_1, Event::ConnectionInfoPrepared(result) => {
+        assert_eq!(result.result_token, 1);
+        unwrap!(result.result)
+    });
+
+    // Both sides connect simultaneously; each must report exactly one success.
+    unwrap!(service_0.connect(our_info_0.clone(), our_info_1.to_pub_connection_info()));
+    unwrap!(service_1.connect(our_info_1, our_info_0.to_pub_connection_info()));
+
+    let id_1: PeerId = expect_event!(event_rx_0, Event::ConnectSuccess(id) => id);
+    let id_0: PeerId = expect_event!(event_rx_1, Event::ConnectSuccess(id) => id);
+    assert_eq!(id_0, service_0.id());
+    assert_eq!(id_1, service_1.id());
+
+    unwrap!(service_0.send(id_1, b"ping".to_vec(), 0));
+    let (id, data) = expect_event!(event_rx_1, Event::NewMessage(id, data) => (id, data));
+    assert_eq!((id, &data[..]), (id_0, &b"ping"[..]));
+
+    assert!(service_0.disconnect(id_1));
+    assert!(!service_0.is_connected(&id_1));
+    let lost_id = expect_event!(event_rx_1, Event::LostPeer(id) => id);
+    assert_eq!(lost_id, id_0);
+}
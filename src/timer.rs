@@ -226,27 +226,164 @@ mod implementation {
     }
 }
 
+#[cfg(feature = "use-mock-crust")]
+pub use self::implementation::advance_time;
+
 #[cfg(feature = "use-mock-crust")]
 mod implementation {
-    use std::cell::Cell;
+    use action::Action;
+    use fake_clock::FakeClock as Instant;
+    use std::cell::{Cell, RefCell};
+    use std::rc::{Rc, Weak};
     use std::time::Duration;
     use types::RoutingActionSender;
 
-    // The mock timer currently never raises timeout events.
+    thread_local! {
+        // All timers created on this thread, so `advance_time` can raise their timeout events.
+        static TIMERS: RefCell<Vec<Weak<RefCell<Inner>>>> = RefCell::new(Vec::new());
+        // Counts all scheduled timeouts, to order those with identical deadlines.
+        static NEXT_SEQUENCE_NUMBER: Cell<u64> = Cell::new(0);
+    }
+
+    struct Detail {
+        scheduled: Instant,
+        duration: Duration,
+        sequence_number: u64,
+        token: u64,
+    }
+
+    /// Mock timer, driven by the virtual clock: timeout events are raised by `advance_time`.
     #[derive(Clone)]
     pub struct Timer {
-        next_token: Cell<u64>,
+        inner: Rc<RefCell<Inner>>,
+    }
+
+    struct Inner {
+        next_token: u64,
+        sender: RoutingActionSender,
+        pending: Vec<Detail>,
     }
 
     impl Timer {
-        pub fn new(_: RoutingActionSender) -> Self {
-            Timer { next_token: Cell::new(0) }
+        pub fn new(sender: RoutingActionSender) -> Self {
+            let inner = Rc::new(RefCell::new(Inner {
+                                                 next_token: 0,
+                                                 sender: sender,
+                                                 pending: Vec::new(),
+                                             }));
+            TIMERS.with(|timers| timers.borrow_mut().push(Rc::downgrade(&inner)));
+            Timer { inner: inner }
         }
 
-        pub fn schedule(&self, _: Duration) -> u64 {
-            let token = self.next_token.get();
-            self.next_token.set(token.wrapping_add(1));
+        pub fn schedule(&self, duration: Duration) -> u64 {
+            let mut inner = self.inner.borrow_mut();
+
+            let token = inner.next_token;
+            inner.next_token = token.wrapping_add(1);
+
+            let sequence_number = NEXT_SEQUENCE_NUMBER.with(|next| {
+                let sequence_number = next.get();
+                next.set(sequence_number + 1);
+                sequence_number
+            });
+            inner
+                .pending
+                .push(Detail {
+                          scheduled: Instant::now(),
+                          duration: duration,
+                          sequence_number: sequence_number,
+                          token: token,
+                      });
             token
         }
     }
+
+    /// Advances the virtual clock by `duration` and raises all timeout events which expired, in
+    /// order of their deadlines. Timeouts scheduled while handling these events are only raised by
+    /// a later call, so tests should advance the time in steps no larger than the timeouts
+    /// they want to see repeated.
+    pub fn advance_time(duration: Duration) {
+        let millis = duration.as_secs() * 1000 + u64::from(duration.subsec_nanos() / 1_000_000);
+        Instant::advance_time(millis);
+
+        // For each expired timeout: how long ago it expired, its sequence number, the sender and
+        // the token.
+        let mut expired = Vec::new();
+        TIMERS.with(|timers| {
+            let mut timers = timers.borrow_mut();
+            timers.retain(|timer| timer.upgrade().is_some());
+            for timer in timers.iter().filter_map(Weak::upgrade) {
+                let mut inner = timer.borrow_mut();
+                let (due, pending): (Vec<_>, Vec<_>) =
+                    inner
+                        .pending
+                        .drain(..)
+                        .partition(|detail| detail.scheduled.elapsed() >= detail.duration);
+                inner.pending = pending;
+                for detail in due {
+                    expired.push((detail.scheduled.elapsed() - detail.duration,
+                                  detail.sequence_number,
+                                  inner.sender.clone(),
+                                  detail.token));
+                }
+            }
+        });
+
+        expired.sort_by(|lhs, rhs| rhs.0.cmp(&lhs.0).then(lhs.1.cmp(&rhs.1)));
+        for (_, _, sender, token) in expired {
+            let _ = sender.send(Action::Timeout(token));
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use action::Action;
+        use maidsafe_utilities::event_sender::MaidSafeEventCategory;
+        use std::sync::mpsc;
+        use std::time::Duration;
+        use types::RoutingActionSender;
+
+        #[test]
+        fn schedule() {
+            let (action_sender, action_receiver) = mpsc::channel();
+            let (category_sender, _category_receiver) = mpsc::channel();
+            let sender = RoutingActionSender::new(action_sender,
+                                                  MaidSafeEventCategory::Routing,
+                                                  category_sender);
+            let timer = Timer::new(sender);
+            let interval = Duration::from_millis(500);
+
+            // Add deadlines, the first to time out after 2.5s, the second after 2.0s, and so on
+            // down to 500ms. The last two share a deadline.
+            let count = 5;
+            for i in 0..count {
+                let token = timer.schedule(interval * (count - i));
+                assert_eq!(token, i as u64);
+            }
+            assert_eq!(timer.schedule(interval), count as u64);
+
+            advance_time(interval / 2);
+            assert!(action_receiver.try_recv().is_err());
+
+            // Advancing past several deadlines raises them in order of expiry.
+            advance_time(interval * 2);
+            let tokens = action_receiver
+                .try_iter()
+                .map(|action| match action {
+                         Action::Timeout(token) => token,
+                         unexpected_action => {
+                             panic!("Expected `Action::Timeout`, but received {:?}",
+                                    unexpected_action)
+                         }
+                     })
+                .collect::<Vec<_>>();
+            assert_eq!(tokens, vec![4, 5, 3]);
+
+            // Timeouts of a dropped timer are never raised.
+            drop(timer);
+            advance_time(interval * 10);
+            assert!(action_receiver.try_recv().is_err());
+        }
+    }
 }
@@ -22,6 +22,7 @@ use maidsafe_utilities::event_sender::MaidSafeObserver;
 use rand;
 #[cfg(any(test, feature = "use-mock-crust"))]
 use rand::Rng;
+use tiny_keccak::sha3_256;
 use xor_name::XorName;
 
 pub type RoutingActionSender = MaidSafeObserver<::action::Action>;
@@ -66,6 +67,14 @@ impl MessageId {
         MessageId(name)
     }
 
+    /// Generate the `MessageId` of the item at `index` of the batch request with the given ID.
+    pub fn from_batch_item(batch_id: &MessageId, index: usize) -> MessageId {
+        let MessageId(XorName(ref batch_bytes)) = *batch_id;
+        let mut bytes = batch_bytes.to_vec();
+        bytes.extend((0..8).map(|i| (index as u64 >> (8 * i)) as u8));
+        MessageId(XorName(sha3_256(&bytes)))
+    }
+
     /// Generate the reverse of the given `MessageId`.
     pub fn from_reverse(name: &MessageId) -> MessageId {
         let MessageId(XorName(mut name_mut)) = *name;
@@ -22,7 +22,7 @@ use rust_sodium::crypto::hash::sha256;
 use std::collections::BTreeSet;
 use std::fmt::{self, Display, Write};
 use std::iter;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use xor_name::XorName;
 
 
@@ -54,6 +54,31 @@ impl Display for DisplayDurObj {
 }
 
 
+/// Returns the current time in seconds since the Unix epoch, as used for data expiry.
+#[cfg(not(feature = "use-mock-crust"))]
+pub fn unix_time_secs() -> u64 {
+    system_unix_time_secs()
+}
+
+/// Returns the current time in seconds since the Unix epoch, as used for data expiry.
+///
+/// In mock-crust tests, this is the system time when it was first queried on this thread, advanced
+/// by the virtual clock.
+#[cfg(feature = "use-mock-crust")]
+pub fn unix_time_secs() -> u64 {
+    use fake_clock::FakeClock;
+
+    thread_local!(static START: (u64, FakeClock) = (system_unix_time_secs(), FakeClock::now()));
+    START.with(|&(start_secs, ref start)| start_secs + start.elapsed().as_secs())
+}
+
+fn system_unix_time_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
 /// Format a vector of bytes as a hexadecimal number, ellipsising all but the first and last three.
 ///
 /// For three bytes with values 1, 2, 3, the output will be "010203".  For more than six bytes, e.g.
@@ -20,7 +20,6 @@ use super::{TestNode, create_connected_nodes, gen_immutable_data, poll_all,
 use routing::{Authority, Event, EventStream, MessageId, QUORUM_DENOMINATOR, QUORUM_NUMERATOR,
               Response, XorName};
 use routing::mock_crust::Network;
-use std::sync::mpsc;
 
 #[test]
 fn messages_accumulate_with_quorum() {
@@ -0,0 +1,100 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{create_connected_nodes, poll_all};
+use routing::{Event, EventStream};
+use routing::mock_crust::Network;
+use std::time::Duration;
+
+// Time after which a proposal which hasn't reached quorum is dropped.
+const AGREEMENT_TIMEOUT_SECS: u64 = 60;
+
+#[test]
+fn section_agreement() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut nodes = create_connected_nodes(&network, min_section_size);
+    let value = b"value".to_vec();
+
+    unwrap!(nodes[0].inner.vote(value.clone()));
+    let _ = poll_all(&mut nodes, &mut []);
+
+    let proposer = nodes[0].name();
+    for node in &mut nodes[1..] {
+        loop {
+            match node.try_next_ev() {
+                Ok(Event::SectionProposal {
+                       value: ref proposed_value,
+                       proposer: ref received_proposer,
+                   }) => {
+                    assert_eq!(*proposed_value, value);
+                    assert_eq!(*received_proposer, proposer);
+                    break;
+                }
+                Ok(_) => (),
+                Err(_) => panic!("Event::SectionProposal not received"),
+            }
+        }
+        unwrap!(node.inner.vote(value.clone()));
+    }
+    let _ = poll_all(&mut nodes, &mut []);
+
+    // Every node sees the quorum completed exactly once.
+    for node in &mut nodes {
+        let mut agreement_count = 0;
+        while let Ok(event) = node.try_next_ev() {
+            if let Event::SectionAgreement {
+                       value: ref agreed_value,
+                       ref voters,
+                   } = event {
+                assert_eq!(*agreed_value, value);
+                assert!(2 * voters.len() > min_section_size);
+                agreement_count += 1;
+            }
+        }
+        assert_eq!(agreement_count, 1);
+    }
+}
+
+#[test]
+fn section_agreement_timeout() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut nodes = create_connected_nodes(&network, min_section_size);
+    let value = b"value".to_vec();
+
+    // Nobody else votes, so the proposal times out on every node.
+    unwrap!(nodes[0].inner.vote(value.clone()));
+    let _ = poll_all(&mut nodes, &mut []);
+    network.advance_time(Duration::from_secs(AGREEMENT_TIMEOUT_SECS));
+    let _ = poll_all(&mut nodes, &mut []);
+
+    for node in &mut nodes {
+        let mut timed_out = false;
+        while let Ok(event) = node.try_next_ev() {
+            match event {
+                Event::SectionAgreement { .. } => panic!("Unexpected Event::SectionAgreement"),
+                Event::SectionAgreementTimeout(timed_out_value) => {
+                    assert_eq!(timed_out_value, value);
+                    timed_out = true;
+                }
+                _ => (),
+            }
+        }
+        assert!(timed_out);
+    }
+}
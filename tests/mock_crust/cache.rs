@@ -20,7 +20,6 @@ use super::{TestNode, create_connected_clients, create_connected_nodes_until_spl
 use rand::Rng;
 use routing::{Authority, Data, Event, EventStream, MessageId, Prefix, Request, Response};
 use routing::mock_crust::Network;
-use std::sync::mpsc;
 
 // Generate random immutable data, but make sure the first node in the given
 // node slice (the proxy node) is not in the data's section.
@@ -137,4 +136,7 @@ fn response_caching() {
     for node in nodes.iter_mut().take(min_section_size) {
         expect_no_event!(node);
     }
+
+    // The proxy node missed the cache for the first request and hit it for the second.
+    assert_eq!(unwrap!(nodes[0].inner.cache_hits_and_misses()), (1, 1));
 }
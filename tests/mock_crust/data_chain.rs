@@ -0,0 +1,106 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{TestNode, create_connected_nodes, poll_and_resend};
+use routing::{RELOCATION_AGE, XorName};
+use routing::mock_crust::{Config, Endpoint, Network};
+use std::collections::BTreeSet;
+
+// Checks that every node's data chain is valid, starts with the first node's genesis block and
+// ends with a block listing exactly the current members.
+fn verify_data_chains(nodes: &[TestNode]) {
+    let genesis_hash = unwrap!(unwrap!(unwrap!(nodes[0].inner.data_chain()).genesis_hash()));
+    let names: BTreeSet<XorName> = nodes.iter().map(TestNode::name).collect();
+    for node in nodes {
+        let data_chain = unwrap!(node.inner.data_chain());
+        unwrap!(data_chain.verify(&genesis_hash));
+        let members: BTreeSet<XorName> = unwrap!(data_chain.last())
+            .payload()
+            .members
+            .iter()
+            .map(|pub_id| *pub_id.name())
+            .collect();
+        assert_eq!(members, names, "{} has an outdated data chain.", node.name());
+    }
+}
+
+#[test]
+fn data_chain_records_joins_and_losses() {
+    let min_section_size = 5;
+    let network = Network::new(min_section_size, None);
+    let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+    poll_and_resend(&mut nodes, &mut []);
+    verify_data_chains(&nodes);
+
+    let _ = nodes.remove(1);
+    poll_and_resend(&mut nodes, &mut []);
+    verify_data_chains(&nodes);
+}
+
+#[test]
+fn old_node_is_relocated_and_keeps_its_age() {
+    let min_section_size = 5;
+    let network = Network::new(min_section_size, None);
+    // The seed node is the oldest one, so it will be relocated first. It rejoins via node 1.
+    let mut nodes = vec![TestNode::builder(&network)
+                             .first()
+                             .config(Config::with_contacts(&[Endpoint(1)]))
+                             .endpoint(Endpoint(0))
+                             .create()];
+    nodes[0].poll();
+    let config = Config::with_contacts(&[Endpoint(0)]);
+    for i in 1..(min_section_size + 1) {
+        nodes.push(TestNode::builder(&network)
+                       .config(config.clone())
+                       .endpoint(Endpoint(i))
+                       .create());
+        poll_and_resend(&mut nodes, &mut []);
+    }
+    let old_name = nodes[0].name();
+
+    // Let nodes join and leave again until the seed node has survived enough churn events.
+    let config = Config::with_contacts(&[Endpoint(1)]);
+    let mut churn_count = 0;
+    while nodes[0].name() == old_name {
+        assert!(churn_count <= RELOCATION_AGE, "The seed node was never relocated.");
+        if churn_count % 2 == 0 {
+            nodes.push(TestNode::builder(&network)
+                           .config(config.clone())
+                           .endpoint(Endpoint(min_section_size + 1 + churn_count as usize))
+                           .create());
+        } else {
+            let _ = nodes.pop();
+        }
+        poll_and_resend(&mut nodes, &mut []);
+        churn_count += 1;
+    }
+    verify_data_chains(&nodes);
+
+    // Its new section took over its age.
+    let new_name = nodes[0].name();
+    for node in &nodes {
+        let age = unwrap!(unwrap!(node.inner.data_chain())
+                              .member_ages()
+                              .into_iter()
+                              .find(|&(pub_id, _)| *pub_id.name() == new_name)
+                              .map(|(_, age)| age));
+        assert!(age >= RELOCATION_AGE,
+                "{} thinks the relocated node's age is {}.",
+                node.name(),
+                age);
+    }
+}
@@ -0,0 +1,529 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{TestClient, TestNode, create_connected_clients, create_connected_nodes,
+            gen_immutable_data, poll_and_resend, sort_nodes_by_distance_to};
+use maidsafe_utilities::serialisation::serialise;
+use rand::Rng;
+use routing::{Authority, Data, DataManager, EXPIRY_GRACE_PERIOD_SECS, Event, EventStream, FullId,
+              MemoryChunkStore, MessageId, Response, RoutingError, StructuredData, XorName,
+              Xorable, unix_time_secs};
+use routing::client_errors::{GetError, MutationError};
+use routing::dns::{DnsRecord, Service};
+use routing::mock_crust::Network;
+use routing::self_encryption::{self, ContentReader};
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+fn create_data_managers(count: usize) -> Vec<DataManager> {
+    (0..count)
+        .map(|_| DataManager::new(Box::new(MemoryChunkStore::new())))
+        .collect()
+}
+
+// Polls the network, passing all events raised by the nodes to their data managers, until no more
+// events are raised.
+fn poll_and_manage(nodes: &mut [TestNode],
+                   clients: &mut [TestClient],
+                   managers: &mut [DataManager]) {
+    poll_and_manage_events(nodes, clients, managers, true)
+}
+
+// Like `poll_and_manage`, but drops `Tick` events, so the data managers don't purge expired data.
+fn poll_without_ticks(nodes: &mut [TestNode],
+                      clients: &mut [TestClient],
+                      managers: &mut [DataManager]) {
+    poll_and_manage_events(nodes, clients, managers, false)
+}
+
+fn poll_and_manage_events(nodes: &mut [TestNode],
+                          clients: &mut [TestClient],
+                          managers: &mut [DataManager],
+                          handle_ticks: bool) {
+    loop {
+        poll_and_resend(nodes, clients);
+        let mut event_handled = false;
+        for (node, manager) in nodes.iter_mut().zip(managers.iter_mut()) {
+            while let Ok(event) = node.inner.try_next_ev() {
+                event_handled = true;
+                if let (&Event::Tick, false) = (&event, handle_ticks) {
+                    continue;
+                }
+                let _ = unwrap!(manager.handle_event(&mut node.inner, event));
+            }
+        }
+        if !event_handled {
+            return;
+        }
+    }
+}
+
+fn gen_structured_data<R: Rng>(full_id: &FullId, rng: &mut R, version: u64) -> StructuredData {
+    let owner_key = *full_id.public_id().signing_public_key();
+    let owners: BTreeSet<_> = Some(owner_key).into_iter().collect();
+    unwrap!(StructuredData::new(10000,
+                                rng.gen(),
+                                version,
+                                rng.gen_iter().take(10).collect(),
+                                owners))
+}
+
+fn successor(data: &StructuredData, full_id: &FullId) -> StructuredData {
+    let owner_key = *full_id.public_id().signing_public_key();
+    let mut successor = unwrap!(StructuredData::new(data.get_type_tag(),
+                                                    *data.name(),
+                                                    data.get_version() + 1,
+                                                    vec![1, 2, 3],
+                                                    data.get_owners().clone()));
+    let _ = unwrap!(successor.add_signature(&(owner_key,
+                                              full_id.signing_private_key().clone())));
+    successor
+}
+
+#[test]
+fn put_and_get() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+    let mut managers = create_data_managers(nodes.len());
+
+    let data = gen_immutable_data(&mut rng, 1024);
+    let client_manager = Authority::ClientManager(clients[0].name());
+    let message_id = MessageId::new();
+    unwrap!(clients[0]
+                .inner
+                .send_put_request(client_manager, data.clone(), message_id));
+    poll_and_manage(&mut nodes, &mut clients, &mut managers);
+    expect_any_event!(clients[0],
+                      Event::Response { response: Response::PutSuccess(_, id), .. }
+                      if id == message_id);
+
+    let message_id = MessageId::new();
+    unwrap!(clients[0]
+                .inner
+                .send_get_request(Authority::NaeManager(*data.name()),
+                                  data.identifier(),
+                                  message_id));
+    poll_and_manage(&mut nodes, &mut clients, &mut managers);
+    expect_any_event!(clients[0],
+                      Event::Response { response: Response::GetSuccess(ref got, id), .. }
+                      if *got == data && id == message_id);
+
+    let message_id = MessageId::new();
+    unwrap!(clients[0]
+                .inner
+                .send_get_account_info_request(client_manager, message_id));
+    poll_and_manage(&mut nodes, &mut clients, &mut managers);
+    expect_any_event!(clients[0],
+                      Event::Response {
+                          response: Response::GetAccountInfoSuccess { id, data_stored, .. }, ..
+                      } if id == message_id && data_stored == 1);
+}
+
+#[test]
+fn post_and_delete_structured_data() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+    let mut managers = create_data_managers(nodes.len());
+
+    let full_id = clients[0].full_id.clone();
+    let data = gen_structured_data(&full_id, &mut rng, 0);
+    let nae_manager = Authority::NaeManager(*data.name());
+    let message_id = MessageId::new();
+    unwrap!(clients[0]
+                .inner
+                .send_put_request(Authority::ClientManager(clients[0].name()),
+                                  Data::Structured(data.clone()),
+                                  message_id));
+    poll_and_manage(&mut nodes, &mut clients, &mut managers);
+    expect_next_event!(clients[0],
+                       Event::Response { response: Response::PutSuccess(..), .. });
+
+    // A valid successor is accepted.
+    let updated = successor(&data, &full_id);
+    let message_id = MessageId::new();
+    unwrap!(clients[0]
+                .inner
+                .send_post_request(nae_manager, Data::Structured(updated.clone()), message_id));
+    poll_and_manage(&mut nodes, &mut clients, &mut managers);
+    expect_any_event!(clients[0],
+                      Event::Response { response: Response::PostSuccess(_, id), .. }
+                      if id == message_id);
+
+    // Posting the same version again is rejected.
+    let message_id = MessageId::new();
+    unwrap!(clients[0]
+                .inner
+                .send_post_request(nae_manager, Data::Structured(updated.clone()), message_id));
+    poll_and_manage(&mut nodes, &mut clients, &mut managers);
+    expect_any_event!(clients[0],
+                      Event::Response { response: Response::PostFailure { id, .. }, .. }
+                      if id == message_id);
+
+    // After deleting, the data can't be retrieved anymore.
+    let message_id = MessageId::new();
+    unwrap!(clients[0]
+                .inner
+                .send_delete_request(nae_manager,
+                                     Data::Structured(successor(&updated, &full_id)),
+                                     message_id));
+    poll_and_manage(&mut nodes, &mut clients, &mut managers);
+    expect_any_event!(clients[0],
+                      Event::Response { response: Response::DeleteSuccess(_, id), .. }
+                      if id == message_id);
+
+    let message_id = MessageId::new();
+    unwrap!(clients[0]
+                .inner
+                .send_get_request(nae_manager, data.identifier(), message_id));
+    poll_and_manage(&mut nodes, &mut clients, &mut managers);
+    expect_any_event!(clients[0],
+                      Event::Response { response: Response::GetFailure { id, .. }, .. }
+                      if id == message_id);
+}
+
+#[test]
+fn expired_data() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+    let mut managers = create_data_managers(nodes.len());
+    let full_id = clients[0].full_id.clone();
+    let client_manager = Authority::ClientManager(clients[0].name());
+
+    // Data which has already expired is refused.
+    let mut data = gen_structured_data(&full_id, &mut rng, 0);
+    data.set_expiry(Some(unix_time_secs() - 1));
+    let message_id = MessageId::new();
+    unwrap!(clients[0]
+                .inner
+                .send_put_request(client_manager, Data::Structured(data), message_id));
+    poll_and_manage(&mut nodes, &mut clients, &mut managers);
+    let data_expired = unwrap!(serialise(&MutationError::DataExpired));
+    expect_any_event!(clients[0],
+                      Event::Response {
+                          response: Response::PutFailure { id, ref external_error_indicator, .. },
+                          ..
+                      } if id == message_id && *external_error_indicator == data_expired);
+
+    let mut data = gen_structured_data(&full_id, &mut rng, 0);
+    data.set_expiry(Some(unix_time_secs() + 60));
+    let data = Data::Structured(data);
+    let nae_manager = Authority::NaeManager(*data.name());
+    unwrap!(clients[0]
+                .inner
+                .send_put_request(client_manager, data.clone(), MessageId::new()));
+    poll_and_manage(&mut nodes, &mut clients, &mut managers);
+    expect_any_event!(clients[0],
+                      Event::Response { response: Response::PutSuccess(..), .. });
+
+    // Within the grace period after its expiry, the data is still served.
+    network.advance_time(Duration::from_secs(61));
+    poll_and_manage(&mut nodes, &mut clients, &mut managers);
+    while let Ok(_) = clients[0].inner.try_next_ev() {}
+    let message_id = MessageId::new();
+    unwrap!(clients[0]
+                .inner
+                .send_get_request(nae_manager, data.identifier(), message_id));
+    poll_and_manage(&mut nodes, &mut clients, &mut managers);
+    expect_any_event!(clients[0],
+                      Event::Response { response: Response::GetSuccess(ref got, id), .. }
+                      if id == message_id && *got == data);
+
+    // After that, and until it is purged, it is refused with a specific error.
+    network.advance_time(Duration::from_secs(EXPIRY_GRACE_PERIOD_SECS));
+    poll_without_ticks(&mut nodes, &mut clients, &mut managers);
+    while let Ok(_) = clients[0].inner.try_next_ev() {}
+    let data_expired = unwrap!(serialise(&GetError::DataExpired));
+    let message_id = MessageId::new();
+    unwrap!(clients[0]
+                .inner
+                .send_get_request(nae_manager, data.identifier(), message_id));
+    poll_without_ticks(&mut nodes, &mut clients, &mut managers);
+    expect_any_event!(clients[0],
+                      Event::Response {
+                          response: Response::GetFailure { id, ref external_error_indicator, .. },
+                          ..
+                      } if id == message_id && *external_error_indicator == data_expired);
+
+    let message_id = MessageId::new();
+    unwrap!(clients[0]
+                .inner
+                .send_get_batch_request(nae_manager, vec![data.identifier()], message_id));
+    poll_without_ticks(&mut nodes, &mut clients, &mut managers);
+    expect_any_event!(clients[0],
+                      Event::Response { response: Response::GetBatch { id, ref results }, .. }
+                      if id == message_id && results.len() == 1 &&
+                         results[0] == (data.identifier(), Err(data_expired.clone())));
+
+    // The next tick drops it.
+    for (node, manager) in nodes.iter_mut().zip(managers.iter_mut()) {
+        assert!(unwrap!(manager.handle_event(&mut node.inner, Event::Tick)).is_some());
+    }
+    let no_such_data = unwrap!(serialise(&GetError::NoSuchData));
+    let message_id = MessageId::new();
+    unwrap!(clients[0]
+                .inner
+                .send_get_request(nae_manager, data.identifier(), message_id));
+    poll_and_manage(&mut nodes, &mut clients, &mut managers);
+    expect_any_event!(clients[0],
+                      Event::Response {
+                          response: Response::GetFailure { id, ref external_error_indicator, .. },
+                          ..
+                      } if id == message_id && *external_error_indicator == no_such_data);
+}
+
+#[test]
+fn data_survives_churn() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, min_section_size + 6);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+    let proxy_name = nodes[0].name();
+
+    let data = gen_immutable_data(&mut rng, 1024);
+    sort_nodes_by_distance_to(&mut nodes, data.name());
+    let mut managers = create_data_managers(nodes.len());
+
+    unwrap!(clients[0]
+                .inner
+                .send_put_request(Authority::ClientManager(clients[0].name()),
+                                  data.clone(),
+                                  MessageId::new()));
+    poll_and_manage(&mut nodes, &mut clients, &mut managers);
+    expect_next_event!(clients[0],
+                       Event::Response { response: Response::PutSuccess(..), .. });
+
+    // Drop half of the data's original holders, one at a time. Without refreshing the data to the
+    // nodes that take their place, there would be no quorum left to respond to a `Get`.
+    for _ in 0..(min_section_size / 2) {
+        let index = unwrap!(nodes.iter().position(|node| node.name() != proxy_name));
+        drop(nodes.remove(index));
+        drop(managers.remove(index));
+        poll_and_manage(&mut nodes, &mut clients, &mut managers);
+    }
+
+    let message_id = MessageId::new();
+    unwrap!(clients[0]
+                .inner
+                .send_get_request(Authority::NaeManager(*data.name()),
+                                  data.identifier(),
+                                  message_id));
+    poll_and_manage(&mut nodes, &mut clients, &mut managers);
+    expect_any_event!(clients[0],
+                      Event::Response { response: Response::GetSuccess(ref got, id), .. }
+                      if *got == data && id == message_id);
+}
+
+#[test]
+fn self_encrypted_content() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+    let mut managers = create_data_managers(nodes.len());
+
+    let content: Vec<u8> = rng.gen_iter().take(4 * self_encryption::MIN_CHUNK_SIZE).collect();
+    let (data_map, chunks) = self_encryption::encrypt(&content);
+    let client_manager = Authority::ClientManager(clients[0].name());
+    for chunk in chunks {
+        unwrap!(clients[0]
+                    .inner
+                    .send_put_request(client_manager, Data::Immutable(chunk), MessageId::new()));
+        poll_and_manage(&mut nodes, &mut clients, &mut managers);
+        expect_next_event!(clients[0],
+                           Event::Response { response: Response::PutSuccess(..), .. });
+    }
+
+    let mut reader = unwrap!(ContentReader::new(&clients[0].inner, data_map));
+    poll_and_manage(&mut nodes, &mut clients, &mut managers);
+    let mut result = None;
+    while let Ok(event) = clients[0].inner.try_next_ev() {
+        if let Event::Response { ref response, .. } = event {
+            result = result.or_else(|| reader.handle_response(response));
+        }
+    }
+    assert_eq!(unwrap!(unwrap!(result)), content);
+}
+
+#[test]
+fn put_and_get_batch() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+    let mut managers = create_data_managers(nodes.len());
+
+    let data: Vec<_> = (0..3).map(|_| gen_immutable_data(&mut rng, 1024)).collect();
+    let message_id = MessageId::new();
+    unwrap!(clients[0]
+                .inner
+                .send_put_batch_request(Authority::ClientManager(clients[0].name()),
+                                        data.clone(),
+                                        message_id));
+    poll_and_manage(&mut nodes, &mut clients, &mut managers);
+    expect_any_event!(clients[0],
+                      Event::Response { response: Response::PutBatch { id, ref results }, .. }
+                      if id == message_id &&
+                         results.iter().map(|&(data_id, _)| data_id).eq(
+                             data.iter().map(Data::identifier)) &&
+                         results.iter().all(|&(_, ref result)| result.is_ok()));
+
+    // Each item gets its own result, including ones the `NaeManager` doesn't hold or manage.
+    let managers_of_data = nae_managers(&nodes, data[0].name(), min_section_size);
+    let (mut missing, mut foreign) = (None, None);
+    while missing.is_none() || foreign.is_none() {
+        let item = gen_immutable_data(&mut rng, 1024);
+        if nae_managers(&nodes, item.name(), min_section_size) == managers_of_data {
+            missing = Some(item);
+        } else {
+            foreign = Some(item);
+        }
+    }
+    let (missing, foreign) = (unwrap!(missing), unwrap!(foreign));
+    let no_such_data = unwrap!(serialise(&GetError::NoSuchData));
+    let wrong_authority = unwrap!(serialise(&GetError::WrongAuthority));
+    let message_id = MessageId::new();
+    unwrap!(clients[0]
+                .inner
+                .send_get_batch_request(Authority::NaeManager(*data[0].name()),
+                                        vec![data[0].identifier(),
+                                             missing.identifier(),
+                                             foreign.identifier()],
+                                        message_id));
+    poll_and_manage(&mut nodes, &mut clients, &mut managers);
+    expect_any_event!(clients[0],
+                      Event::Response { response: Response::GetBatch { id, ref results }, .. }
+                      if id == message_id && results.len() == 3 &&
+                         results[0] == (data[0].identifier(), Ok(data[0].clone())) &&
+                         results[1] == (missing.identifier(), Err(no_such_data.clone())) &&
+                         results[2] == (foreign.identifier(), Err(wrong_authority.clone())));
+}
+
+// Returns the names of the `NaeManager` group for `name`, assuming all nodes are in one section.
+fn nae_managers(nodes: &[TestNode], name: &XorName, group_size: usize) -> BTreeSet<XorName> {
+    let mut names: Vec<_> = nodes.iter().map(TestNode::name).collect();
+    names.sort_by(|lhs, rhs| name.cmp_distance(lhs, rhs));
+    names.into_iter().take(group_size).collect()
+}
+
+// Resolves `domain` via the first client and returns the `StructuredData` holding its record.
+fn resolve_domain(nodes: &mut [TestNode],
+                  clients: &mut [TestClient],
+                  managers: &mut [DataManager],
+                  domain: &str)
+                  -> StructuredData {
+    let message_id = MessageId::new();
+    unwrap!(clients[0].inner.send_dns_resolve_request(domain, message_id));
+    poll_and_manage(nodes, clients, managers);
+    let mut result = None;
+    while let Ok(event) = clients[0].inner.try_next_ev() {
+        if let Event::Response {
+                   response: Response::GetSuccess(Data::Structured(data), id), ..
+               } = event {
+            if id == message_id {
+                result = Some(data);
+            }
+        }
+    }
+    unwrap!(result, "Domain {} not resolved.", domain)
+}
+
+#[test]
+fn dns_register_update_and_resolve() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+    let mut managers = create_data_managers(nodes.len());
+
+    let keys = (*clients[0].full_id.public_id().signing_public_key(),
+                clients[0].full_id.signing_private_key().clone());
+    let content = gen_immutable_data(&mut rng, 1024);
+    let service = Service {
+        data_id: content.identifier(),
+        public_key: keys.0,
+    };
+    let services = Some(("www".to_owned(), service)).into_iter().collect();
+    let record = unwrap!(DnsRecord::new("example.com", services, &keys));
+    unwrap!(clients[0].inner.send_dns_register_request(&record, &keys, MessageId::new()));
+    poll_and_manage(&mut nodes, &mut clients, &mut managers);
+    expect_next_event!(clients[0],
+                       Event::Response { response: Response::PutSuccess(..), .. });
+
+    let data = resolve_domain(&mut nodes, &mut clients, &mut managers, "Example.com.");
+    assert_eq!(unwrap!(DnsRecord::from_structured_data(&data)), record);
+
+    // Only the owner can update the record.
+    let other_id = FullId::new();
+    let other_keys = (*other_id.public_id().signing_public_key(),
+                      other_id.signing_private_key().clone());
+    let forged = unwrap!(DnsRecord::new("example.com", BTreeMap::new(), &other_keys));
+    match clients[0].inner.send_dns_update_request(&data, &forged, &other_keys, MessageId::new()) {
+        Err(RoutingError::InvalidOwners) => (),
+        result => panic!("Unexpected result: {:?}", result),
+    }
+
+    // The network rejects it as well, if the client-side check is bypassed.
+    let forged_data = unwrap!(forged.to_structured_data(1, &other_keys));
+    let message_id = MessageId::new();
+    unwrap!(clients[0]
+                .inner
+                .send_post_request(Authority::NaeManager(*data.name()),
+                                   Data::Structured(forged_data),
+                                   message_id));
+    poll_and_manage(&mut nodes, &mut clients, &mut managers);
+    expect_any_event!(clients[0],
+                      Event::Response { response: Response::PostFailure { id, .. }, .. }
+                      if id == message_id);
+
+    let updated = unwrap!(DnsRecord::new("example.com", BTreeMap::new(), &keys));
+    unwrap!(clients[0].inner.send_dns_update_request(&data, &updated, &keys, MessageId::new()));
+    poll_and_manage(&mut nodes, &mut clients, &mut managers);
+    expect_any_event!(clients[0],
+                      Event::Response { response: Response::PostSuccess(..), .. });
+
+    let data = resolve_domain(&mut nodes, &mut clients, &mut managers, "example.com");
+    assert_eq!(data.get_version(), 1);
+    assert_eq!(unwrap!(DnsRecord::from_structured_data(&data)), updated);
+
+    // The owner can transfer the domain, after which the new owner can update it.
+    let transferred = unwrap!(DnsRecord::new("example.com", BTreeMap::new(), &other_keys));
+    unwrap!(clients[0].inner.send_dns_update_request(&data, &transferred, &keys, MessageId::new()));
+    poll_and_manage(&mut nodes, &mut clients, &mut managers);
+    expect_any_event!(clients[0],
+                      Event::Response { response: Response::PostSuccess(..), .. });
+
+    let data = resolve_domain(&mut nodes, &mut clients, &mut managers, "example.com");
+    assert_eq!(*unwrap!(DnsRecord::from_structured_data(&data)).owner(),
+               other_keys.0);
+    match clients[0].inner.send_dns_update_request(&data, &updated, &keys, MessageId::new()) {
+        Err(RoutingError::InvalidOwners) => (),
+        result => panic!("Unexpected result: {:?}", result),
+    }
+}
@@ -15,9 +15,11 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software.
 
-use super::{TestNode, create_connected_nodes, poll_all, verify_invariant_for_all_nodes};
-use routing::{Event, EventStream};
-use routing::mock_crust::Network;
+use super::{TestClient, TestNode, create_connected_nodes, gen_immutable_data, poll_all,
+            verify_invariant_for_all_nodes};
+use routing::{Authority, Event, EventStream, MessageId, Request, Response, XorName};
+use routing::mock_crust::{Config, Network};
+use std::collections::BTreeSet;
 
 // Drop node at index and verify its own section receives NodeLost.
 fn drop_node(nodes: &mut Vec<TestNode>, index: usize) {
@@ -34,7 +36,11 @@ fn drop_node(nodes: &mut Vec<TestNode>, index: usize) {
             .filter(|n| close_names.contains(&n.name())) {
         loop {
             match node.try_next_ev() {
-                Ok(Event::NodeLost(lost_name, _)) if lost_name == name => break,
+                Ok(Event::NodeLost(lost_name, churn)) if lost_name == name => {
+                    assert!(unwrap!(churn.old_table()).has(&name));
+                    assert!(!churn.new_table().has(&name));
+                    break;
+                }
                 Ok(_) => (),
                 _ => panic!("Event::NodeLost({:?}) not received", name),
             }
@@ -69,3 +75,104 @@ fn node_restart() {
 
     expect_next_event!(nodes[0], Event::RestartRequired);
 }
+
+// Sends a put request from the first client and returns the name of the proxy it was routed
+// through.
+fn put_via_proxy(network: &Network, nodes: &mut [TestNode], clients: &mut [TestClient]) -> XorName {
+    let mut rng = network.new_rng();
+    let dst = Authority::ClientManager(clients[0].name());
+    let data = gen_immutable_data(&mut rng, 1024);
+    let message_id = MessageId::new();
+    assert!(clients[0]
+                .inner
+                .send_put_request(dst, data, message_id)
+                .is_ok());
+
+    let _ = poll_all(nodes, clients);
+
+    let mut proxy_names = Vec::new();
+    for node in nodes.iter_mut().filter(|n| n.is_recipient(&dst)) {
+        loop {
+            match node.try_next_ev() {
+                Ok(Event::Request {
+                       request: Request::Put(_, id),
+                       src: Authority::Client { proxy_node_name, .. },
+                       ..
+                   }) if id == message_id => {
+                    proxy_names.push(proxy_node_name);
+                    break;
+                }
+                Ok(_) => (),
+                _ => panic!("Event::Request not received"),
+            }
+        }
+    }
+
+    let proxy_name = unwrap!(proxy_names.first().cloned());
+    assert!(proxy_names.iter().all(|name| *name == proxy_name));
+    proxy_name
+}
+
+#[test]
+fn client_survives_proxy_drop() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut nodes = create_connected_nodes(&network, min_section_size + 2);
+
+    let contacts = nodes[..3]
+        .iter()
+        .map(|node| node.handle.endpoint())
+        .collect::<Vec<_>>();
+    let mut clients = vec![TestClient::new(&network, Some(Config::with_contacts(&contacts)), None)];
+    let _ = poll_all(&mut nodes, &mut clients);
+    expect_next_event!(clients[0], Event::Connected);
+
+    let proxy_name = put_via_proxy(&network, &mut nodes, &mut clients);
+
+    // Send a request, and drop the proxy before it can be delivered.
+    let mut rng = network.new_rng();
+    let data = gen_immutable_data(&mut rng, 1024);
+    let dst = Authority::NaeManager(*data.name());
+    let message_id = MessageId::new();
+    unwrap!(clients[0]
+                .inner
+                .send_get_request(dst, data.identifier(), message_id));
+    let index = unwrap!(nodes.iter().position(|node| node.name() == proxy_name));
+    drop(nodes.remove(index));
+    let _ = poll_all(&mut nodes, &mut clients);
+
+    // The client fails over to one of its secondary proxies instead of terminating, and resends
+    // the request through it.
+    let mut new_proxy_names = BTreeSet::new();
+    for node in nodes.iter_mut().filter(|n| n.is_recipient(&dst)) {
+        loop {
+            match node.try_next_ev() {
+                Ok(Event::Request {
+                       request: Request::Get(_, id),
+                       src,
+                       dst,
+                   }) if id == message_id => {
+                    if let Authority::Client { proxy_node_name, .. } = src {
+                        let _ = new_proxy_names.insert(proxy_node_name);
+                    }
+                    unwrap!(node.inner.send_get_success(dst, src, data.clone(), id));
+                    break;
+                }
+                Ok(_) => (),
+                _ => panic!("Event::Request not received"),
+            }
+        }
+    }
+    assert_eq!(new_proxy_names.len(), 1);
+    assert!(!new_proxy_names.contains(&proxy_name));
+
+    // The response arrives through the new proxy.
+    let _ = poll_all(&mut nodes, &mut clients);
+    expect_any_event!(clients[0],
+                      Event::Response { response: Response::GetSuccess(ref got, id), .. }
+                      if *got == data && id == message_id);
+
+    // Further requests are routed through the new proxy as well.
+    let new_proxy_name = put_via_proxy(&network, &mut nodes, &mut clients);
+    assert!(new_proxy_names.contains(&new_proxy_name));
+}
@@ -0,0 +1,116 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{TestNode, create_connected_nodes, create_connected_nodes_until_split,
+            gen_immutable_data, poll_all, poll_and_resend, sort_nodes_by_distance_to};
+use routing::{Authority, Event, EventStream, MessageId, QUORUM_DENOMINATOR, QUORUM_NUMERATOR,
+              Response, XorName};
+use routing::mock_crust::{Config, Network};
+use std::collections::BTreeSet;
+
+// Returns the names of the elders of `node`'s section, according to its data chain.
+fn section_elders(node: &TestNode, min_section_size: usize) -> BTreeSet<XorName> {
+    unwrap!(node.inner.data_chain())
+        .elders(min_section_size)
+        .iter()
+        .map(|pub_id| *pub_id.name())
+        .collect()
+}
+
+#[test]
+fn only_elders_sign_section_messages() {
+    let min_section_size = 5;
+    let network = Network::new(min_section_size, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, min_section_size + 3);
+    poll_and_resend(&mut nodes, &mut []);
+
+    // All members agree on the elders.
+    let elders = section_elders(&nodes[0], min_section_size);
+    assert_eq!(elders.len(), min_section_size);
+    for node in nodes.iter() {
+        assert_eq!(section_elders(node, min_section_size), elders);
+    }
+
+    // Send from the section named after an elder, so that it collects the signatures.
+    let src = Authority::Section(*unwrap!(elders.iter().next()));
+    sort_nodes_by_distance_to(&mut nodes, &src.name());
+    let (elder_indices, adult_indices): (Vec<usize>, Vec<usize>) =
+        (0..nodes.len()).partition(|&index| elders.contains(&nodes[index].name()));
+    let dst_index = adult_indices[0];
+    let dst = Authority::ManagedNode(nodes[dst_index].name());
+    let data = gen_immutable_data(&mut rng, 8);
+    let message_id = MessageId::new();
+    let send = |node: &mut TestNode| {
+        assert!(node.inner
+                    .send_get_success(src, dst, data.clone(), message_id)
+                    .is_ok());
+    };
+    // The smallest number such that
+    // `quorum * QUORUM_DENOMINATOR > min_section_size * QUORUM_NUMERATOR`:
+    let quorum = 1 + (min_section_size * QUORUM_NUMERATOR) / QUORUM_DENOMINATOR;
+
+    // The adults don't sign, so their messages never accumulate.
+    for &index in &adult_indices {
+        send(&mut nodes[index]);
+    }
+    let _ = poll_all(&mut nodes, &mut []);
+    expect_no_event!(nodes[dst_index]);
+
+    // A quorum of the elders is enough, though.
+    for &index in &elder_indices[..(quorum - 1)] {
+        send(&mut nodes[index]);
+    }
+    let _ = poll_all(&mut nodes, &mut []);
+    expect_no_event!(nodes[dst_index]);
+    send(&mut nodes[elder_indices[quorum - 1]]);
+    let _ = poll_all(&mut nodes, &mut []);
+    expect_next_event!(nodes[dst_index],
+                       Event::Response { response: Response::GetSuccess(..), .. });
+}
+
+#[test]
+fn messages_to_neighbours_are_routed_via_their_elders() {
+    let min_section_size = 5;
+    let network = Network::new(min_section_size, None);
+    let mut nodes = create_connected_nodes_until_split(&network, vec![1, 1], false);
+
+    // A node joins one of the sections, which then announces its elders to the other one.
+    let config = Config::with_contacts(&[nodes[0].handle.endpoint()]);
+    nodes.push(TestNode::builder(&network).config(config).create());
+    poll_and_resend(&mut nodes, &mut []);
+    let new_name = unwrap!(nodes.last()).name();
+    let elders = section_elders(unwrap!(nodes.last()), min_section_size);
+    assert!(!elders.contains(&new_name));
+
+    let dst = Authority::Section(new_name);
+    for node in nodes.iter() {
+        let in_dst_section = unwrap!(node.inner.routing_table())
+            .our_prefix()
+            .matches(&new_name);
+        if in_dst_section {
+            // Messages to our own section are not routed via the elders.
+            assert_eq!(node.inner.elder_target(dst, 0), None);
+        } else {
+            // Different routes lead to different elders.
+            let targets = (0..min_section_size)
+                .map(|route| unwrap!(node.inner.elder_target(dst, route as u8)))
+                .collect::<BTreeSet<_>>();
+            assert_eq!(targets, elders);
+        }
+    }
+}
@@ -30,8 +30,10 @@ fn merge(prefix_lengths: Vec<usize>) {
     verify_invariant_for_all_nodes(&mut nodes);
 
     // Drop nodes from a section with the shortest prefix until we get a merge event for the empty
-    // prefix.
+    // prefix. Each merge also removes sections from the remaining nodes' view of the network, so
+    // record which nodes were told about removed remote sections.
     let mut min_prefix = *nodes[0].routing_table().our_prefix();
+    let mut saw_removed_sections = BTreeSet::new();
     loop {
         rng.shuffle(&mut nodes);
         let mut index = nodes.len();
@@ -56,17 +58,27 @@ fn merge(prefix_lengths: Vec<usize>) {
                     Event::NodeAdded(..) |
                     Event::NodeLost(..) |
                     Event::Tick => (),
-                    Event::SectionMerge(prefix) => {
+                    Event::SectionMerge(prefix, _) => {
                         if prefix.bit_count() == 0 {
                             merge_events_missing -= 1;
                         }
                     }
+                    Event::RemoteSectionsChanged { removed, .. } => {
+                        if !removed.is_empty() {
+                            let _ = saw_removed_sections.insert(node.name());
+                        }
+                    }
                     event => panic!("{} got unexpected event: {:?}", node.name(), event),
                 }
             }
         }
         verify_invariant_for_all_nodes(&mut nodes);
         if merge_events_missing == 0 {
+            for node in nodes.iter() {
+                assert!(saw_removed_sections.contains(&node.name()),
+                        "{} got no RemoteSectionsChanged event.",
+                        node.name());
+            }
             return;
         }
     }
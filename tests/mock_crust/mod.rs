@@ -16,17 +16,25 @@
 // relating to use of the SAFE Network Software.
 
 mod accumulate;
+mod agreement;
 mod cache;
 mod churn;
+mod data_chain;
+mod data_manager;
 mod drop;
+mod elders;
 mod merge;
+mod relocation;
 mod requests;
+mod section_info;
+mod timeout;
 mod tunnel;
 mod utils;
 
 pub use self::utils::{Nodes, TestClient, TestNode, add_connected_nodes_until_split,
                       create_connected_clients, create_connected_nodes,
-                      create_connected_nodes_until_split, gen_bytes, gen_immutable_data,
+                      create_connected_nodes_until_split,
+                      create_connected_nodes_with_encrypted_links, gen_bytes, gen_immutable_data,
                       gen_range, gen_range_except, poll_all, poll_and_resend,
                       remove_nodes_which_failed_to_connect, sort_nodes_by_distance_to,
                       verify_invariant_for_all_nodes};
@@ -0,0 +1,65 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{TestNode, poll_and_resend, verify_invariant_for_all_nodes};
+use routing::{BalancingRelocation, Event, EventStream, Prefix, XorName};
+use routing::mock_crust::{Config, Endpoint, Network};
+use std::collections::BTreeMap;
+
+// Returns the prefix of the section `BalancingRelocation` should pick, based on `node`'s view of
+// the network.
+fn balancing_target(node: &TestNode) -> Prefix<XorName> {
+    let sections: BTreeMap<_, _> = node.routing_table()
+        .all_sections_iter()
+        .map(|(prefix, (_, section))| (prefix, section.len()))
+        .collect();
+    *unwrap!(sections.iter().min_by_key(|&(prefix, &len)| (prefix.bit_count(), len))).0
+}
+
+#[test]
+fn balancing_relocation() {
+    let min_section_size = 5;
+    let network = Network::new(min_section_size, None);
+    let mut nodes = vec![TestNode::builder(&network)
+                             .first()
+                             .endpoint(Endpoint(0))
+                             .relocation_strategy(Box::new(BalancingRelocation))
+                             .create()];
+    nodes[0].poll();
+    let config = Config::with_contacts(&[nodes[0].handle.endpoint()]);
+
+    // Every node joins, i.e. the members of the relocating section agree on the destination, and
+    // ends up in the least developed section, also after the network has split.
+    for i in 1..(4 * min_section_size) {
+        let target = balancing_target(&nodes[0]);
+        nodes.push(TestNode::builder(&network)
+                       .config(config.clone())
+                       .endpoint(Endpoint(i))
+                       .relocation_strategy(Box::new(BalancingRelocation))
+                       .create());
+        poll_and_resend(&mut nodes, &mut []);
+        verify_invariant_for_all_nodes(&mut nodes);
+
+        let node = unwrap!(nodes.last_mut());
+        expect_any_event!(node, Event::Connected);
+        assert!(target.matches(&node.name()),
+                "{} joined outside of {:?}.",
+                node.name(),
+                target);
+    }
+    assert!(nodes.iter().any(|node| node.routing_table().our_prefix().bit_count() > 0));
+}
@@ -15,11 +15,11 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software.
 
-use super::{create_connected_clients, create_connected_nodes, gen_bytes, gen_immutable_data,
-            poll_all};
-use routing::{Authority, Data, DataIdentifier, Event, EventStream, ImmutableData, MessageId,
-              Request, Response};
-use routing::mock_crust::Network;
+use super::{TestClient, TestNode, create_connected_clients, create_connected_nodes,
+            create_connected_nodes_with_encrypted_links, gen_bytes, gen_immutable_data, poll_all};
+use routing::{Authority, Data, DataIdentifier, Event, EventStream, FullId, ImmutableData,
+              KeyRotation, MessageId, Request, Response};
+use routing::mock_crust::{Config, Network};
 
 #[test]
 fn successful_put_request() {
@@ -60,6 +60,44 @@ fn successful_put_request() {
     assert!(2 * request_received_count > min_section_size);
 }
 
+#[test]
+fn successful_put_request_over_encrypted_links() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes_with_encrypted_links(&network, min_section_size + 1);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+
+    let dst = Authority::ClientManager(clients[0].name());
+    let data = gen_immutable_data(&mut rng, 1024);
+    let message_id = MessageId::new();
+
+    assert!(clients[0]
+                .inner
+                .send_put_request(dst, data.clone(), message_id)
+                .is_ok());
+
+    let _ = poll_all(&mut nodes, &mut clients);
+
+    let mut request_received_count = 0;
+    for node in nodes.iter_mut().filter(|n| n.is_recipient(&dst)) {
+        loop {
+            match node.try_next_ev() {
+                Ok(Event::Request { request: Request::Put(ref immutable, ref id), .. }) => {
+                    request_received_count += 1;
+                    if data == *immutable && message_id == *id {
+                        break;
+                    }
+                }
+                Ok(_) => (),
+                _ => panic!("Event::Request not received"),
+            }
+        }
+    }
+
+    assert!(2 * request_received_count > min_section_size);
+}
+
 #[test]
 fn successful_get_request() {
     let min_section_size = 8;
@@ -131,6 +169,112 @@ fn successful_get_request() {
     assert_eq!(response_received_count, 1);
 }
 
+// Creates a client which verifies responses. It bootstraps off three nodes, so that it has
+// several proxies to cross-check the members of the responding sections with.
+fn create_verifying_client(network: &Network, nodes: &mut [TestNode]) -> TestClient {
+    let contacts = nodes[..3]
+        .iter()
+        .map(|node| node.handle.endpoint())
+        .collect::<Vec<_>>();
+    let config = Config::with_contacts(&contacts);
+    let mut clients = vec![TestClient::verifying(network, Some(config), None)];
+    let _ = poll_all(nodes, &mut clients);
+    expect_next_event!(clients[0], Event::Connected);
+    unwrap!(clients.pop())
+}
+
+// Sends a `Get` request for `data` from `clients[0]` and lets the data's managers respond.
+fn get_from_managers(nodes: &mut [TestNode], clients: &mut [TestClient], data: &Data) -> MessageId {
+    let dst = Authority::NaeManager(*data.name());
+    let message_id = MessageId::new();
+    unwrap!(clients[0]
+                .inner
+                .send_get_request(dst, data.identifier(), message_id));
+    let _ = poll_all(nodes, clients);
+
+    for node in nodes.iter_mut().filter(|n| n.is_recipient(&dst)) {
+        loop {
+            match node.try_next_ev() {
+                Ok(Event::Request {
+                       request: Request::Get(_, id),
+                       src,
+                       dst,
+                   }) if id == message_id => {
+                    unwrap!(node.inner.send_get_success(dst, src, data.clone(), id));
+                    break;
+                }
+                Ok(_) => (),
+                _ => panic!("Event::Request not received"),
+            }
+        }
+    }
+
+    // The client needs to fetch the section's members before it accepts the response.
+    let _ = poll_all(nodes, clients);
+    message_id
+}
+
+#[test]
+fn verified_get_request() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+    let mut clients = vec![create_verifying_client(&network, &mut nodes)];
+
+    let data = gen_immutable_data(&mut rng, 1024);
+    let message_id = get_from_managers(&mut nodes, &mut clients, &data);
+
+    let mut response_received_count = 0;
+    while let Ok(event) = clients[0].inner.try_next_ev() {
+        match event {
+            Event::Response { response: Response::GetSuccess(ref immutable, ref id), .. } => {
+                assert_eq!(data, *immutable);
+                assert_eq!(message_id, *id);
+                response_received_count += 1;
+            }
+            Event::ResponseVerificationFailed { .. } => panic!("Failed to verify response."),
+            Event::SectionInfo { .. } => panic!("Unexpected Event::SectionInfo."),
+            _ => (),
+        }
+    }
+    assert_eq!(response_received_count, 1);
+}
+
+#[test]
+fn under_signed_get_response() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+    let mut clients = vec![create_verifying_client(&network, &mut nodes)];
+
+    // The data's managers claim that their group only has three members, so that two of their
+    // signatures pass as a quorum, and every node relays the response.
+    for node in nodes.iter_mut() {
+        node.inner.set_claimed_group_size(3);
+    }
+
+    let data = gen_immutable_data(&mut rng, 1024);
+    let message_id = get_from_managers(&mut nodes, &mut clients, &data);
+
+    let mut failed_count = 0;
+    while let Ok(event) = clients[0].inner.try_next_ev() {
+        match event {
+            Event::ResponseVerificationFailed {
+                response: Response::GetSuccess(ref immutable, ref id), ..
+            } => {
+                assert_eq!(data, *immutable);
+                assert_eq!(message_id, *id);
+                failed_count += 1;
+            }
+            Event::Response { .. } => panic!("Accepted an under-signed response."),
+            _ => (),
+        }
+    }
+    assert_eq!(failed_count, 1);
+}
+
 #[test]
 fn failed_get_request() {
     let min_section_size = 8;
@@ -201,6 +345,78 @@ fn failed_get_request() {
     assert_eq!(response_received_count, 1);
 }
 
+#[test]
+fn custom_request_and_response() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+
+    let tag = 42;
+    let dst = Authority::ClientManager(clients[0].name());
+    let message_id = MessageId::new();
+
+    assert!(clients[0]
+                .inner
+                .send_custom_request(dst, tag, b"query".to_vec(), false, message_id)
+                .is_ok());
+
+    let _ = poll_all(&mut nodes, &mut clients);
+
+    let mut request_received_count = 0;
+    for node in nodes.iter_mut().filter(|n| n.is_recipient(&dst)) {
+        loop {
+            match node.try_next_ev() {
+                Ok(Event::Request {
+                       request: Request::Custom {
+                           tag: received_tag,
+                           ref payload,
+                           id,
+                           ..
+                       },
+                       src,
+                       dst,
+                   }) if id == message_id => {
+                    request_received_count += 1;
+                    assert_eq!(received_tag, tag);
+                    assert_eq!(*payload, b"query".to_vec());
+                    let payload = b"answer".to_vec();
+                    unwrap!(node.inner
+                                .send_custom_response(dst, src, tag, payload, false, id));
+                    break;
+                }
+                Ok(_) => (),
+                _ => panic!("Event::Request not received"),
+            }
+        }
+    }
+
+    assert!(2 * request_received_count > min_section_size);
+
+    let _ = poll_all(&mut nodes, &mut clients);
+
+    // The responses from the section accumulate, so the client receives exactly one.
+    let mut response_received_count = 0;
+    while let Ok(event) = clients[0].inner.try_next_ev() {
+        if let Event::Response {
+                   response: Response::Custom {
+                       tag: received_tag,
+                       ref payload,
+                       id,
+                       ..
+                   },
+                   ..
+               } = event {
+            assert_eq!(received_tag, tag);
+            assert_eq!(*payload, b"answer".to_vec());
+            assert_eq!(id, message_id);
+            response_received_count += 1;
+        }
+    }
+
+    assert_eq!(response_received_count, 1);
+}
+
 #[test]
 fn disconnect_on_get_request() {
     let min_section_size = 8;
@@ -268,3 +484,45 @@ fn disconnect_on_get_request() {
         }
     }
 }
+
+#[test]
+fn key_rotation_request() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+
+    let new_id = FullId::new();
+    let rotation = unwrap!(KeyRotation::new(&clients[0].full_id, &new_id));
+    let dst = Authority::ClientManager(rotation.old_name());
+    let message_id = MessageId::new();
+    assert!(clients[0]
+                .inner
+                .send_rotate_key_request(rotation.clone(), message_id)
+                .is_ok());
+
+    // A rotation of someone else's key must be dropped.
+    let forged_rotation = unwrap!(KeyRotation::new(&FullId::new(), &new_id));
+    let forged_message_id = MessageId::new();
+    assert!(clients[0]
+                .inner
+                .send_rotate_key_request(forged_rotation, forged_message_id)
+                .is_ok());
+
+    let _ = poll_all(&mut nodes, &mut clients);
+
+    let mut request_received_count = 0;
+    for node in &mut nodes {
+        let is_recipient = node.is_recipient(&dst);
+        while let Ok(event) = node.try_next_ev() {
+            if let Event::Request { request: Request::RotateKey(ref received, id), .. } = event {
+                assert_ne!(id, forged_message_id);
+                assert!(is_recipient);
+                assert_eq!(*received, rotation);
+                request_received_count += 1;
+            }
+        }
+    }
+
+    assert!(2 * request_received_count > min_section_size);
+}
@@ -0,0 +1,95 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{TestNode, create_connected_clients, create_connected_nodes_until_split, poll_all};
+use rand::Rng;
+use routing::{Event, EventStream, MessageId, XorName};
+use routing::mock_crust::Network;
+use std::collections::BTreeSet;
+
+// Drains the events of `stream` and returns the `SectionInfo` ones.
+fn section_info_events<T: EventStream<Item = Event>>(stream: &mut T) -> Vec<Event> {
+    let mut events = vec![];
+    while let Ok(event) = stream.try_next_ev() {
+        if let Event::SectionInfo { .. } = event {
+            events.push(event);
+        }
+    }
+    events
+}
+
+// Checks that `events` consists of a single `SectionInfo` with the given message ID, describing
+// the section of `nodes` which is responsible for `name`.
+fn verify_section_info(events: Vec<Event>, name: &XorName, msg_id: MessageId, nodes: &[TestNode]) {
+    assert_eq!(events.len(), 1, "Expected one Event::SectionInfo, got {:?}", events);
+    match events[0] {
+        Event::SectionInfo {
+            ref prefix,
+            ref members,
+            ref message_id,
+            ..
+        } => {
+            assert_eq!(*message_id, msg_id);
+            assert!(prefix.matches(name));
+            let expected: BTreeSet<XorName> = nodes
+                .iter()
+                .map(TestNode::name)
+                .filter(|node_name| prefix.matches(node_name))
+                .collect();
+            let actual: BTreeSet<XorName> = members.iter().map(|pub_id| *pub_id.name()).collect();
+            assert_eq!(actual, expected);
+        }
+        ref event => panic!("Expected Event::SectionInfo, got {:?}", event),
+    }
+}
+
+#[test]
+fn client_section_info() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes_until_split(&network, vec![1, 1], false);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+
+    for _ in 0..4 {
+        let name: XorName = rng.gen();
+        let message_id = MessageId::new();
+        unwrap!(clients[0].inner.send_section_info_request(name, message_id));
+        let _ = poll_all(&mut nodes, &mut clients);
+
+        let events = section_info_events(&mut clients[0]);
+        verify_section_info(events, &name, message_id, &nodes);
+    }
+}
+
+#[test]
+fn node_section_info() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes_until_split(&network, vec![1, 1], false);
+
+    for _ in 0..4 {
+        let name: XorName = rng.gen();
+        let message_id = MessageId::new();
+        unwrap!(nodes[0].inner.send_section_info_request(name, message_id));
+        let _ = poll_all(&mut nodes, &mut []);
+
+        let events = section_info_events(&mut nodes[0]);
+        verify_section_info(events, &name, message_id, &nodes);
+    }
+}
@@ -0,0 +1,154 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{TestClient, TestNode, create_connected_clients, create_connected_nodes,
+            gen_immutable_data, poll_all, poll_and_resend};
+use routing::{Authority, Event, EventStream, MessageId};
+use routing::mock_crust::{Config, Network};
+use std::time::Duration;
+
+// Time after which a bootstrapping client gives up on an unresponsive proxy.
+const BOOTSTRAP_TIMEOUT_SECS: u64 = 20;
+// Time after which an unacknowledged message is resent via the next route.
+const ACK_TIMEOUT_SECS: u64 = 20;
+// Time a section gives a candidate to prove its resources.
+const RESOURCE_PROOF_DURATION_SECS: u64 = 300;
+// Time after which incomplete signature accumulations are discarded.
+const ACCUMULATION_TIMEOUT_SECS: u64 = 30;
+// Time a relocated node waits for its approval before giving up.
+const APPROVAL_TIMEOUT_SECS: u64 = RESOURCE_PROOF_DURATION_SECS + ACCUMULATION_TIMEOUT_SECS +
+                                   4 * ACK_TIMEOUT_SECS;
+
+// Polls the nodes until the last one has been relocated and starts the approval process, and then
+// stops, so the last node makes no further progress.
+fn poll_until_relocated(nodes: &mut [TestNode]) {
+    let new_index = nodes.len() - 1;
+    while !nodes[new_index].inner.is_node() {
+        assert!(nodes.iter_mut().any(|node| node.poll()),
+                "The new node has not been relocated.");
+    }
+}
+
+#[test]
+fn client_bootstrap_timeout() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let nodes = create_connected_nodes(&network, min_section_size + 1);
+
+    let contact = nodes[0].handle.endpoint();
+    let config = Config::with_contacts(&[contact]);
+    let mut clients = vec![TestClient::new(&network, Some(config), None)];
+
+    // Only poll the client, so the node never answers its `ClientIdentify`.
+    let _ = poll_all(&mut [], &mut clients);
+    expect_no_event!(clients[0]);
+
+    network.advance_time(Duration::from_secs(BOOTSTRAP_TIMEOUT_SECS - 1));
+    let _ = poll_all(&mut [], &mut clients);
+    expect_no_event!(clients[0]);
+
+    // After the timeout, the client drops the node. It has no other contacts, so it terminates.
+    network.advance_time(Duration::from_secs(1));
+    let _ = poll_all(&mut [], &mut clients);
+    expect_next_event!(clients[0], Event::Terminate);
+}
+
+#[test]
+fn ack_resend() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+
+    let dst = Authority::ClientManager(clients[0].name());
+    let data = gen_immutable_data(&mut rng, 1024);
+    unwrap!(clients[0].inner.send_put_request(dst, data, MessageId::new()));
+
+    // The nodes are never polled, so the request is never acknowledged. The client resends it via
+    // the next route after each timeout.
+    for _ in 1..min_section_size {
+        network.advance_time(Duration::from_secs(ACK_TIMEOUT_SECS));
+        let _ = poll_all(&mut [], &mut clients);
+        assert!(clients[0].inner.has_unacknowledged());
+    }
+
+    network.advance_time(Duration::from_secs(ACK_TIMEOUT_SECS - 1));
+    let _ = poll_all(&mut [], &mut clients);
+    assert!(clients[0].inner.has_unacknowledged());
+
+    // After the timeout on the last route, the client gives up.
+    network.advance_time(Duration::from_secs(1));
+    let _ = poll_all(&mut [], &mut clients);
+    assert!(!clients[0].inner.has_unacknowledged());
+}
+
+#[test]
+fn candidate_expiry() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut nodes = create_connected_nodes(&network, min_section_size);
+    let config = Config::with_contacts(&[nodes[0].handle.endpoint()]);
+
+    // The section accepts a candidate, which then stalls and never proves its resources.
+    nodes.push(TestNode::builder(&network).config(config.clone()).create());
+    poll_until_relocated(&mut nodes);
+    let _stalled_candidate = unwrap!(nodes.pop());
+
+    // While the section is still handling the stalled candidate, another one is refused.
+    nodes.push(TestNode::builder(&network).config(config.clone()).create());
+    let _ = poll_all(&mut nodes, &mut []);
+    assert!(!unwrap!(nodes.last()).inner.is_node());
+    let _ = nodes.pop();
+    let _ = poll_all(&mut nodes, &mut []);
+
+    // Once the stalled candidate has expired, the section accepts a new one.
+    network.advance_time(Duration::from_secs(RESOURCE_PROOF_DURATION_SECS +
+                                             ACCUMULATION_TIMEOUT_SECS +
+                                             1));
+    let _ = poll_all(&mut nodes, &mut []);
+    nodes.push(TestNode::builder(&network).config(config).create());
+    poll_and_resend(&mut nodes, &mut []);
+    let new_index = nodes.len() - 1;
+    expect_any_event!(nodes[new_index], Event::Connected);
+}
+
+#[test]
+fn resource_proof_deadline() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut nodes = create_connected_nodes(&network, min_section_size);
+    let config = Config::with_contacts(&[nodes[0].handle.endpoint()]);
+
+    // The new node is relocated, but the section is never polled again, so it is never approved.
+    nodes.push(TestNode::builder(&network).config(config).create());
+    poll_until_relocated(&mut nodes);
+    let mut new_node = unwrap!(nodes.pop());
+
+    network.advance_time(Duration::from_secs(APPROVAL_TIMEOUT_SECS - 1));
+    let _ = new_node.poll();
+    while let Ok(event) = new_node.inner.try_next_ev() {
+        if let Event::Terminate = event {
+            panic!("The new node terminated before its approval deadline.");
+        }
+    }
+
+    // After the deadline, the node gives up.
+    network.advance_time(Duration::from_secs(1));
+    let _ = new_node.poll();
+    expect_any_event!(new_node, Event::Terminate);
+}
@@ -18,8 +18,8 @@
 use itertools::Itertools;
 use rand::Rng;
 use routing::{Authority, Cache, Client, Data, DataIdentifier, Event, EventStream, FullId,
-              ImmutableData, Node, NullCache, Prefix, Request, Response, RoutingTable, XorName,
-              Xorable, verify_network_invariant};
+              ImmutableData, Node, NullCache, Prefix, RelocationStrategy, Request, Response,
+              RoutingTable, XorName, Xorable, verify_network_invariant};
 use routing::mock_crust::{self, Config, Endpoint, Network, ServiceHandle};
 use std::{cmp, thread};
 use std::cell::RefCell;
@@ -122,6 +122,8 @@ impl TestNode {
             config: None,
             endpoint: None,
             cache: Box::new(NullCache),
+            encrypt_links: false,
+            relocation_strategy: None,
         }
     }
 
@@ -129,14 +131,23 @@ impl TestNode {
                first_node: bool,
                config: Option<Config>,
                endpoint: Option<Endpoint>,
-               cache: Box<Cache>)
+               cache: Box<Cache>,
+               encrypt_links: bool,
+               relocation_strategy: Option<Box<RelocationStrategy>>)
                -> Self {
         let handle = network.new_service_handle(config, endpoint);
         let node = mock_crust::make_current(&handle, || {
-            unwrap!(Node::builder()
-                        .cache(cache)
-                        .first(first_node)
-                        .create(network.min_section_size()))
+            let builder = Node::builder().cache(cache).first(first_node);
+            let builder = if encrypt_links {
+                builder.encrypt_links()
+            } else {
+                builder
+            };
+            let builder = match relocation_strategy {
+                Some(strategy) => builder.relocation_strategy(strategy),
+                None => builder,
+            };
+            unwrap!(builder.create(network.min_section_size()))
         });
 
         TestNode {
@@ -171,6 +182,8 @@ pub struct TestNodeBuilder<'a> {
     config: Option<Config>,
     endpoint: Option<Endpoint>,
     cache: Box<Cache>,
+    encrypt_links: bool,
+    relocation_strategy: Option<Box<RelocationStrategy>>,
 }
 
 impl<'a> TestNodeBuilder<'a> {
@@ -199,12 +212,24 @@ impl<'a> TestNodeBuilder<'a> {
         self
     }
 
+    pub fn encrypt_links(mut self, encrypt_links: bool) -> Self {
+        self.encrypt_links = encrypt_links;
+        self
+    }
+
+    pub fn relocation_strategy(mut self, strategy: Box<RelocationStrategy>) -> Self {
+        self.relocation_strategy = Some(strategy);
+        self
+    }
+
     pub fn create(self) -> TestNode {
         TestNode::new(self.network,
                       self.first_node,
                       self.config,
                       self.endpoint,
-                      self.cache)
+                      self.cache,
+                      self.encrypt_links,
+                      self.relocation_strategy)
     }
 }
 
@@ -219,9 +244,28 @@ pub struct TestClient {
 
 impl TestClient {
     pub fn new(network: &Network, config: Option<Config>, endpoint: Option<Endpoint>) -> Self {
+        Self::create(network, config, endpoint, false)
+    }
+
+    /// Creates a client which verifies responses from group authorities.
+    pub fn verifying(network: &Network,
+                     config: Option<Config>,
+                     endpoint: Option<Endpoint>)
+                     -> Self {
+        Self::create(network, config, endpoint, true)
+    }
+
+    fn create(network: &Network,
+              config: Option<Config>,
+              endpoint: Option<Endpoint>,
+              verify_responses: bool)
+              -> Self {
         let full_id = FullId::new();
         let handle = network.new_service_handle(config, endpoint);
-        let client = mock_crust::make_current(&handle, || {
+        let client = mock_crust::make_current(&handle, || if verify_responses {
+            unwrap!(Client::with_response_verification(Some(full_id.clone()),
+                                                       network.min_section_size()))
+        } else {
             unwrap!(Client::new(Some(full_id.clone()), network.min_section_size()))
         });
 
@@ -342,6 +386,16 @@ pub fn create_connected_nodes(network: &Network, size: usize) -> Nodes {
 }
 
 pub fn create_connected_nodes_with_cache(network: &Network, size: usize, use_cache: bool) -> Nodes {
+    create_nodes(network, size, use_cache, false)
+}
+
+pub fn create_connected_nodes_with_encrypted_links(network: &Network, size: usize) -> Nodes {
+    create_nodes(network, size, false, true)
+}
+
+// The nodes created here don't relocate themselves on churn, so that the network keeps the
+// membership the tests ask for.
+fn create_nodes(network: &Network, size: usize, use_cache: bool, encrypt_links: bool) -> Nodes {
     let mut nodes = Vec::new();
 
     // Create the seed node.
@@ -349,8 +403,10 @@ pub fn create_connected_nodes_with_cache(network: &Network, size: usize, use_cac
                    .first()
                    .endpoint(Endpoint(0))
                    .cache(use_cache)
+                   .encrypt_links(encrypt_links)
                    .create());
     nodes[0].poll();
+    nodes[0].inner.set_age_relocation(false);
 
     let config = Config::with_contacts(&[nodes[0].handle.endpoint()]);
 
@@ -360,8 +416,10 @@ pub fn create_connected_nodes_with_cache(network: &Network, size: usize, use_cac
                        .config(config.clone())
                        .endpoint(Endpoint(i))
                        .cache(use_cache)
+                       .encrypt_links(encrypt_links)
                        .create());
         poll_and_resend(&mut nodes, &mut []);
+        nodes[i].inner.set_age_relocation(false);
         verify_invariant_for_all_nodes(&mut nodes);
     }
 
@@ -377,6 +435,7 @@ pub fn create_connected_nodes_with_cache(network: &Network, size: usize, use_cac
                 Event::NodeAdded(..) => node_added_count += 1,
                 Event::NodeLost(..) |
                 Event::SectionSplit(..) |
+                Event::RemoteSectionsChanged { .. } |
                 Event::RestartRequired |
                 Event::Tick => (),
                 event => panic!("Got unexpected event: {:?}", event),
@@ -508,7 +567,8 @@ pub fn add_connected_nodes_until_split(network: &Network,
                 Event::NodeAdded(..) |
                 Event::NodeLost(..) |
                 Event::Tick |
-                Event::SectionSplit(..) => (),
+                Event::SectionSplit(..) |
+                Event::RemoteSectionsChanged { .. } => (),
                 event => panic!("Got unexpected event: {:?}", event),
             }
         }
@@ -619,6 +679,7 @@ fn prefixes<T: Rng>(prefix_lengths: &[usize], rng: &mut T) -> Vec<Prefix<XorName
     prefixes
 }
 
+// Like the ones from `create_nodes`, the added node doesn't relocate itself on churn.
 fn add_node_to_section<T: Rng>(network: &Network,
                                nodes: &mut Vec<TestNode>,
                                prefix: &Prefix<XorName>,
@@ -642,6 +703,7 @@ fn add_node_to_section<T: Rng>(network: &Network,
                    .cache(use_cache)
                    .create());
     poll_and_resend(nodes, &mut []);
+    unwrap!(nodes.last_mut()).inner.set_age_relocation(false);
     expect_any_event!(unwrap!(nodes.last_mut()), Event::Connected);
     assert!(prefix.matches(nodes[nodes.len() - 1].routing_table().our_name()));
 }
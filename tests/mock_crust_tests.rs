@@ -89,7 +89,7 @@ macro_rules! expect_no_event {
     ($node:expr) => {{
         match $node.inner.try_next_ev() {
             Ok(Event::Tick) => (),
-            Err(mpsc::TryRecvError::Empty) => (),
+            Err(::std::sync::mpsc::TryRecvError::Empty) => (),
             other => panic!("Expected no event at {}, got {:?}",
                 unwrap!($node.inner.name()),
                 other),